@@ -0,0 +1,18 @@
+fn main() {
+    // The C header only needs regenerating when the C API changes, so it's
+    // kept in the repository and only written with the `header` feature.
+    #[cfg(feature = "header")]
+    {
+        println!("cargo:rerun-if-changed=src/ffi.rs");
+        println!("cargo:rerun-if-changed=cbindgen.toml");
+        let dir = std::env::var("CARGO_MANIFEST_DIR").expect("Cargo sets the manifest dir");
+        let config = cbindgen::Config::from_file(format!("{dir}/cbindgen.toml"))
+            .expect("cbindgen.toml is valid");
+        cbindgen::Builder::new()
+            .with_config(config)
+            .with_src(format!("{dir}/src/ffi.rs"))
+            .generate()
+            .expect("The C API can be turned into a header")
+            .write_to_file(format!("{dir}/include/scanlan_sudoku.h"));
+    }
+}
@@ -0,0 +1,154 @@
+/* Generated by cbindgen from src/ffi.rs, do not edit. */
+
+#ifndef SCANLAN_SUDOKU_H
+#define SCANLAN_SUDOKU_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+/**
+ * The outcome of a call. Anything but `Ok` means the call failed, and
+ * `sudoku_last_error` explains why.
+ */
+typedef enum SudokuStatus {
+  SUDOKU_STATUS_OK = 0,
+  /**
+   * A pointer that's needed was null.
+   */
+  SUDOKU_STATUS_NULL_POINTER = 1,
+  /**
+   * A puzzle, or the name of a variant, constraint, difficulty or
+   * symmetry, couldn't be parsed.
+   */
+  SUDOKU_STATUS_INVALID_FORMAT = 2,
+  /**
+   * The clues of a puzzle break its rules.
+   */
+  SUDOKU_STATUS_BROKEN_RULES = 3,
+  /**
+   * The puzzle has no solution.
+   */
+  SUDOKU_STATUS_NO_SOLUTION = 4,
+  /**
+   * No puzzle could be generated, as the rules may not allow one.
+   */
+  SUDOKU_STATUS_TIMED_OUT = 5,
+  /**
+   * A bug in the library stopped the call. The handles passed in are left
+   * as they were and can still be freed.
+   */
+  SUDOKU_STATUS_INTERNAL = 6,
+} SudokuStatus;
+
+/**
+ * A puzzle, only ever handled through a pointer. Handles are made by
+ * `sudoku_grid_parse`, `sudoku_grid_solve` and `sudoku_generate`, and must be
+ * freed with `sudoku_grid_free`.
+ */
+typedef struct SudokuGrid SudokuGrid;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Returns an explanation of the last call on this thread that failed, or
+ * null if none has. The string belongs to the library and stays valid until
+ * the next failed call on the same thread.
+ */
+const char *sudoku_last_error(void);
+
+/**
+ * Parse a puzzle in line format into a new handle stored in `out`.
+ * `variant` and `constraints` are named as on the command line, and may be
+ * null for a standard puzzle with no extra constraints.
+ *
+ * # Safety
+ *
+ * `line` must be a valid C string, `variant` and `constraints` valid C
+ * strings or null, and `out` a valid pointer to write the handle to.
+ */
+enum SudokuStatus sudoku_grid_parse(const char *line,
+                                    const char *variant,
+                                    const char *constraints,
+                                    struct SudokuGrid **out);
+
+/**
+ * Solve the puzzle `grid` into a new handle stored in `out`, leaving `grid`
+ * as it was.
+ *
+ * # Safety
+ *
+ * `grid` must be a handle that hasn't been freed, and `out` a valid pointer
+ * to write the new handle to.
+ */
+enum SudokuStatus sudoku_grid_solve(const struct SudokuGrid *grid, struct SudokuGrid **out);
+
+/**
+ * Count the solutions of the puzzle `grid` into `out`, stopping once `limit`
+ * have been found. A limit of 2 is enough to tell if the solution is unique.
+ *
+ * # Safety
+ *
+ * `grid` must be a handle that hasn't been freed, and `out` a valid pointer.
+ */
+enum SudokuStatus sudoku_grid_count_solutions(const struct SudokuGrid *grid,
+                                              size_t limit,
+                                              size_t *out);
+
+/**
+ * Generate a puzzle of `difficulty` from `seed` into a new handle stored in
+ * `out`. The same arguments always give the same puzzle. `symmetry`,
+ * `variant` and `constraints` are named as on the command line, and may be
+ * null for their defaults.
+ *
+ * # Safety
+ *
+ * `difficulty` must be a valid C string, `symmetry`, `variant` and
+ * `constraints` valid C strings or null, and `out` a valid pointer to write
+ * the handle to.
+ */
+enum SudokuStatus sudoku_generate(const char *difficulty,
+                                  uint64_t seed,
+                                  const char *symmetry,
+                                  const char *variant,
+                                  const char *constraints,
+                                  struct SudokuGrid **out);
+
+/**
+ * Returns the value in the cell of `grid` at `row` and `col`, counting from
+ * 0, or 0 if it's empty, outside the grid or `grid` is null.
+ *
+ * # Safety
+ *
+ * `grid` must be a handle that hasn't been freed, or null.
+ */
+uint8_t sudoku_grid_get(const struct SudokuGrid *grid, size_t row, size_t col);
+
+/**
+ * Write `grid` in line format to `buf`, which holds `len` bytes, as a C
+ * string cut short to fit if needed. Like `snprintf`, returns the length of
+ * the whole line, not counting the terminating null, so a `buf` that's too
+ * small can be detected and `buf` can be null to find the size needed.
+ *
+ * # Safety
+ *
+ * `grid` must be a handle that hasn't been freed, and `buf` valid for
+ * writing `len` bytes, or null with a `len` of 0.
+ */
+size_t sudoku_grid_to_line(const struct SudokuGrid *grid, char *buf, size_t len);
+
+/**
+ * Free a handle. Does nothing if `grid` is null.
+ *
+ * # Safety
+ *
+ * `grid` must be a handle that hasn't already been freed, or null.
+ */
+void sudoku_grid_free(struct SudokuGrid *grid);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* SCANLAN_SUDOKU_H */
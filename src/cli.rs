@@ -0,0 +1,547 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Read, Write},
+    path::Path,
+    time::Instant,
+};
+
+use crate::{
+    logic::{
+        generate_batch, parse_parts, parse_puzzle, solve_batch, BatchOptions, BatchStatus, Coord,
+        GenerateOptions, Grid, GridError, SIZE,
+    },
+    render::{
+        book, grid_page, rows_page, to_html, to_pdf, to_png, to_svg, Highlights, RenderOptions,
+    },
+    server::Server,
+};
+
+pub const USAGE: &str = "Usage: scanlan_sudoku [COMMAND]
+
+Starts the interactive game if no command is given.
+
+Commands:
+  solve <INPUT> [-v VARIANT] [-t CONSTRAINTS] [-o OUTPUT] [-j THREADS]
+        [-c COUNT_LIMIT]
+        Solve every puzzle in INPUT (\"-\" for stdin) in parallel. Puzzles are
+        read one per line in line format, e.g. \"4.....8.5.3.....\", and any
+        text after the first space or comma on a line is ignored. Jigsaw
+        puzzles follow their cells with \":\" and a letter for each cell
+        naming its region. Killer puzzles then add \";\", a letter for each
+        cell naming its cage (\".\" for none), \";\" and the cage sums
+        separated by \"/\", in order of each cage's first cell. Kropki
+        puzzles end with \"=\" and a character for each pair of touching
+        cells, those side by side then those one above the other, row by row:
+        \"o\" for a white dot, \"*\" for a black dot or \".\" for none,
+        followed by \"!\" if cells without a dot are neither consecutive nor
+        one double the other. Any puzzle can end with \"+\" and extra
+        constraints separated by \"/\", with cells written as their row and
+        column: \"t\" and the cells from the bulb of a thermometer (e.g.
+        \"t112233\"), \"a\", the circle and the cells along an arrow (e.g.
+        \"a5556\"), \"r\" or \"c\", the row or column, \"=\" and its
+        sandwich sum (e.g. \"r1=15\"), \"g\", a cell and the smaller
+        neighbour a greater-than sign points to (e.g. \"g1112\"), or \"e\" or
+        \"o\" and the even or odd cells (e.g. \"e1199\"). Results are written
+        to OUTPUT (stdout by default) in input order as tab-separated lines:
+        index, status, solution, solution count, time in microseconds.
+        VARIANT is the rule set the puzzles follow: standard (the default),
+        diagonal, hyper, jigsaw, killer or kropki. CONSTRAINTS are extra rules
+        switched on for any variant, separated by commas: anti-knight, where
+        cells a knight's move apart can't repeat a value, anti-king, where
+        diagonally touching cells can't, and non-consecutive, where orthogonally
+        touching cells can't hold consecutive values. THREADS defaults to the
+        number of cores, and COUNT_LIMIT (the number of solutions to stop
+        counting at) to 2.
+  generate -n COUNT [-d DIFFICULTY] [-y SYMMETRY] [-v VARIANT]
+           [-t CONSTRAINTS] [-s SEED] [-j THREADS] [-o OUTPUT]
+        Generate COUNT distinct puzzles in parallel. DIFFICULTY is easy,
+        medium (the default), hard or minimal, where minimal puzzles have no
+        clue that could be removed without losing uniqueness. SYMMETRY is the
+        pattern the clues follow: none (the default), rotational,
+        rotational-90, diagonal, horizontal, vertical or dihedral. VARIANT is
+        standard (the default), diagonal, hyper, jigsaw, which gives each
+        puzzle a random layout, killer, where the difficulty sets how big the
+        cages can be, or kropki, which shows every dot of the solution and
+        uses the negative constraint. CONSTRAINTS are as for solve. Giving a
+        SEED makes the output repeatable. Each puzzle is generated again until
+        it has a unique solution and is rated at DIFFICULTY (any rating for
+        minimal), and the command fails if none is found in 500 tries.
+        Minimal puzzles are the slowest to make, and the work is shared
+        between the THREADS. Puzzles are written to OUTPUT (stdout by
+        default) as tab-separated lines: the puzzle in line format, its rated
+        difficulty, its rating score, its number of clues and whether it's
+        minimal.
+  export <INPUT> [-v VARIANT] [-t CONSTRAINTS] [-o OUTPUT] [-f FORMAT]
+         [-p PER_PAGE] [--candidates] [--no-answers]
+        Lay out every puzzle in INPUT, read as for solve, on A4 pages for
+        printing, PER_PAGE to a page (4 by default), followed by pages of
+        their solutions as an answer key unless --no-answers is given. Clues
+        are drawn in bold and the values of solutions in blue, with
+        --candidates adding the candidates of each empty cell as pencil
+        marks. FORMAT is svg, pdf or html, and defaults to the extension of
+        OUTPUT, or svg. PDF is written to OUTPUT (stdout by default) as one
+        document, while SVG has one page to a file, so OUTPUT is needed for
+        more than one page and each is numbered, e.g. \"book-1.svg\". HTML
+        is a single page where every puzzle can be played offline in a
+        browser, with pencil marks, values repeated in a region shown as
+        they're entered and a hash of each unique solution, unless
+        --no-answers is given, to check a finished grid against. VARIANT and
+        CONSTRAINTS are as for solve.
+  render <INPUT> [-v VARIANT] [-t CONSTRAINTS] [-o OUTPUT] [-s CELL_SIZE]
+         [-c CELLS] [--candidates]
+        Draw every puzzle in INPUT, read as for solve, as a PNG image with
+        cells CELL_SIZE pixels wide (48 by default), along with its cages,
+        dots, signs, thermometers, arrows and sandwich sums. CELLS are
+        highlighted, written as row and column pairs (e.g. \"1155\"), and
+        clues that break the rules are drawn in red rather than rejected.
+        --candidates adds the candidates of each empty cell as pencil marks.
+        A single image is written to OUTPUT (stdout by default), and several
+        are numbered, e.g. \"grid-1.png\", so need OUTPUT. VARIANT and
+        CONSTRAINTS are as for solve.
+  serve [-p PORT]
+        Answer requests with JSON over HTTP on localhost, port PORT (8080 by
+        default). POST /solve, /rate and /validate take a body such as
+        {\"puzzle\": \"4.....8.5.3...\"} and return its solution and number
+        of solutions (up to 2), its rating, or the values that break the
+        rules. GET /generate returns a new puzzle, taking difficulty,
+        symmetry and seed as query parameters, e.g.
+        /generate?difficulty=hard&seed=7. Every endpoint also takes variant
+        and constraints, as for solve. Errors are returned as {\"error\": ...}
+        with the kind of error where there is one.
+  help  Show this message";
+
+/// Run the non-interactive command given by `args`, excluding the program
+/// name. Returns the exit code for the process.
+pub fn run_command(args: &[String]) -> i32 {
+    let result = match args.first().map(String::as_str) {
+        Some("solve") => solve(&args[1..]),
+        Some("generate") => generate(&args[1..]),
+        Some("export") => export(&args[1..]),
+        Some("render") => render(&args[1..]),
+        Some("serve") => serve(&args[1..]),
+        Some("help" | "-h" | "--help") | None => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        Some(cmd) => Err(format!("Unknown command `{cmd}`")),
+    };
+
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{e}\n\n{USAGE}");
+            1
+        }
+    }
+}
+
+/// Command to solve a file of puzzles in parallel.
+fn solve(args: &[String]) -> Result<(), String> {
+    let (positional, options) = parse_args(args, &["-v", "-t", "-o", "-j", "-c"], &[])?;
+    let [input] = positional.as_slice() else {
+        return Err(String::from("Expected exactly one input file"));
+    };
+
+    let defaults = BatchOptions::default();
+    let batch_options = BatchOptions {
+        variant: parse_named(&options, "-v", defaults.variant)?,
+        toggles: parse_named(&options, "-t", defaults.toggles)?,
+        threads: parse_option(&options, "-j", defaults.threads)?,
+        count_limit: parse_option(&options, "-c", defaults.count_limit)?,
+        ..defaults
+    };
+
+    let puzzles = read_puzzles(input)?;
+    let now = Instant::now();
+    let results = solve_batch(&puzzles, &batch_options);
+    let elapsed = now.elapsed();
+
+    let mut out = String::from("# index\tstatus\tsolution\tsolutions\ttime_us\n");
+    for r in results.iter() {
+        out.push_str(&format!("{r}\n"));
+    }
+    write_output(options.get("-o"), &out)?;
+
+    let solved = results
+        .iter()
+        .filter(|r| matches!(r.status, BatchStatus::Solved { .. }))
+        .count();
+    let unique = results
+        .iter()
+        .filter(|r| {
+            matches!(
+                r.status,
+                BatchStatus::Solved {
+                    solution_count: 1,
+                    ..
+                }
+            )
+        })
+        .count();
+    eprintln!(
+        "Solved {solved} of {} puzzle{} ({unique} with a unique solution) in {elapsed:?} using {} thread{}",
+        results.len(),
+        plural(results.len()),
+        batch_options.threads,
+        plural(batch_options.threads)
+    );
+
+    Ok(())
+}
+
+/// Command to generate a file of distinct puzzles in parallel.
+fn generate(args: &[String]) -> Result<(), String> {
+    let (positional, options) =
+        parse_args(args, &["-n", "-d", "-y", "-v", "-t", "-s", "-j", "-o"], &[])?;
+    if !positional.is_empty() {
+        return Err(format!("Unexpected argument `{}`", positional[0]));
+    }
+
+    let defaults = GenerateOptions::default();
+    let generate_options = GenerateOptions {
+        count: parse_option(&options, "-n", 0)?,
+        difficulty: parse_named(&options, "-d", defaults.difficulty)?,
+        symmetry: parse_named(&options, "-y", defaults.symmetry)?,
+        variant: parse_named(&options, "-v", defaults.variant)?,
+        toggles: parse_named(&options, "-t", defaults.toggles)?,
+        seed: options
+            .get("-s")
+            .map(|_| parse_option(&options, "-s", 0))
+            .transpose()?,
+        threads: parse_option(&options, "-j", defaults.threads)?,
+    };
+    if generate_options.count == 0 {
+        return Err(String::from(
+            "Option `-n` is needed to say how many puzzles to generate",
+        ));
+    }
+
+    let now = Instant::now();
+    let puzzles =
+        generate_batch(&generate_options).map_err(|e| e.to_string().trim_end().to_string())?;
+    let elapsed = now.elapsed();
+
+    let mut out = String::from("# puzzle\trating\tscore\tclues\tminimal\n");
+    for p in puzzles.iter() {
+        out.push_str(&format!("{p}\n"));
+    }
+    write_output(options.get("-o"), &out)?;
+
+    eprintln!(
+        "Generated {} {} puzzle{} in {elapsed:?} using {} thread{}",
+        puzzles.len(),
+        generate_options.difficulty,
+        plural(puzzles.len()),
+        generate_options.threads,
+        plural(generate_options.threads)
+    );
+
+    Ok(())
+}
+
+/// Command to lay out a file of puzzles on pages for printing, as SVG or PDF,
+/// followed by their answer key.
+fn export(args: &[String]) -> Result<(), String> {
+    let (positional, options) = parse_args(
+        args,
+        &["-v", "-t", "-o", "-f", "-p"],
+        &["--candidates", "--no-answers"],
+    )?;
+    let [input] = positional.as_slice() else {
+        return Err(String::from("Expected exactly one input file"));
+    };
+
+    let defaults = BatchOptions::default();
+    let batch_options = BatchOptions {
+        variant: parse_named(&options, "-v", defaults.variant)?,
+        toggles: parse_named(&options, "-t", defaults.toggles)?,
+        count_limit: 1,
+        ..defaults
+    };
+    let render_options = RenderOptions {
+        per_page: parse_option(&options, "-p", RenderOptions::default().per_page)?,
+        candidates: options.contains_key("--candidates"),
+    };
+    if render_options.per_page == 0 {
+        return Err(String::from("Option `-p` must be at least 1"));
+    }
+
+    let output = options.get("-o");
+    let format = match options.get("-f") {
+        Some(format) => format.as_str(),
+        None => output
+            .and_then(|path| Path::new(path).extension())
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("svg"),
+    };
+    if !["svg", "pdf", "html"].contains(&format) {
+        return Err(format!(
+            "Unknown format `{format}`, expected svg, pdf or html"
+        ));
+    }
+
+    let lines = read_puzzles(input)?;
+    let puzzles = lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            parse_puzzle(line, batch_options.variant, batch_options.toggles)
+                .map_err(|e| format!("Puzzle {}: {}", i + 1, e.to_string().trim_end()))
+        })
+        .collect::<Result<Vec<Grid>, String>>()?;
+    let answers = if options.contains_key("--no-answers") {
+        Vec::new()
+    } else {
+        solve_batch(&lines, &batch_options)
+            .into_iter()
+            .map(|r| match r.status {
+                BatchStatus::Solved { solution, .. } => Ok(*solution),
+                BatchStatus::Unsolvable(e) | BatchStatus::Invalid(e) => Err(format!(
+                    "Puzzle {} can't be solved for the answer key: {}",
+                    r.index + 1,
+                    e.to_string().trim_end()
+                )),
+            })
+            .collect::<Result<Vec<Grid>, String>>()?
+    };
+
+    if format == "html" {
+        write_output(output, to_html(&puzzles, &answers))?;
+        eprintln!("Exported {} puzzles to a playable page", puzzles.len());
+        return Ok(());
+    }
+
+    let pages = book(&puzzles, &answers, &render_options);
+    if format == "pdf" {
+        write_output(output, to_pdf(&pages))?;
+    } else {
+        // SVG has no pages, so each is written to its own file.
+        let files: Vec<String> = pages.iter().map(to_svg).collect();
+        write_numbered(output, "svg", &files)?;
+    }
+
+    eprintln!(
+        "Exported {} puzzles on {} pages",
+        puzzles.len(),
+        pages.len()
+    );
+
+    Ok(())
+}
+
+/// Command to draw each puzzle in a file as a PNG image.
+fn render(args: &[String]) -> Result<(), String> {
+    let (positional, options) =
+        parse_args(args, &["-v", "-t", "-o", "-s", "-c"], &["--candidates"])?;
+    let [input] = positional.as_slice() else {
+        return Err(String::from("Expected exactly one input file"));
+    };
+
+    let defaults = BatchOptions::default();
+    let variant = parse_named(&options, "-v", defaults.variant)?;
+    let toggles = parse_named(&options, "-t", defaults.toggles)?;
+    let cell_size: f32 = parse_option(&options, "-s", 48.0)?;
+    if !(8.0..=512.0).contains(&cell_size) {
+        return Err(String::from("Option `-s` must be between 8 and 512"));
+    }
+    let highlights = Highlights {
+        selected: match options.get("-c") {
+            Some(cells) => parse_cell_list(cells)?,
+            None => Vec::new(),
+        },
+        conflicts: true,
+    };
+    let candidates = options.contains_key("--candidates");
+
+    let images = read_puzzles(input)?
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            let (rows, rules) = parse_parts(line, variant, toggles)
+                .map_err(|e| format!("Puzzle {}: {}", i + 1, e.to_string().trim_end()))?;
+            // Puzzles whose clues break the rules are still drawn, to show
+            // where.
+            let page = match Grid::from_rows_with_rules(rows, rules.clone()) {
+                Ok(g) => grid_page(&g, cell_size, candidates, &highlights),
+                Err(e) => {
+                    eprintln!("Puzzle {}: {}", i + 1, e.to_string().trim_end());
+                    rows_page(&rules, &rows, cell_size, &highlights)
+                }
+            };
+            to_png(&page).map_err(|e| format!("Puzzle {}: {e}", i + 1))
+        })
+        .collect::<Result<Vec<Vec<u8>>, String>>()?;
+
+    write_numbered(options.get("-o"), "png", &images)?;
+    Ok(())
+}
+
+/// Command to answer requests to solve, rate, generate and validate puzzles
+/// over HTTP until stopped.
+fn serve(args: &[String]) -> Result<(), String> {
+    let (positional, options) = parse_args(args, &["-p"], &[])?;
+    if !positional.is_empty() {
+        return Err(format!("Unexpected argument `{}`", positional[0]));
+    }
+    let port: u16 = parse_option(&options, "-p", 8080)?;
+
+    let server = Server::bind(("127.0.0.1", port))
+        .map_err(|e| format!("Couldn't listen on port {port}: {e}"))?;
+    if let Ok(addr) = server.local_addr() {
+        eprintln!("Listening on http://{addr}");
+    }
+    server.run().map_err(|e| format!("Server stopped: {e}"))
+}
+
+/// Parse a list of cells written as their row and column, e.g. `1155` for
+/// the top-left and centre cells, as in the extra constraints of line format.
+fn parse_cell_list(s: &str) -> Result<Vec<Coord>, String> {
+    let digits: Vec<Option<usize>> = s
+        .chars()
+        .map(|c| match c.to_digit(36) {
+            Some(n) if (1..=SIZE as u32).contains(&n) => Some(n as usize - 1),
+            _ => None,
+        })
+        .collect();
+    if digits.len().is_multiple_of(2) {
+        if let Some(cells) = digits
+            .chunks(2)
+            .map(|pair| Some(Coord::from((pair[0]?, pair[1]?))))
+            .collect()
+        {
+            return Ok(cells);
+        }
+    }
+    Err(format!(
+        "Expected cells as row and column pairs, e.g. `1155`, got `{s}`"
+    ))
+}
+
+/// Write `files` to `path`, or stdout if no path is given, if there's just
+/// one. Otherwise `path` is needed, and each is written to it numbered from
+/// 1 with the extension `ext`, e.g. `book-1.svg`.
+fn write_numbered(
+    path: Option<&String>,
+    ext: &str,
+    files: &[impl AsRef<[u8]>],
+) -> Result<(), String> {
+    match (path, files) {
+        (_, [file]) => write_output(path, file),
+        (None, files) => Err(format!(
+            "Option `-o` is needed to write {} {ext} files",
+            files.len()
+        )),
+        (Some(path), files) => {
+            let stem = Path::new(path).with_extension("");
+            files.iter().enumerate().try_for_each(|(i, file)| {
+                let path = format!("{}-{}.{ext}", stem.display(), i + 1);
+                write_output(Some(&path), file)
+            })
+        }
+    }
+}
+
+/// Split `args` into positional arguments and the values of the options named
+/// in `allowed`, each of which must be followed by a value. The options named
+/// in `flags` take no value, and are given an empty one if present.
+fn parse_args(
+    args: &[String],
+    allowed: &[&str],
+    flags: &[&str],
+) -> Result<(Vec<String>, BTreeMap<String, String>), String> {
+    let mut positional = Vec::new();
+    let mut options = BTreeMap::new();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        if allowed.contains(&arg.as_str()) {
+            let val = args
+                .next()
+                .ok_or_else(|| format!("Option `{arg}` needs a value"))?;
+            options.insert(arg.clone(), val.clone());
+        } else if flags.contains(&arg.as_str()) {
+            options.insert(arg.clone(), String::new());
+        } else if arg.starts_with('-') && arg != "-" {
+            return Err(format!("Unknown option `{arg}`"));
+        } else {
+            positional.push(arg.clone());
+        }
+    }
+
+    Ok((positional, options))
+}
+
+/// Parse the value of option `name` as a number, or return `default` if it
+/// wasn't given.
+fn parse_option<T: std::str::FromStr>(
+    options: &BTreeMap<String, String>,
+    name: &str,
+    default: T,
+) -> Result<T, String> {
+    match options.get(name) {
+        Some(val) => val
+            .parse()
+            .map_err(|_| format!("Option `{name}` expects a number, got `{val}`")),
+        None => Ok(default),
+    }
+}
+
+/// Parse the value of option `name` as one of a set of names, such as a
+/// difficulty, or return `default` if it wasn't given.
+fn parse_named<T: std::str::FromStr<Err = GridError>>(
+    options: &BTreeMap<String, String>,
+    name: &str,
+    default: T,
+) -> Result<T, String> {
+    match options.get(name) {
+        Some(val) => val
+            .parse()
+            .map_err(|e: GridError| e.to_string().trim_end().to_string()),
+        None => Ok(default),
+    }
+}
+
+/// Read the puzzles from the file at `path`, or stdin if it's `-`. Blank lines
+/// and lines starting with `#` are skipped, and only the first field of each
+/// line is kept.
+pub(crate) fn read_puzzles(path: &str) -> Result<Vec<String>, String> {
+    let mut text = String::new();
+    if path == "-" {
+        io::stdin()
+            .read_to_string(&mut text)
+            .map_err(|e| format!("Couldn't read stdin: {e}"))?;
+    } else {
+        text = fs::read_to_string(path).map_err(|e| format!("Couldn't read `{path}`: {e}"))?;
+    }
+
+    Ok(text
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(|l| {
+            l.split([' ', '\t', ','])
+                .next()
+                .unwrap_or_default()
+                .to_string()
+        })
+        .collect())
+}
+
+/// Write `text` to the file at `path`, or stdout if no path is given.
+fn write_output(path: Option<&String>, text: impl AsRef<[u8]>) -> Result<(), String> {
+    match path {
+        Some(path) => fs::write(path, text).map_err(|e| format!("Couldn't write `{path}`: {e}")),
+        None => io::stdout()
+            .write_all(text.as_ref())
+            .map_err(|e| format!("Couldn't write to stdout: {e}")),
+    }
+}
+
+/// Returns the ending for a count of `n` things: "s" unless there's one.
+fn plural(n: usize) -> &'static str {
+    match n {
+        1 => "",
+        _ => "s",
+    }
+}
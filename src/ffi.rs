@@ -0,0 +1,317 @@
+use std::{
+    any::Any,
+    cell::RefCell,
+    ffi::{c_char, CStr, CString},
+    panic::{self, AssertUnwindSafe},
+    ptr,
+    str::FromStr,
+};
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::logic::{
+    count_solutions, generate_with_rng, parse_puzzle, solve_backtracking_heuristics, Difficulty,
+    ErrorKind, Grid, GridError, Symmetry, Toggles, Variant, SIZE,
+};
+
+thread_local! {
+    /// Explanation of the last call on this thread that failed.
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// A puzzle, only ever handled through a pointer. Handles are made by
+/// `sudoku_grid_parse`, `sudoku_grid_solve` and `sudoku_generate`, and must be
+/// freed with `sudoku_grid_free`.
+pub struct SudokuGrid(Grid);
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The outcome of a call. Anything but `Ok` means the call failed, and
+/// `sudoku_last_error` explains why.
+pub enum SudokuStatus {
+    Ok = 0,
+    /// A pointer that's needed was null.
+    NullPointer = 1,
+    /// A puzzle, or the name of a variant, constraint, difficulty or
+    /// symmetry, couldn't be parsed.
+    InvalidFormat = 2,
+    /// The clues of a puzzle break its rules.
+    BrokenRules = 3,
+    /// The puzzle has no solution.
+    NoSolution = 4,
+    /// No puzzle could be generated, as the rules may not allow one.
+    TimedOut = 5,
+    /// A bug in the library stopped the call. The handles passed in are left
+    /// as they were and can still be freed.
+    Internal = 6,
+}
+
+impl From<&GridError> for SudokuStatus {
+    fn from(e: &GridError) -> Self {
+        match e.kind {
+            ErrorKind::InvalidFormat => SudokuStatus::InvalidFormat,
+            ErrorKind::NoSolution => SudokuStatus::NoSolution,
+            ErrorKind::TimedOut => SudokuStatus::TimedOut,
+            _ => SudokuStatus::BrokenRules,
+        }
+    }
+}
+
+/// Returns an explanation of the last call on this thread that failed, or
+/// null if none has. The string belongs to the library and stays valid until
+/// the next failed call on the same thread.
+#[no_mangle]
+pub extern "C" fn sudoku_last_error() -> *const c_char {
+    catch(ptr::null(), || {
+        LAST_ERROR.with(|e| e.borrow().as_ref().map_or(ptr::null(), |e| e.as_ptr()))
+    })
+}
+
+/// Parse a puzzle in line format into a new handle stored in `out`.
+/// `variant` and `constraints` are named as on the command line, and may be
+/// null for a standard puzzle with no extra constraints.
+///
+/// # Safety
+///
+/// `line` must be a valid C string, `variant` and `constraints` valid C
+/// strings or null, and `out` a valid pointer to write the handle to.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_grid_parse(
+    line: *const c_char,
+    variant: *const c_char,
+    constraints: *const c_char,
+    out: *mut *mut SudokuGrid,
+) -> SudokuStatus {
+    catch(SudokuStatus::Internal, || {
+        if line.is_null() || out.is_null() {
+            return fail(SudokuStatus::NullPointer, "`line` and `out` can't be null");
+        }
+        let result = (|| {
+            let variant = named(variant, Variant::default())?;
+            let toggles = named(constraints, Toggles::default())?;
+            parse_puzzle(&string(line)?, variant, toggles)
+        })();
+
+        store(result, out)
+    })
+}
+
+/// Solve the puzzle `grid` into a new handle stored in `out`, leaving `grid`
+/// as it was.
+///
+/// # Safety
+///
+/// `grid` must be a handle that hasn't been freed, and `out` a valid pointer
+/// to write the new handle to.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_grid_solve(
+    grid: *const SudokuGrid,
+    out: *mut *mut SudokuGrid,
+) -> SudokuStatus {
+    catch(SudokuStatus::Internal, || {
+        if grid.is_null() || out.is_null() {
+            return fail(SudokuStatus::NullPointer, "`grid` and `out` can't be null");
+        }
+
+        store(solve_backtracking_heuristics((*grid).0.clone()), out)
+    })
+}
+
+/// Count the solutions of the puzzle `grid` into `out`, stopping once `limit`
+/// have been found. A limit of 2 is enough to tell if the solution is unique.
+///
+/// # Safety
+///
+/// `grid` must be a handle that hasn't been freed, and `out` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_grid_count_solutions(
+    grid: *const SudokuGrid,
+    limit: usize,
+    out: *mut usize,
+) -> SudokuStatus {
+    catch(SudokuStatus::Internal, || {
+        if grid.is_null() || out.is_null() {
+            return fail(SudokuStatus::NullPointer, "`grid` and `out` can't be null");
+        }
+
+        *out = count_solutions((*grid).0.clone(), limit);
+        SudokuStatus::Ok
+    })
+}
+
+/// Generate a puzzle of `difficulty` from `seed` into a new handle stored in
+/// `out`. The same arguments always give the same puzzle. `symmetry`,
+/// `variant` and `constraints` are named as on the command line, and may be
+/// null for their defaults.
+///
+/// # Safety
+///
+/// `difficulty` must be a valid C string, `symmetry`, `variant` and
+/// `constraints` valid C strings or null, and `out` a valid pointer to write
+/// the handle to.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_generate(
+    difficulty: *const c_char,
+    seed: u64,
+    symmetry: *const c_char,
+    variant: *const c_char,
+    constraints: *const c_char,
+    out: *mut *mut SudokuGrid,
+) -> SudokuStatus {
+    catch(SudokuStatus::Internal, || {
+        if difficulty.is_null() || out.is_null() {
+            return fail(
+                SudokuStatus::NullPointer,
+                "`difficulty` and `out` can't be null",
+            );
+        }
+        let result = (|| {
+            generate_with_rng(
+                Difficulty::from_str(&string(difficulty)?)?,
+                named(symmetry, Symmetry::default())?,
+                named(variant, Variant::default())?,
+                named(constraints, Toggles::default())?,
+                &mut StdRng::seed_from_u64(seed),
+            )
+        })();
+
+        store(result, out)
+    })
+}
+
+/// Returns the value in the cell of `grid` at `row` and `col`, counting from
+/// 0, or 0 if it's empty, outside the grid or `grid` is null.
+///
+/// # Safety
+///
+/// `grid` must be a handle that hasn't been freed, or null.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_grid_get(grid: *const SudokuGrid, row: usize, col: usize) -> u8 {
+    catch(0, || {
+        if grid.is_null() || row >= SIZE || col >= SIZE {
+            return 0;
+        }
+
+        (*grid).0.get_cell((row, col).into()).value().unwrap_or(0)
+    })
+}
+
+/// Write `grid` in line format to `buf`, which holds `len` bytes, as a C
+/// string cut short to fit if needed. Like `snprintf`, returns the length of
+/// the whole line, not counting the terminating null, so a `buf` that's too
+/// small can be detected and `buf` can be null to find the size needed.
+///
+/// # Safety
+///
+/// `grid` must be a handle that hasn't been freed, and `buf` valid for
+/// writing `len` bytes, or null with a `len` of 0.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_grid_to_line(
+    grid: *const SudokuGrid,
+    buf: *mut c_char,
+    len: usize,
+) -> usize {
+    catch(0, || {
+        if grid.is_null() {
+            return 0;
+        }
+        let line = (*grid).0.to_line();
+
+        if !buf.is_null() && len > 0 {
+            let n = line.len().min(len - 1);
+            ptr::copy_nonoverlapping(line.as_ptr(), buf.cast(), n);
+            *buf.add(n) = 0;
+        }
+        line.len()
+    })
+}
+
+/// Free a handle. Does nothing if `grid` is null.
+///
+/// # Safety
+///
+/// `grid` must be a handle that hasn't already been freed, or null.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_grid_free(grid: *mut SudokuGrid) {
+    catch((), || {
+        if !grid.is_null() {
+            drop(Box::from_raw(grid));
+        }
+    })
+}
+
+/// Run `f`, the body of a function called from C, returning `on_panic` with
+/// the panic recorded as the last error if it panics, as a panic can't unwind
+/// into C and would abort the whole program.
+fn catch<T>(on_panic: T, f: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        fail(
+            SudokuStatus::Internal,
+            &format!("Internal error: {}", panic_message(payload.as_ref())),
+        );
+        on_panic
+    })
+}
+
+/// Returns the message a panic was started with.
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => message,
+        None => payload.downcast_ref::<String>().map_or("unknown", |s| s),
+    }
+}
+
+/// Record `message` as the last error and return `status`.
+fn fail(status: SudokuStatus, message: &str) -> SudokuStatus {
+    // Messages never contain a null, as they're built from UTF-8 input that
+    // went through a C string.
+    let message = CString::new(message).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(message));
+    status
+}
+
+/// Store the grid in `result` as a new handle in `out`, or record the error.
+unsafe fn store(result: Result<Grid, GridError>, out: *mut *mut SudokuGrid) -> SudokuStatus {
+    match result {
+        Ok(g) => {
+            *out = Box::into_raw(Box::new(SudokuGrid(g)));
+            SudokuStatus::Ok
+        }
+        Err(e) => fail(SudokuStatus::from(&e), e.to_string().trim_end()),
+    }
+}
+
+/// Returns the contents of the C string `s`, which must be UTF-8.
+unsafe fn string(s: *const c_char) -> Result<String, GridError> {
+    CStr::from_ptr(s)
+        .to_str()
+        .map(String::from)
+        .map_err(|_| GridError::invalid_format(String::from("Strings must be UTF-8")))
+}
+
+/// Parse the C string `s` as one of a set of names, or return `default` if
+/// it's null.
+unsafe fn named<T: FromStr<Err = GridError>>(s: *const c_char, default: T) -> Result<T, GridError> {
+    match s.is_null() {
+        true => Ok(default),
+        false => string(s)?.parse(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn panics_become_internal_errors() {
+        let status = catch(SudokuStatus::Internal, || panic!("Out of cheese"));
+        assert_eq!(status, SudokuStatus::Internal);
+        let message = unsafe { CStr::from_ptr(sudoku_last_error()) };
+        assert_eq!(message.to_str(), Ok("Internal error: Out of cheese"));
+
+        let n = catch(0, || panic!("{} cheeses", 3));
+        assert_eq!(n, 0);
+        let message = unsafe { CStr::from_ptr(sudoku_last_error()) };
+        assert_eq!(message.to_str(), Ok("Internal error: 3 cheeses"));
+    }
+}
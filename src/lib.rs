@@ -1,8 +1,23 @@
-#![feature(slice_flatten)]
+#[cfg(all(feature = "terminal", not(target_arch = "wasm32")))]
+pub mod cli;
+#[cfg(all(feature = "ffi", not(target_arch = "wasm32")))]
+pub mod ffi;
 pub mod logic;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "render")]
+pub mod render;
+#[cfg(all(feature = "server", not(target_arch = "wasm32")))]
+pub mod server;
+#[cfg(all(feature = "terminal", not(target_arch = "wasm32")))]
 pub mod ui;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 // pub mod puzzles;
 
+#[cfg(all(feature = "terminal", not(target_arch = "wasm32")))]
+pub use cli::run_command;
+#[cfg(all(feature = "terminal", not(target_arch = "wasm32")))]
 pub use ui::run;
 
 pub const ORDER: usize = 3;
@@ -0,0 +1,464 @@
+use std::{
+    collections::HashSet,
+    fmt,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::{
+    count_solutions, find_solutions,
+    generator::generate_with_rng,
+    grid::{ErrorKind, GridError},
+    is_minimal, parse_puzzle, rate, Difficulty, Grid, Rating, Symmetry, Toggles, Variant, SIZE,
+};
+
+/// Signature shared by the solvers that can be used for batch solving.
+pub type Solver = fn(Grid) -> Result<Grid, GridError>;
+
+#[derive(Debug, Clone, Copy)]
+/// Options for `solve_batch`. `threads` is the number of worker threads to
+/// use, and `count_limit` is the number of solutions to stop counting at.
+/// `solver` is only used when `count_limit` is 1 or less; otherwise the
+/// search that counts the solutions also finds the one reported.
+pub struct BatchOptions {
+    pub solver: Solver,
+    pub variant: Variant,
+    pub toggles: Toggles,
+    pub threads: usize,
+    pub count_limit: usize,
+}
+
+impl Default for BatchOptions {
+    /// Use the heuristic backtracking solver on all available cores, counting
+    /// up to two solutions, which is enough to tell if a puzzle is unique.
+    fn default() -> Self {
+        BatchOptions {
+            solver: super::solve_backtracking_heuristics,
+            variant: Variant::Standard,
+            toggles: Toggles::default(),
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            count_limit: 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// The outcome of solving a single puzzle in a batch.
+pub enum BatchStatus {
+    /// The puzzle was solved. `solution_count` is the number of solutions
+    /// found, up to the batch's count limit.
+    Solved {
+        solution: Box<Grid>,
+        solution_count: usize,
+    },
+    /// The puzzle was parsed but couldn't be solved.
+    Unsolvable(GridError),
+    /// The puzzle couldn't be parsed, or its clues were invalid.
+    Invalid(GridError),
+}
+
+#[derive(Debug, Clone)]
+/// Struct representing the result of solving the puzzle at position `index`
+/// of a batch, including how long it took.
+pub struct BatchResult {
+    pub index: usize,
+    pub status: BatchStatus,
+    pub time: Duration,
+}
+
+/// Formats the result as a tab-separated line: the puzzle's position (from
+/// 1), its status, the solution in line format, the solution count, and the
+/// time taken in microseconds.
+impl fmt::Display for BatchResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let micros = self.time.as_micros();
+        match &self.status {
+            BatchStatus::Solved {
+                solution,
+                solution_count,
+            } => write!(
+                f,
+                "{}\tsolved\t{}\t{solution_count}\t{micros}",
+                self.index + 1,
+                solution.to_line()
+            ),
+            BatchStatus::Unsolvable(e) => write!(
+                f,
+                "{}\tunsolvable\t{}\t0\t{micros}",
+                self.index + 1,
+                e.to_string().trim_end()
+            ),
+            BatchStatus::Invalid(e) => write!(
+                f,
+                "{}\tinvalid\t{}\t0\t{micros}",
+                self.index + 1,
+                e.to_string().trim_end()
+            ),
+        }
+    }
+}
+
+/// Solve a single puzzle given in line format.
+fn solve_one(index: usize, puzzle: &str, options: &BatchOptions) -> BatchResult {
+    let now = Instant::now();
+    let status = match parse_puzzle(puzzle, options.variant, options.toggles) {
+        Err(e) => BatchStatus::Invalid(e),
+        Ok(g) => {
+            // Counting solutions already finds one, so the puzzle is only
+            // searched once either way.
+            let solved = if options.count_limit > 1 {
+                find_solutions(g, options.count_limit, usize::MAX)
+            } else {
+                (options.solver)(g).map(|solution| (solution, 1))
+            };
+            match solved {
+                Err(e) => BatchStatus::Unsolvable(e),
+                Ok((solution, solution_count)) => BatchStatus::Solved {
+                    solution: Box::new(solution),
+                    solution_count,
+                },
+            }
+        }
+    };
+
+    BatchResult {
+        index,
+        status,
+        time: now.elapsed(),
+    }
+}
+
+/// Solve every puzzle in `puzzles`, given in line format, in parallel. The
+/// results are returned in the same order as the input.
+pub fn solve_batch<S: AsRef<str> + Sync>(
+    puzzles: &[S],
+    options: &BatchOptions,
+) -> Vec<BatchResult> {
+    run_parallel(puzzles.len(), options.threads, |index| {
+        solve_one(index, puzzles[index].as_ref(), options)
+    })
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Options for `generate_batch`. If `seed` is given, the same options always
+/// produce the same puzzles, whatever the number of `threads`.
+pub struct GenerateOptions {
+    pub difficulty: Difficulty,
+    pub symmetry: Symmetry,
+    pub variant: Variant,
+    pub toggles: Toggles,
+    pub count: usize,
+    pub threads: usize,
+    pub seed: Option<u64>,
+}
+
+impl Default for GenerateOptions {
+    fn default() -> Self {
+        GenerateOptions {
+            difficulty: Difficulty::Medium,
+            symmetry: Symmetry::None,
+            variant: Variant::Standard,
+            toggles: Toggles::default(),
+            count: 1,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            seed: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A puzzle produced by `generate_batch`, with its rating, number of clues,
+/// and whether it's minimal.
+pub struct GeneratedPuzzle {
+    pub puzzle: Box<Grid>,
+    pub rating: Rating,
+    pub clues: usize,
+    pub minimal: bool,
+}
+
+/// Formats the puzzle as a tab-separated line: the puzzle in line format, its
+/// rated difficulty, its rating score, its number of clues, and whether it's
+/// minimal.
+impl fmt::Display for GeneratedPuzzle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}\t{}\t{}\t{}\t{}",
+            self.puzzle.to_line(),
+            self.rating.difficulty,
+            self.rating.score,
+            self.clues,
+            if self.minimal { "minimal" } else { "-" }
+        )
+    }
+}
+
+/// Generate `options.count` distinct puzzles in parallel. Each puzzle is
+/// generated from its own seed, derived from `options.seed` and its position,
+/// and any duplicates are replaced with new puzzles. Returns the first error
+/// met if a puzzle can't be generated, such as when the rules have no
+/// solution, or an error of kind `TimedOut` if `DUPLICATE_ROUNDS` rounds in a
+/// row produce nothing but duplicates.
+pub fn generate_batch(options: &GenerateOptions) -> Result<Vec<GeneratedPuzzle>, GridError> {
+    let seed = options.seed.unwrap_or_else(|| rand::thread_rng().gen());
+    let mut seen = HashSet::new();
+    let mut puzzles = Vec::with_capacity(options.count);
+    let mut attempts = 0;
+    let mut duplicate_rounds = 0;
+
+    while puzzles.len() < options.count {
+        if duplicate_rounds == DUPLICATE_ROUNDS {
+            return Err(GridError::with_details(
+                ErrorKind::TimedOut,
+                format!(
+                    "Only found {} distinct puzzles of the {} asked for",
+                    puzzles.len(),
+                    options.count
+                ),
+            ));
+        }
+
+        let needed = options.count - puzzles.len();
+        let generated = run_parallel(needed, options.threads, |i| {
+            let mut rng = StdRng::seed_from_u64(seed.wrapping_add((attempts + i) as u64));
+            generate_one(options, &mut rng)
+        });
+        attempts += needed;
+
+        let found = puzzles.len();
+        for p in generated {
+            let p = p?;
+            if seen.insert(p.puzzle.to_line()) {
+                puzzles.push(p);
+            }
+        }
+        duplicate_rounds = if puzzles.len() == found {
+            duplicate_rounds + 1
+        } else {
+            0
+        };
+    }
+
+    Ok(puzzles)
+}
+
+/// Number of rounds in a row that `generate_batch` accepts producing only
+/// duplicates before deciding there are no more distinct puzzles to find.
+const DUPLICATE_ROUNDS: usize = 20;
+
+/// Number of puzzles generated for each place in a batch before giving up on
+/// finding one with a unique solution and the difficulty asked for.
+const GENERATE_ATTEMPTS: usize = 500;
+
+/// Generate a puzzle with `options`, trying again until one has a unique
+/// solution and is rated at `options.difficulty`. Minimal puzzles are kept
+/// whatever their rating. Returns an error of kind `TimedOut` if none is found
+/// within `GENERATE_ATTEMPTS` tries.
+fn generate_one<R: Rng>(
+    options: &GenerateOptions,
+    rng: &mut R,
+) -> Result<GeneratedPuzzle, GridError> {
+    // The rating, not the number of clues, decides the difficulty. Puzzles
+    // left with as many clues as a medium one rarely need more than singles,
+    // so medium puzzles are cut down as far as hard ones.
+    let removal = match options.difficulty {
+        Difficulty::Medium => Difficulty::Hard,
+        d => d,
+    };
+
+    for _ in 0..GENERATE_ATTEMPTS {
+        let puzzle = generate_with_rng(
+            removal,
+            options.symmetry,
+            options.variant,
+            options.toggles,
+            rng,
+        )?;
+        if count_solutions(puzzle.clone(), 2) != 1 {
+            continue;
+        }
+        let rating = rate(&puzzle)?;
+        if options.difficulty != Difficulty::Minimal && rating.difficulty != options.difficulty {
+            continue;
+        }
+
+        return Ok(GeneratedPuzzle {
+            rating,
+            clues: SIZE.pow(2) - puzzle.empty_cell_count,
+            minimal: is_minimal(&puzzle),
+            puzzle: Box::new(puzzle),
+        });
+    }
+
+    Err(GridError::with_details(
+        ErrorKind::TimedOut,
+        format!(
+            "Couldn't generate a {} puzzle with a unique solution in {GENERATE_ATTEMPTS} attempts",
+            options.difficulty
+        ),
+    ))
+}
+
+/// Run `f` for every index below `count`, spread over `threads` worker
+/// threads, and return the results in index order.
+fn run_parallel<T, F>(count: usize, threads: usize, f: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(usize) -> T + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let threads = threads.clamp(1, count.max(1));
+
+    let mut results: Vec<(usize, T)> = thread::scope(|s| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                s.spawn(|| {
+                    let mut results = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        if index >= count {
+                            return results;
+                        }
+                        results.push((index, f(index)));
+                    }
+                })
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .flat_map(|w| w.join().expect("Batch worker thread panicked"))
+            .collect()
+    });
+
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, r)| r).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::Puzzle;
+
+    const PUZZLE: &str =
+        "530070000600195000098000060800060003400803001700020006060000280000419005000080079";
+    const SOLUTION: &str =
+        "534678912672195348198342567859761423426853791713924856961537284287419635345286179";
+
+    fn options(count_limit: usize) -> BatchOptions {
+        BatchOptions {
+            threads: 3,
+            count_limit,
+            ..BatchOptions::default()
+        }
+    }
+
+    #[test]
+    fn results_keep_the_input_order() {
+        let puzzles = [PUZZLE, "x", PUZZLE, &"0".repeat(81), PUZZLE];
+        for count_limit in [1, 2] {
+            let results = solve_batch(&puzzles, &options(count_limit));
+            let indices: Vec<_> = results.iter().map(|r| r.index).collect();
+            assert_eq!(indices, [0, 1, 2, 3, 4]);
+            for i in [0, 2, 4] {
+                match &results[i].status {
+                    BatchStatus::Solved {
+                        solution,
+                        solution_count,
+                    } => {
+                        assert_eq!(solution.to_line(), SOLUTION);
+                        assert_eq!(*solution_count, 1);
+                    }
+                    status => panic!("puzzle {i} wasn't solved: {status:?}"),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn counts_solutions_up_to_the_limit() {
+        let results = solve_batch(&["0".repeat(81)], &options(5));
+        match &results[0].status {
+            BatchStatus::Solved {
+                solution,
+                solution_count,
+            } => {
+                assert!(solution.is_solved());
+                assert_eq!(*solution_count, 5);
+            }
+            status => panic!("empty grid wasn't solved: {status:?}"),
+        }
+    }
+
+    fn generate_options(difficulty: Difficulty, count: usize, threads: usize) -> GenerateOptions {
+        GenerateOptions {
+            difficulty,
+            symmetry: Symmetry::Rotational180,
+            count,
+            threads,
+            seed: Some(7),
+            ..GenerateOptions::default()
+        }
+    }
+
+    fn lines(puzzles: &[GeneratedPuzzle]) -> Vec<String> {
+        puzzles.iter().map(|p| p.puzzle.to_line()).collect()
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_batch() {
+        let first = generate_batch(&generate_options(Difficulty::Easy, 4, 1)).unwrap();
+        let second = generate_batch(&generate_options(Difficulty::Easy, 4, 3)).unwrap();
+        assert_eq!(lines(&first), lines(&second));
+    }
+
+    #[test]
+    fn batches_have_no_duplicates() {
+        let puzzles = generate_batch(&generate_options(Difficulty::Easy, 8, 2)).unwrap();
+        let distinct: HashSet<_> = lines(&puzzles).into_iter().collect();
+        assert_eq!(puzzles.len(), 8);
+        assert_eq!(distinct.len(), 8);
+    }
+
+    #[test]
+    fn ratings_match_the_difficulty() {
+        for d in [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard] {
+            for p in generate_batch(&generate_options(d, 3, 2)).unwrap() {
+                assert_eq!(p.rating.difficulty, d, "{}", p.puzzle.to_line());
+                assert_eq!(count_solutions(*p.puzzle, 2), 1);
+            }
+        }
+    }
+
+    #[test]
+    fn reports_invalid_and_unsolvable_puzzles() {
+        // Two 5s in the first row.
+        let repeated = format!("55{}", &PUZZLE[2..]);
+        // Every cell has a candidate, but the first two cells of the top row
+        // can only hold a 9: 1-7 are in their row, and each has an 8 in its
+        // column.
+        let stuck = format!(
+            "001234567{z}800000000{z}{z}080000000{z}{z}{z}",
+            z = "0".repeat(9)
+        );
+        let results = solve_batch(&["", &repeated, &stuck], &options(2));
+
+        for result in &results[..2] {
+            assert!(
+                matches!(result.status, BatchStatus::Invalid(_)),
+                "{:?}",
+                result.status
+            );
+        }
+        assert!(
+            matches!(results[2].status, BatchStatus::Unsolvable(_)),
+            "{:?}",
+            results[2].status
+        );
+        assert!(results[1].to_string().starts_with("2\tinvalid\t"));
+        assert!(results[2].to_string().starts_with("3\tunsolvable\t"));
+    }
+}
@@ -0,0 +1,193 @@
+use rand::{seq::SliceRandom, Rng};
+
+use super::{grid::GridError, layout::neighbours, Coord, GridArray, SIZE};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Struct representing a cage in Killer Sudoku: a connected group of cells
+/// whose values add up to `sum`, without any value repeated. The cells are
+/// kept in row order, so the first is the one the sum is shown on.
+pub struct Cage {
+    pub cells: Vec<Coord>,
+    pub sum: u32,
+}
+
+impl Cage {
+    /// Construct a cage from the coordinates of its cells and their sum.
+    pub fn new(cells: impl IntoIterator<Item = Coord>, sum: u32) -> Self {
+        let mut cells: Vec<Coord> = cells.into_iter().collect();
+        cells.sort();
+        cells.dedup();
+        Cage { cells, sum }
+    }
+
+    /// Returns the sum of the values already placed in the cage in `rows`,
+    /// and the cells that are still empty.
+    pub fn progress(&self, rows: &GridArray) -> (u32, Vec<Coord>) {
+        progress(rows, &self.cells)
+    }
+}
+
+/// Returns the sum of the values already placed in `cells` in `rows`, and the
+/// cells that are still empty.
+pub(super) fn progress(rows: &GridArray, cells: &[Coord]) -> (u32, Vec<Coord>) {
+    let mut placed = 0;
+    let mut empty = Vec::new();
+    for &cell in cells.iter() {
+        match rows[cell.row][cell.col].value() {
+            Some(n) => placed += n as u32,
+            None => empty.push(cell),
+        }
+    }
+    (placed, empty)
+}
+
+/// Split the solved grid `solution` into random cages of at most `max_size`
+/// cells, using `rng` as the only source of randomness. Each cage grows from
+/// a random cell into neighbouring cells until it's big enough or every
+/// neighbour would repeat a value. The cages are returned in order of their
+/// first cell.
+pub fn random_cages<R: Rng + ?Sized>(
+    solution: &GridArray,
+    max_size: usize,
+    rng: &mut R,
+) -> Vec<Cage> {
+    let mut caged = [[false; SIZE]; SIZE];
+    let mut starts: Vec<Coord> = (0..SIZE.pow(2))
+        .map(|i| Coord::from((i / SIZE, i % SIZE)))
+        .collect();
+    starts.shuffle(rng);
+
+    let value = |c: Coord| solution[c.row][c.col].value().unwrap_or(0);
+    let mut cages = Vec::new();
+    for start in starts.into_iter() {
+        if caged[start.row][start.col] {
+            continue;
+        }
+
+        let size = rng.gen_range(1..=max_size.max(1));
+        let mut cells = vec![start];
+        caged[start.row][start.col] = true;
+        while cells.len() < size {
+            let options: Vec<Coord> = cells
+                .iter()
+                .flat_map(|&c| neighbours(c))
+                .filter(|&n| !caged[n.row][n.col] && cells.iter().all(|&c| value(c) != value(n)))
+                .collect();
+            let Some(&next) = options.choose(rng) else {
+                break;
+            };
+            caged[next.row][next.col] = true;
+            cells.push(next);
+        }
+
+        let sum = cells.iter().map(|&c| value(c) as u32).sum();
+        cages.push(Cage::new(cells, sum));
+    }
+
+    cages.sort_by_key(|cage| cage.cells[0]);
+    cages
+}
+
+/// Parse cages from `map`, `SIZE` squared characters in row order ignoring
+/// whitespace, and `sums`, a list separated by `/`. A cage is a connected
+/// group of cells sharing a character, so the same character can be reused
+/// for cages that don't touch, and the sums are given in order of each cage's
+/// first cell. Cells marked `.` aren't in a cage.
+pub fn parse_cages(map: &str, sums: &str) -> Result<Vec<Cage>, GridError> {
+    let chars: Vec<char> = map.chars().filter(|c| !c.is_whitespace()).collect();
+    if chars.len() != SIZE.pow(2) {
+        return Err(GridError::invalid_format(format!(
+            "Expected {} cage letters but found {}",
+            SIZE.pow(2),
+            chars.len()
+        )));
+    }
+    let label = |c: Coord| chars[c.row * SIZE + c.col];
+
+    let mut caged = [[false; SIZE]; SIZE];
+    let mut groups: Vec<Vec<Coord>> = Vec::new();
+    for start in (0..SIZE.pow(2)).map(|i| Coord::from((i / SIZE, i % SIZE))) {
+        if caged[start.row][start.col] || label(start) == NO_CAGE {
+            continue;
+        }
+
+        caged[start.row][start.col] = true;
+        let mut cells = vec![start];
+        let mut stack = vec![start];
+        while let Some(cell) = stack.pop() {
+            for n in neighbours(cell) {
+                if !caged[n.row][n.col] && label(n) == label(start) {
+                    caged[n.row][n.col] = true;
+                    cells.push(n);
+                    stack.push(n);
+                }
+            }
+        }
+        groups.push(cells);
+    }
+
+    let sums: Vec<&str> = match sums.trim() {
+        "" => Vec::new(),
+        sums => sums.split(SUM_SEPARATOR).map(str::trim).collect(),
+    };
+    if sums.len() != groups.len() {
+        return Err(GridError::invalid_format(format!(
+            "Found {} cages but {} sums",
+            groups.len(),
+            sums.len()
+        )));
+    }
+
+    groups
+        .into_iter()
+        .zip(sums)
+        .map(|(cells, sum)| {
+            let first = Coord::from((cells[0].row + 1, cells[0].col + 1));
+            match sum.parse::<u32>() {
+                Ok(sum) if cells.len() <= SIZE => Ok(Cage::new(cells, sum)),
+                Ok(_) => Err(GridError::invalid_format(format!(
+                    "The cage at {first} has more than {SIZE} cells"
+                ))),
+                Err(_) => Err(GridError::invalid_format(format!(
+                    "Unexpected sum `{sum}` for the cage at {first}"
+                ))),
+            }
+        })
+        .collect()
+}
+
+/// Character marking a cell that isn't in any cage.
+const NO_CAGE: char = '.';
+
+/// Character separating the sums of the cages. Commas and spaces aren't used
+/// so a puzzle stays a single field in batch input.
+const SUM_SEPARATOR: char = '/';
+
+/// Write `cages` in the format read by `parse_cages`, returning the map and
+/// the sums. Each cage gets the first letter from `A` not used by a cage it
+/// touches.
+pub fn format_cages(cages: &[Cage]) -> (String, String) {
+    let mut cages: Vec<&Cage> = cages.iter().collect();
+    cages.sort_by_key(|cage| cage.cells[0]);
+
+    let mut labels = [[NO_CAGE; SIZE]; SIZE];
+    for cage in cages.iter() {
+        let touching: Vec<char> = cage
+            .cells
+            .iter()
+            .flat_map(|&c| neighbours(c))
+            .map(|n| labels[n.row][n.col])
+            .collect();
+        let label = ('A'..='Z')
+            .chain('a'..='z')
+            .find(|l| !touching.contains(l))
+            .unwrap_or('?');
+        for cell in cage.cells.iter() {
+            labels[cell.row][cell.col] = label;
+        }
+    }
+
+    let map = labels.iter().flatten().collect();
+    let sums: Vec<String> = cages.iter().map(|cage| cage.sum.to_string()).collect();
+    (map, sums.join(&SUM_SEPARATOR.to_string()))
+}
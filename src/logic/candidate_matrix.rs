@@ -1,9 +1,9 @@
 use std::fmt::Write;
 use std::{array, collections::HashSet, fmt};
 
-use rand::seq::SliceRandom;
+use rand::{seq::SliceRandom, Rng};
 
-use super::{grid::get_box_coords_containing, Cell, Coord, NUM_WIDTH, ORDER, SIZE};
+use super::{Coord, NUM_WIDTH, ORDER, SIZE};
 
 #[derive(Debug, Clone)]
 /// Struct representing the possible values for cells in a `Grid`. If a value is
@@ -13,31 +13,6 @@ use super::{grid::get_box_coords_containing, Cell, Coord, NUM_WIDTH, ORDER, SIZE
 /// considered by methods such as `get_min_candidates`.
 pub struct CandidateMatrix([[HashSet<u8>; SIZE]; SIZE]);
 
-/// Create the candidate matrix for the grid passed as rows.
-impl From<&[[Cell; SIZE]; SIZE]> for CandidateMatrix {
-    fn from(rows: &[[Cell; SIZE]; SIZE]) -> Self {
-        let candidates = HashSet::from(array::from_fn::<u8, SIZE, _>(|i| (i + 1) as u8));
-
-        let mut cm = CandidateMatrix(rows.map(|row| {
-            row.map(|cell| match cell {
-                Cell::Clue(_) => HashSet::from([0]),
-                _ => candidates.clone(),
-            })
-        }));
-
-        rows.iter().enumerate().for_each(|(row_i, row)| {
-            row.iter().enumerate().for_each(|(col_i, cell)| {
-                if let Cell::Clue(n) = cell {
-                    cm.update_around((row_i, col_i).into(), *n)
-                        .expect("invalid grid entered");
-                }
-            })
-        });
-
-        cm
-    }
-}
-
 impl CandidateMatrix {
     /// Create a new candidate matrix
     pub fn new() -> Self {
@@ -46,67 +21,161 @@ impl CandidateMatrix {
         CandidateMatrix(array::from_fn(|_| array::from_fn(|_| c_matrix.clone())))
     }
 
-    /// Get the canidates for the cell at `cell`.
+    /// Get the canidates for the cell at `cell`, in ascending order.
     pub fn get_candidates(&self, cell: Coord) -> Vec<u8> {
-        Vec::from_iter(self.0[cell.row][cell.col].clone())
+        let mut candidates = Vec::from_iter(self.0[cell.row][cell.col].clone());
+        candidates.sort_unstable();
+        candidates
     }
 
-    /// Update the candidate sets for each group containing `cell`. This removes
-    /// `val` from all cells in these groups if it's present.
-    pub fn update_around(&mut self, cell: Coord, val: u8) -> Result<(), ()> {
-        for candidates in self.0[cell.row].iter_mut() {
+    /// Remove `val` from the candidate sets of every cell in `cells`. Returns
+    /// the coordinate of the first cell left with no candidates as an error.
+    pub fn eliminate(&mut self, cells: &[Coord], val: u8) -> Result<(), Coord> {
+        for &cell in cells.iter() {
+            let candidates = &mut self.0[cell.row][cell.col];
             candidates.remove(&val);
             if candidates.is_empty() {
-                return Err(());
+                return Err(cell);
             }
         }
 
-        for row in self.0.iter_mut() {
-            row[cell.col].remove(&val);
-            if row[cell.col].is_empty() {
-                return Err(());
+        Ok(())
+    }
+
+    /// Keep only the candidates of the empty cells `cells` that can be part of
+    /// a set of different values adding up to `sum`, one value per cell. A set
+    /// of values is only used if every cell has a candidate in it and the
+    /// cells have every value in it between them. Returns the coordinate of
+    /// the first cell left with no candidates as an error.
+    pub fn restrict_to_sum(&mut self, cells: &[Coord], sum: u32) -> Result<(), Coord> {
+        let masks: Vec<u32> = cells
+            .iter()
+            .map(|c| {
+                self.0[c.row][c.col]
+                    .iter()
+                    .filter(|&&n| n != 0)
+                    .fold(0, |mask, &n| mask | 1 << (n - 1))
+            })
+            .collect();
+        let union = masks.iter().fold(0, |union, &mask| union | mask);
+
+        let mut allowed = vec![0; cells.len()];
+        for set in 0u32..1 << SIZE {
+            if set.count_ones() as usize != cells.len()
+                || set & union != set
+                || set_sum(set) != sum
+                || masks.iter().any(|&mask| mask & set == 0)
+            {
+                continue;
+            }
+            for (allowed, &mask) in allowed.iter_mut().zip(masks.iter()) {
+                *allowed |= mask & set;
             }
         }
 
-        for coord in get_box_coords_containing(cell).into_iter() {
-            self.0[coord.row][coord.col].remove(&val);
-            if self.0[coord.row][coord.col].is_empty() {
-                return Err(());
+        for (&cell, &allowed) in cells.iter().zip(allowed.iter()) {
+            let candidates = &mut self.0[cell.row][cell.col];
+            candidates.retain(|&n| n != 0 && allowed & 1 << (n - 1) != 0);
+            if candidates.is_empty() {
+                return Err(cell);
             }
         }
 
         Ok(())
     }
 
+    /// Keep only the candidates of `cell` that `related` accepts alongside at
+    /// least one possible value of the neighbouring cell `other`: `placed` if
+    /// it's filled, otherwise its candidates. Fixed cells are left alone.
+    /// Returns `cell` as an error if it's left with no candidates.
+    pub fn retain_related(
+        &mut self,
+        cell: Coord,
+        other: Coord,
+        placed: Option<u8>,
+        related: impl Fn(u8, u8) -> bool,
+    ) -> Result<(), Coord> {
+        let values = match placed {
+            Some(n) => 1 << (n - 1),
+            None => self.0[other.row][other.col]
+                .iter()
+                .filter(|&&n| n != 0)
+                .fold(0u32, |mask, &n| mask | 1 << (n - 1)),
+        };
+        let candidates = &mut self.0[cell.row][cell.col];
+        if values == 0 || candidates.contains(&0) {
+            return Ok(());
+        }
+
+        let mut allowed = 0u32;
+        for v in (1..=SIZE as u8).filter(|&v| values & 1 << (v - 1) != 0) {
+            for n in 1..=SIZE as u8 {
+                if related(v, n) {
+                    allowed |= 1 << (n - 1);
+                }
+            }
+        }
+        candidates.retain(|&n| allowed & 1 << (n - 1) != 0);
+        if candidates.is_empty() {
+            return Err(cell);
+        }
+
+        Ok(())
+    }
+
+    /// Returns `true` if `val` is a candidate of `cell`.
+    pub fn contains(&self, cell: Coord, val: u8) -> bool {
+        self.0[cell.row][cell.col].contains(&val)
+    }
+
+    /// Returns the lowest and highest candidates of `cell`, or `None` if it's
+    /// fixed.
+    pub fn bounds(&self, cell: Coord) -> Option<(u8, u8)> {
+        let candidates = &self.0[cell.row][cell.col];
+        if candidates.contains(&0) {
+            return None;
+        }
+        Some((*candidates.iter().min()?, *candidates.iter().max()?))
+    }
+
+    /// Keep only the candidates of `cell` from `lo` to `hi`. Fixed cells are
+    /// left alone. Returns `cell` as an error if it's left with no candidates.
+    pub fn restrict_to_range(&mut self, cell: Coord, lo: u8, hi: u8) -> Result<(), Coord> {
+        let candidates = &mut self.0[cell.row][cell.col];
+        if candidates.contains(&0) {
+            return Ok(());
+        }
+
+        candidates.retain(|n| (lo..=hi).contains(n));
+        if candidates.is_empty() {
+            return Err(cell);
+        }
+
+        Ok(())
+    }
+
     /// Remove candiate `val` from `cell`.
     pub fn remove_candidate(&mut self, cell: Coord, val: u8) -> bool {
         self.0[cell.row][cell.col].remove(&val)
     }
 
     /// Gets the coordinates of the cell with the lowest possibilities in the
-    /// grid. Excludes cells with already-set values (i.e. contain `0`).
-    pub fn get_min_candidates_cell(&self) -> Coord {
-        let rows_to_lens = |row: [HashSet<u8>; SIZE]| {
-            row.into_iter()
-                .map(|c| if c.contains(&0) { usize::MAX } else { c.len() })
-                .collect()
-        };
-        let lens: Vec<Vec<_>> = self.0.clone().into_iter().map(rows_to_lens).collect();
+    /// grid, using `rng` to break ties. Excludes cells with already-set values
+    /// (i.e. contain `0`).
+    pub fn get_min_candidates_cell<R: Rng + ?Sized>(&self, rng: &mut R) -> Coord {
+        let len = |c: &HashSet<u8>| if c.contains(&0) { usize::MAX } else { c.len() };
+        let min = self.0.iter().flatten().map(len).min();
 
         let mut coords: Vec<Coord> = Vec::new();
-
-        let min = lens.clone().into_iter().flatten().min();
-        lens.into_iter().enumerate().for_each(|(i, row)| {
-            row.into_iter().enumerate().for_each(|(j, c)| {
-                if Some(c) == min {
+        for (i, row) in self.0.iter().enumerate() {
+            for (j, c) in row.iter().enumerate() {
+                if Some(len(c)) == min {
                     coords.push((i, j).into());
                 }
-            })
-        });
+            }
+        }
 
-        *coords
-            .choose(&mut rand::thread_rng())
-            .expect("There has to be a minimum")
+        *coords.choose(rng).expect("There has to be a minimum")
     }
 
     /// This marks a cell as fixed, so it won't be included in
@@ -145,6 +214,15 @@ impl Default for CandidateMatrix {
     }
 }
 
+/// Helper function to add up the values in `set`, where bit `i` stands for
+/// the value `i + 1`.
+fn set_sum(set: u32) -> u32 {
+    (0..SIZE as u32)
+        .filter(|&i| set & 1 << i != 0)
+        .map(|i| i + 1)
+        .sum()
+}
+
 /// Helper function for displaying a candidate matrix for debugging purposes
 fn fmt_row(row: &[HashSet<u8>; SIZE]) -> Result<String, fmt::Error> {
     let width = NUM_WIDTH as usize;
@@ -173,3 +251,66 @@ fn fmt_row(row: &[HashSet<u8>; SIZE]) -> Result<String, fmt::Error> {
     }
     Ok(s)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cells(coords: &[(usize, usize)]) -> Vec<Coord> {
+        coords.iter().map(|&c| c.into()).collect()
+    }
+
+    #[test]
+    fn restricts_two_cells_to_the_only_pair() {
+        let mut cm = CandidateMatrix::new();
+        let cage = cells(&[(0, 0), (0, 1)]);
+        assert_eq!(cm.restrict_to_sum(&cage, 3), Ok(()));
+        for &cell in cage.iter() {
+            assert_eq!(cm.get_candidates(cell), [1, 2]);
+        }
+    }
+
+    #[test]
+    fn restricts_three_cells_to_the_only_triple() {
+        let mut cm = CandidateMatrix::new();
+        let cage = cells(&[(0, 0), (1, 0), (2, 0)]);
+        assert_eq!(cm.restrict_to_sum(&cage, 24), Ok(()));
+        for &cell in cage.iter() {
+            assert_eq!(cm.get_candidates(cell), [7, 8, 9]);
+        }
+    }
+
+    #[test]
+    fn keeps_every_value_in_some_combination() {
+        // 10 is 1+9, 2+8, 3+7 or 4+6, so only 5 is ruled out.
+        let mut cm = CandidateMatrix::new();
+        let cage = cells(&[(0, 0), (0, 1)]);
+        assert_eq!(cm.restrict_to_sum(&cage, 10), Ok(()));
+        assert_eq!(cm.get_candidates(cage[0]), [1, 2, 3, 4, 6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn uses_the_candidates_left_in_each_cell() {
+        let mut cm = CandidateMatrix::new();
+        let cage = cells(&[(0, 0), (0, 1)]);
+        for n in 3..=SIZE as u8 {
+            cm.remove_candidate(cage[0], n);
+        }
+        // Only 1+9 and 2+8 are left. Repeats of the first cell's values are
+        // ruled out by the cells' shared row, not by the sum.
+        assert_eq!(cm.restrict_to_sum(&cage, 10), Ok(()));
+        assert_eq!(cm.get_candidates(cage[0]), [1, 2]);
+        assert_eq!(cm.get_candidates(cage[1]), [1, 2, 8, 9]);
+    }
+
+    #[test]
+    fn fails_when_no_combination_adds_up() {
+        let mut cm = CandidateMatrix::new();
+        let cage = cells(&[(0, 0), (0, 1)]);
+        assert_eq!(cm.restrict_to_sum(&cage, 18), Err(cage[0]));
+
+        let mut cm = CandidateMatrix::new();
+        let cage = cells(&[(0, 0), (0, 1), (0, 2)]);
+        assert_eq!(cm.restrict_to_sum(&cage, 5), Err(cage[0]));
+    }
+}
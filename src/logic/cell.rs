@@ -1,19 +1,27 @@
 use std::fmt;
 
-use colored::Colorize;
-
 use crate::CELL_WIDTH;
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 /// Enum representing possible states of a cell. `Clue` is when a number is
-/// fixed, represented in bold, `Filled` is when a cell contains a player's
-/// input, and `Empty` is an empty cell.
+/// fixed, `Filled` is when a cell contains a player's input, and `Empty` is
+/// an empty cell.
 pub enum Cell {
     Clue(u8),
     Filled(u8),
     Empty,
 }
 
+impl Cell {
+    /// Returns the value in the cell, or `None` if it's empty.
+    pub fn value(&self) -> Option<u8> {
+        match *self {
+            Cell::Clue(n) | Cell::Filled(n) if n != 0 => Some(n),
+            _ => None,
+        }
+    }
+}
+
 impl fmt::Display for Cell {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let width = CELL_WIDTH as usize;
@@ -22,7 +30,7 @@ impl fmt::Display for Cell {
                 if n == &0 {
                     write!(f, "{:>width$}", '?')
                 } else {
-                    write!(f, "{:>width$}", n.to_string().bold())
+                    write!(f, "{n:>width$}")
                 }
             }
             Self::Filled(n) => write!(f, "{:width$}", n),
@@ -0,0 +1,214 @@
+use rand::Rng;
+
+use super::{grid::GridError, Coord, GridArray, SIZE};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+/// Enum representing how the values in two orthogonally neighbouring cells
+/// have to be related.
+pub enum Relation {
+    /// The values are consecutive, shown by a white Kropki dot.
+    Consecutive,
+    /// One value is double the other, shown by a black Kropki dot.
+    Double,
+    /// The values aren't consecutive.
+    NotConsecutive,
+    /// The values are neither consecutive nor one double the other, as for
+    /// neighbours without a dot when the negative constraint of Kropki Sudoku
+    /// is used.
+    Neither,
+    /// The first value is smaller than the second, shown by a greater-than
+    /// sign pointing at the first cell.
+    Less,
+    /// The first value is bigger than the second, shown by a greater-than
+    /// sign pointing at the second cell.
+    Greater,
+}
+
+impl Relation {
+    /// Returns `true` if the values `a` and `b` can be either side of an edge
+    /// with this relation.
+    pub fn allows(&self, a: u8, b: u8) -> bool {
+        let consecutive = a.abs_diff(b) == 1;
+        let double = a == 2 * b || b == 2 * a;
+        match self {
+            Relation::Consecutive => consecutive,
+            Relation::Double => double,
+            Relation::NotConsecutive => !consecutive,
+            Relation::Neither => !consecutive && !double,
+            Relation::Less => a < b,
+            Relation::Greater => a > b,
+        }
+    }
+
+    /// Returns the relation with the two values swapped round.
+    pub fn flipped(&self) -> Self {
+        match self {
+            Relation::Less => Relation::Greater,
+            Relation::Greater => Relation::Less,
+            &relation => relation,
+        }
+    }
+
+    /// Returns `true` if the relation only rules out a few pairs of values,
+    /// so it's only worth applying once one of the values is placed.
+    pub fn is_negative(&self) -> bool {
+        matches!(self, Relation::NotConsecutive | Relation::Neither)
+    }
+
+    /// The character the relation's dot is drawn and written as, if it has
+    /// one.
+    pub fn dot(&self) -> Option<char> {
+        match self {
+            Relation::Consecutive => Some(WHITE_DOT),
+            Relation::Double => Some(BLACK_DOT),
+            Relation::NotConsecutive | Relation::Neither | Relation::Less | Relation::Greater => {
+                None
+            }
+        }
+    }
+}
+
+/// Character for a white dot, between consecutive values.
+const WHITE_DOT: char = 'o';
+
+/// Character for a black dot, between values where one is double the other.
+const BLACK_DOT: char = '*';
+
+/// Character for an edge without a dot in line format.
+const NO_DOT: char = '.';
+
+/// Character at the end of the dots in line format that marks the negative
+/// constraint: neighbours without a dot are neither consecutive nor double.
+const NEGATIVE: char = '!';
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// Struct representing a rule between the values of two orthogonally
+/// neighbouring cells, with the first cell above or left of the second.
+pub struct Edge {
+    pub cells: (Coord, Coord),
+    pub relation: Relation,
+}
+
+impl Edge {
+    /// Construct an edge between `a` and `b`, where `relation` compares the
+    /// value in `a` to the one in `b`. The cells can be given either way
+    /// round.
+    pub fn new(a: Coord, b: Coord, relation: Relation) -> Self {
+        match a < b {
+            true => Edge {
+                cells: (a, b),
+                relation,
+            },
+            false => Edge {
+                cells: (b, a),
+                relation: relation.flipped(),
+            },
+        }
+    }
+
+    /// The character the edge is drawn as between its cells, if it's drawn:
+    /// its Kropki dot, or for a greater-than sign `<` or `>` between cells
+    /// side by side and `^` or `v` between cells one above the other, opening
+    /// towards the bigger value.
+    pub fn mark(&self) -> Option<char> {
+        let across = self.cells.0.row == self.cells.1.row;
+        match (self.relation, across) {
+            (Relation::Less, true) => Some('<'),
+            (Relation::Greater, true) => Some('>'),
+            (Relation::Less, false) => Some('^'),
+            (Relation::Greater, false) => Some('v'),
+            (relation, _) => relation.dot(),
+        }
+    }
+}
+
+/// Returns every pair of orthogonally neighbouring cells: the pairs side by
+/// side in row order, then the pairs one above the other in row order.
+pub fn neighbour_pairs() -> Vec<(Coord, Coord)> {
+    let across = (0..SIZE)
+        .flat_map(|i| (0..SIZE - 1).map(move |j| (Coord::from((i, j)), Coord::from((i, j + 1)))));
+    let down = (0..SIZE - 1)
+        .flat_map(|i| (0..SIZE).map(move |j| (Coord::from((i, j)), Coord::from((i + 1, j)))));
+    across.chain(down).collect()
+}
+
+/// Returns the Kropki dots for the solved grid `solution`: a white dot
+/// between every pair of consecutive neighbours and a black dot between every
+/// pair where one is double the other, choosing between them with `rng` for
+/// a 1 next to a 2.
+pub fn kropki_dots<R: Rng + ?Sized>(solution: &GridArray, rng: &mut R) -> Vec<Edge> {
+    let value = |c: Coord| solution[c.row][c.col].value().unwrap_or(0);
+    neighbour_pairs()
+        .into_iter()
+        .filter_map(|(a, b)| {
+            let (x, y) = (value(a), value(b));
+            let relation = match (
+                Relation::Consecutive.allows(x, y),
+                Relation::Double.allows(x, y),
+            ) {
+                (true, true) if rng.gen() => Relation::Double,
+                (true, _) => Relation::Consecutive,
+                (false, true) => Relation::Double,
+                (false, false) => return None,
+            };
+            Some(Edge::new(a, b, relation))
+        })
+        .collect()
+}
+
+/// Parse Kropki dots from one character per pair of neighbouring cells, in
+/// the order of `neighbour_pairs`, ignoring whitespace: `o` for a white dot,
+/// `*` for a black dot and `.` for none. A final `!` marks the negative
+/// constraint. Returns the dots and whether the negative constraint is used.
+pub fn parse_dots(s: &str) -> Result<(Vec<Edge>, bool), GridError> {
+    let s: String = s.chars().filter(|c| !c.is_whitespace()).collect();
+    let (s, negative) = match s.strip_suffix(NEGATIVE) {
+        Some(s) => (s, true),
+        None => (s.as_str(), false),
+    };
+
+    let pairs = neighbour_pairs();
+    let chars: Vec<char> = s.chars().collect();
+    if chars.len() != pairs.len() {
+        return Err(GridError::invalid_format(format!(
+            "Expected {} dots but found {}",
+            pairs.len(),
+            chars.len()
+        )));
+    }
+
+    let mut dots = Vec::new();
+    for (&(a, b), c) in pairs.iter().zip(chars) {
+        let relation = match c.to_ascii_lowercase() {
+            WHITE_DOT | 'w' => Relation::Consecutive,
+            BLACK_DOT | 'b' => Relation::Double,
+            NO_DOT => continue,
+            _ => {
+                return Err(GridError::invalid_format(format!(
+                    "Unexpected dot `{c}`, expected `{WHITE_DOT}`, `{BLACK_DOT}` or `{NO_DOT}`"
+                )))
+            }
+        };
+        dots.push(Edge::new(a, b, relation));
+    }
+
+    Ok((dots, negative))
+}
+
+/// Write `dots` in the format read by `parse_dots`, followed by `!` if
+/// `negative`.
+pub fn format_dots(dots: &[&Edge], negative: bool) -> String {
+    let mut s: String = neighbour_pairs()
+        .into_iter()
+        .map(|pair| {
+            dots.iter()
+                .find(|e| e.cells == pair)
+                .and_then(|e| e.relation.dot())
+                .unwrap_or(NO_DOT)
+        })
+        .collect();
+    if negative {
+        s.push(NEGATIVE);
+    }
+    s
+}
@@ -1,46 +1,401 @@
-use std::{
-    array,
-    time::{Duration, Instant},
-};
-
-use crate::logic::{Coord, SIZE};
+use std::{fmt, str::FromStr, sync::Arc, time::Duration};
 
-use super::{solve_backtracking_heuristics, Grid};
+use super::{
+    count_solutions,
+    grid::{ErrorKind, GridError},
+    kropki_dots, random_cages,
+    solver::{solve_with_limit, solve_with_rng},
+    Cell, Coord, Grid, GridArray, Layout, Rules, Symmetry, Toggles, Variant, SIZE,
+};
 
-use rand::seq::{IteratorRandom, SliceRandom};
+use rand::{
+    seq::{IteratorRandom, SliceRandom},
+    Rng,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Difficulty {
     Easy,
     Medium,
     Hard,
+    /// Clues are removed until none can be without the puzzle losing its
+    /// unique solution.
+    Minimal,
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+            Difficulty::Minimal => "minimal",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Enable parsing a difficulty from its name, ignoring case.
+impl FromStr for Difficulty {
+    type Err = GridError;
+
+    fn from_str(s: &str) -> Result<Self, GridError> {
+        match s.to_lowercase().as_str() {
+            "easy" | "e" => Ok(Difficulty::Easy),
+            "medium" | "m" => Ok(Difficulty::Medium),
+            "hard" | "h" => Ok(Difficulty::Hard),
+            "minimal" | "x" => Ok(Difficulty::Minimal),
+            _ => Err(GridError::invalid_format(format!(
+                "Unknown difficulty `{s}`, expected easy, medium, hard or minimal"
+            ))),
+        }
+    }
 }
 
-pub fn generate(d: Difficulty) -> (Grid, Duration) {
+/// Generate a puzzle as `generate_with_rng` does, from the thread's random
+/// number generator, and return it with the time taken. WebAssembly has no
+/// source of entropy of its own, so this isn't available there and a seeded
+/// `rng` should be given to `generate_with_rng` instead.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn generate(
+    d: Difficulty,
+    symmetry: Symmetry,
+    variant: Variant,
+    toggles: Toggles,
+) -> Result<(Grid, Duration), GridError> {
     let now = Instant::now();
-    let mut g = solve_backtracking_heuristics(Grid::new()).unwrap();
-    let coords: [[Coord; SIZE]; SIZE] = array::from_fn(|i| array::from_fn(|j| (i, j).into()));
-    let coords = coords.flatten();
+    let g = generate_with_rng(d, symmetry, variant, toggles, &mut rand::thread_rng())?;
+
+    Ok((g, now.elapsed()))
+}
+
+/// Generate a puzzle of `variant`, with the constraints in `toggles` switched
+/// on, with difficulty `d` whose clues follow `symmetry`, using `rng` as the
+/// only source of randomness so that a seeded `rng` always produces the same
+/// puzzle. Returns an error of kind `TimedOut` if no solved grid following
+/// the rules is found, as some combinations of constraints have none.
+pub fn generate_with_rng<R: Rng + ?Sized>(
+    d: Difficulty,
+    symmetry: Symmetry,
+    variant: Variant,
+    toggles: Toggles,
+    rng: &mut R,
+) -> Result<Grid, GridError> {
+    let solution = random_solution(variant, toggles, rng)?;
+    if variant == Variant::Killer {
+        return Ok(generate_killer(d, symmetry, solution, rng));
+    } else if variant == Variant::Kropki {
+        return Ok(generate_kropki(d, symmetry, solution, rng));
+    }
+
+    Ok(generate_from_solution(d, symmetry, solution, rng))
+}
+
+/// Generate a Killer puzzle by splitting `solution` into random cages, then
+/// removing as many clues as possible while the solution stays unique, so
+/// most puzzles are left with none. The difficulty sets how big the cages can
+/// be, as bigger cages have more ways to make up their sum.
+fn generate_killer<R: Rng + ?Sized>(
+    d: Difficulty,
+    symmetry: Symmetry,
+    solution: Grid,
+    rng: &mut R,
+) -> Grid {
+    let max_cage_size = match d {
+        Difficulty::Easy => 3,
+        Difficulty::Medium => 4,
+        Difficulty::Hard => 5,
+        Difficulty::Minimal => 6,
+    };
+
+    let rules = random_cages(solution.rows(), max_cage_size, rng)
+        .into_iter()
+        .fold(solution.rules().clone(), Rules::with_cage);
+    remove_clues(
+        solution.rows(),
+        symmetry,
+        &Arc::new(rules),
+        SIZE.pow(2),
+        rng,
+    )
+}
+
+/// Generate a Kropki puzzle by adding every dot `solution` has, with the
+/// negative constraint so the neighbours without a dot give clues too, then
+/// removing clues as for any other variant.
+fn generate_kropki<R: Rng + ?Sized>(
+    d: Difficulty,
+    symmetry: Symmetry,
+    solution: Grid,
+    rng: &mut R,
+) -> Grid {
+    let dots = kropki_dots(solution.rows(), rng);
+    let rules = solution.rules().clone().with_dots(&dots, true);
+    let solution = Grid::from_rows_with_rules(*solution.rows(), rules)
+        .expect("The dots are taken from the solution, so it follows them");
+    generate_from_solution(d, symmetry, solution, rng)
+}
+
+/// Number of times a random Jigsaw layout's search for a solution can
+/// backtrack before it's started again.
+const JIGSAW_BACKTRACK_LIMIT: usize = 50;
+
+/// Number of times the search for a solution to a random Jigsaw layout, or
+/// to rules with extra constraints, is started again before trying another
+/// layout, as many layouts have none.
+const JIGSAW_RESTARTS: usize = 10;
+
+/// Number of times the search for a solution can backtrack before it's
+/// started again when extra constraints are switched on.
+const CONSTRAINED_BACKTRACK_LIMIT: usize = 1000;
+
+/// Number of searches for a solution tried in total when extra constraints
+/// are switched on, before giving up on the rules having one.
+const CONSTRAINED_ATTEMPTS: usize = 200;
+
+/// Helper function to find a random solved grid following the rules of
+/// `variant` and `toggles`, with a random layout for Jigsaw Sudoku.
+///
+/// Searches that don't finish quickly are started again, with a new layout
+/// for Jigsaw Sudoku, as many layouts have no solution. With extra
+/// constraints there may be no solution at all, so after a number of
+/// attempts an error of kind `TimedOut` is returned.
+fn random_solution<R: Rng + ?Sized>(
+    variant: Variant,
+    toggles: Toggles,
+    rng: &mut R,
+) -> Result<Grid, GridError> {
+    let constrained = toggles != Toggles::default();
+    if variant != Variant::Jigsaw && !constrained {
+        return solve_with_rng(Grid::with_variant(variant), rng);
+    }
+
+    let limit = match constrained {
+        true => CONSTRAINED_BACKTRACK_LIMIT,
+        false => JIGSAW_BACKTRACK_LIMIT,
+    };
+    let mut attempts = 0;
+    while !constrained || attempts < CONSTRAINED_ATTEMPTS {
+        let mut rules = variant.rules();
+        if variant == Variant::Jigsaw {
+            rules = rules.with_layout(&Layout::random(rng));
+        }
+        let rules = Arc::new(toggles.apply(rules));
+
+        for _ in 0..JIGSAW_RESTARTS {
+            attempts += 1;
+            // Constraints that rule out a cell's every value can't be met
+            // with this layout.
+            let Ok(g) = Grid::with_rules(rules.clone()) else {
+                break;
+            };
+            if let Ok(solution) = solve_with_limit(g, limit, rng) {
+                return Ok(solution);
+            }
+        }
+    }
 
+    Err(GridError::with_details(
+        ErrorKind::TimedOut,
+        format!("Couldn't find a solved {variant} grid with the {toggles} constraints, which may not allow one"),
+    ))
+}
+
+/// Turn `solution`, a solved grid following any rules, into a puzzle with
+/// difficulty `d` whose clues follow `symmetry`, using `rng` as the only
+/// source of randomness. Clues are only removed while the solution stays
+/// unique, so harder puzzles may keep a few more clues than they aim for.
+pub fn generate_from_solution<R: Rng + ?Sized>(
+    d: Difficulty,
+    symmetry: Symmetry,
+    solution: Grid,
+    rng: &mut R,
+) -> Grid {
     let to_delete = match d {
-        Difficulty::Easy => (40..45)
-            .choose(&mut rand::thread_rng())
-            .unwrap_or(43),
-        Difficulty::Medium => (46..50)
-            .choose(&mut rand::thread_rng())
-            .unwrap_or(47),
-        Difficulty::Hard => (51..56)
-            .choose(&mut rand::thread_rng())
-            .unwrap_or(53),
+        Difficulty::Easy => (40..45).choose(rng).unwrap_or(43),
+        Difficulty::Medium => (46..50).choose(rng).unwrap_or(47),
+        Difficulty::Hard => (51..56).choose(rng).unwrap_or(53),
+        Difficulty::Minimal => SIZE.pow(2),
+    };
+
+    let rules = Arc::new(solution.rules().clone());
+    remove_clues(solution.rows(), symmetry, &rules, to_delete, rng)
+}
+
+/// Type representing a pattern of clues, where `true` marks a cell that
+/// should hold a clue.
+pub type Mask = [[bool; SIZE]; SIZE];
+
+/// The fewest clues a 9x9 puzzle can have and still have a unique solution.
+const MIN_CLUES: usize = 17;
+
+/// Solutions are only counted up to this many when searching for a puzzle
+/// that fits a mask, to keep each step of the search quick.
+const MASK_COUNT_LIMIT: usize = 16;
+
+/// Number of clue changes tried before the mask search restarts from a new
+/// random solution.
+const MASK_RESTART_STEPS: usize = 400;
+
+/// Generate a puzzle whose clues are exactly the cells marked in `mask`.
+///
+/// The search starts from the mask applied to a random solution, then
+/// repeatedly changes the value of one clue to another that still leaves the
+/// puzzle solvable, keeping the change if it doesn't increase the number of
+/// solutions, until the solution is unique. Returns an error of kind
+/// `TimedOut` if no puzzle is found within `timeout`, or `NoSolution` if the
+/// mask has too few clues to ever work.
+pub fn generate_from_mask<R: Rng + ?Sized>(
+    mask: &Mask,
+    timeout: Duration,
+    rng: &mut R,
+) -> Result<Grid, GridError> {
+    let clues: Vec<Coord> = (0..SIZE.pow(2))
+        .map(|i| Coord::from((i / SIZE, i % SIZE)))
+        .filter(|c| mask[c.row][c.col])
+        .collect();
+    if SIZE == 9 && clues.len() < MIN_CLUES {
+        return Err(GridError::with_details(
+            ErrorKind::NoSolution,
+            format!(
+                "A puzzle needs at least {MIN_CLUES} clues to have a unique solution, but the pattern has {}",
+                clues.len()
+            ),
+        ));
+    }
+
+    let now = Instant::now();
+    while now.elapsed() < timeout {
+        let solution = solve_with_rng(Grid::new(), rng)?;
+        let mut rows = apply_mask(solution.rows(), mask);
+        let mut count = count_solutions(Grid::from_rows(rows)?, MASK_COUNT_LIMIT);
+
+        for _ in 0..MASK_RESTART_STEPS {
+            if count == 1 {
+                return Grid::from_rows(rows);
+            } else if now.elapsed() >= timeout {
+                break;
+            }
+
+            let Some(&cell) = clues.choose(rng) else {
+                break;
+            };
+            if let Some(changed) = change_clue(&rows, mask, cell, rng) {
+                let changed_count = count_solutions(Grid::from_rows(changed)?, MASK_COUNT_LIMIT);
+                if changed_count <= count {
+                    (rows, count) = (changed, changed_count);
+                }
+            }
+        }
+    }
+
+    Err(GridError::with_details(
+        ErrorKind::TimedOut,
+        format!("Couldn't find a puzzle with a unique solution for the pattern within {timeout:?}"),
+    ))
+}
+
+/// Helper function to keep only the cells of `solution` marked in `mask`, as
+/// clues.
+fn apply_mask(solution: &GridArray, mask: &Mask) -> GridArray {
+    std::array::from_fn(|i| {
+        std::array::from_fn(|j| match solution[i][j] {
+            Cell::Clue(n) | Cell::Filled(n) if mask[i][j] => Cell::Clue(n),
+            _ => Cell::Empty,
+        })
+    })
+}
+
+/// Returns the puzzle `rows` with the clue at `cell` given a different value,
+/// and the other clues re-chosen from a random solution that has that value,
+/// or `None` if the other clues force the current value.
+fn change_clue<R: Rng + ?Sized>(
+    rows: &GridArray,
+    mask: &Mask,
+    cell: Coord,
+    rng: &mut R,
+) -> Option<GridArray> {
+    let Cell::Clue(current) = rows[cell.row][cell.col] else {
+        return None;
     };
 
-    coords
-        .choose_multiple(&mut rand::thread_rng(), to_delete)
-        .for_each(|coord| {
-            let _ = g.clear(*coord);
-        });
-    g.set_clues();
-    g.reset_candidate_matrix();
+    let mut without = *rows;
+    without[cell.row][cell.col] = Cell::Empty;
+    let mut g = Grid::from_rows(without).ok()?;
+    g.remove_candidate(cell, current);
+    let solution = solve_with_rng(g, rng).ok()?;
+
+    Some(apply_mask(solution.rows(), mask))
+}
+
+/// Turn `solution` into a puzzle by removing orbits of `symmetry` in a random
+/// order, skipping any orbit whose removal would give the puzzle more than one
+/// solution, until at least `to_delete` cells are empty. Whole orbits are
+/// removed so the clues keep the symmetry, which can remove a few more cells
+/// than `to_delete`. Removing clues never reduces the number of solutions, so
+/// if the pass ends before `to_delete` is reached, no orbit can be removed.
+/// That only makes the puzzle minimal without a symmetry, as a single clue of
+/// a larger orbit may still be removable on its own.
+fn remove_clues<R: Rng + ?Sized>(
+    solution: &GridArray,
+    symmetry: Symmetry,
+    rules: &Arc<Rules>,
+    to_delete: usize,
+    rng: &mut R,
+) -> Grid {
+    let mut rows = solution.map(|row| {
+        row.map(|cell| match cell {
+            Cell::Clue(n) | Cell::Filled(n) => Cell::Clue(n),
+            Cell::Empty => Cell::Empty,
+        })
+    });
+    let mut orbits = symmetry.orbits();
+    orbits.shuffle(rng);
+
+    let mut deleted = 0;
+    for orbit in orbits.into_iter() {
+        if deleted >= to_delete {
+            break;
+        }
+        let removed = rows;
+        for coord in orbit.iter() {
+            rows[coord.row][coord.col] = Cell::Empty;
+        }
+        match has_unique_solution(&rows, rules) {
+            true => deleted += orbit.len(),
+            false => rows = removed,
+        }
+    }
+
+    Grid::from_rows_with_rules(rows, rules.clone())
+        .expect("Clues taken from a solution can't contradict each other")
+}
+
+/// Returns `true` if `rows` is a valid grid with exactly one solution under
+/// `rules`.
+fn has_unique_solution(rows: &GridArray, rules: &Arc<Rules>) -> bool {
+    Grid::from_rows_with_rules(*rows, rules.clone()).is_ok_and(|g| count_solutions(g, 2) == 1)
+}
+
+/// Returns `true` if `g` has a unique solution, and removing any one of its
+/// clues would give it more than one.
+pub fn is_minimal(g: &Grid) -> bool {
+    let rows = *g.rows();
+    let rules = Arc::new(g.rules().clone());
+    if !has_unique_solution(&rows, &rules) {
+        return false;
+    }
 
-    (g, now.elapsed())
+    (0..SIZE.pow(2))
+        .map(|i| Coord::from((i / SIZE, i % SIZE)))
+        .filter(|c| matches!(rows[c.row][c.col], Cell::Clue(_)))
+        .all(|c| {
+            let mut without = rows;
+            without[c.row][c.col] = Cell::Empty;
+            !has_unique_solution(&without, &rules)
+        })
 }
@@ -1,19 +1,19 @@
 use super::{
-    candidate_matrix::CandidateMatrix,
-    grid_trait::{DisplayableGrid, GridTrait},
-    Cell, Coord, GridArray, ORDER, SIZE,
+    candidate_matrix::CandidateMatrix, grid_trait::DisplayableGrid, validate::Conflict, Cell,
+    Coord, GridArray, Rules, Variant, ORDER, SIZE,
 };
 
-use std::{array, collections::HashSet, fmt};
+use std::{array, fmt, sync::Arc};
+
+use rand::Rng;
 
 #[derive(Clone, Debug)]
 /// Struct to represent a Sudoku grid, with fields for the representation as
-/// rows, columns and boxes, as well as the candidate matrix, an empty cell
-/// count field, and a boolean representing whether the puzzle has been solved.
+/// rows, the rules of the puzzle, the candidate matrix, an empty cell count
+/// field, and a boolean representing whether the puzzle has been solved.
 pub struct Grid {
     rows: GridArray,
-    cols: GridArray,
-    boxes: GridArray,
+    rules: Arc<Rules>,
     candidate_matrix: CandidateMatrix,
     pub empty_cell_count: usize,
     pub solved: bool,
@@ -22,25 +22,31 @@ pub struct Grid {
 impl Grid {
     /// Construct a new grid.
     pub fn new() -> Grid {
-        Grid {
-            rows: [[Cell::Empty; SIZE]; SIZE],
-            cols: [[Cell::Empty; SIZE]; SIZE],
-            boxes: [[Cell::Empty; SIZE]; SIZE],
-            candidate_matrix: CandidateMatrix::new(),
-            empty_cell_count: SIZE.pow(2),
-            solved: false,
-        }
+        Self::with_variant(Variant::Standard)
     }
 
-    /// Check if the puzzle is solved by constructing a `HashSet` from all
-    /// groups and checking its length is equal to SIZE.
+    /// Construct a new grid following the rules of `variant`.
+    pub fn with_variant(variant: Variant) -> Grid {
+        Self::with_rules(variant.rules())
+            .expect("A variant's regions alone can't leave a cell with no candidates")
+    }
+
+    /// Construct a new grid following `rules`. Returns an error if the extra
+    /// constraints in `rules`, such as a cage whose sum no values can make,
+    /// leave a cell with no candidates before any value is placed.
+    pub fn with_rules(rules: impl Into<Arc<Rules>>) -> Result<Grid, GridError> {
+        Self::from_rows_with_rules([[Cell::Empty; SIZE]; SIZE], rules)
+    }
+
+    /// Check if the puzzle is solved, i.e. every cell is filled and no rule
+    /// is broken.
     fn check_solved(&mut self) {
-        let different = |&group| HashSet::from(group).len() == SIZE;
+        self.solved = self.empty_cell_count == 0 && self.rules.is_solution(&self.rows);
+    }
 
-        self.solved = self.empty_cell_count == 0
-            && self.rows.iter().all(different)
-            && self.cols.iter().all(different)
-            && self.boxes.iter().all(different);
+    /// Return the rules the grid follows.
+    pub fn rules(&self) -> &Rules {
+        &self.rules
     }
 
     /// Return a reference to the `rows` field.
@@ -65,10 +71,7 @@ impl Grid {
             Cell::Empty => Ok(()),
             Cell::Clue(_) => Err(GridError::new(ErrorKind::ClearedClue, cell, 0)),
             Cell::Filled(_) => {
-                let (box_row, box_col) = row_coords_to_box_coords(cell).into();
-                self.rows[row][col] = Cell::Empty;
-                self.cols[col][row] = Cell::Empty;
-                self.boxes[box_row][box_col] = Cell::Empty;
+                self.rows[cell.row][cell.col] = Cell::Empty;
                 self.empty_cell_count += 1;
                 self.check_solved();
 
@@ -89,15 +92,12 @@ impl Grid {
             self.empty_cell_count -= 1;
         }
 
-        let (box_row, box_col) = row_coords_to_box_coords(cell).into();
         self.rows[cell.row][cell.col] = Cell::Filled(val);
-        self.cols[cell.col][cell.row] = Cell::Filled(val);
-        self.boxes[box_row][box_col] = Cell::Filled(val);
 
         self.candidate_matrix.set_fixed(cell);
         let result = self
-            .candidate_matrix
-            .update_around(cell, val)
+            .rules
+            .eliminate(&self.rows, &mut self.candidate_matrix, cell, val)
             .map_err(|_| GridError::new(ErrorKind::ZeroCandidates, cell, val));
 
         self.check_solved();
@@ -110,9 +110,10 @@ impl Grid {
         self.candidate_matrix.remove_candidate(cell, val)
     }
 
-    /// Returns the coordinates of the cell with the least valid candidates.
-    pub fn get_min_candidates_cell(&self) -> Coord {
-        self.candidate_matrix.get_min_candidates_cell()
+    /// Returns the coordinates of the cell with the least valid candidates,
+    /// using `rng` to choose between cells with the same number.
+    pub fn get_min_candidates_cell<R: Rng + ?Sized>(&self, rng: &mut R) -> Coord {
+        self.candidate_matrix.get_min_candidates_cell(rng)
     }
 
     /// Gets the candidates at `cell` as a vector.
@@ -120,26 +121,44 @@ impl Grid {
         self.candidate_matrix.get_candidates(cell)
     }
 
-    /// Constructs a `Grid` from a 2D array of `Cell`s.
-    pub fn from_rows(rows: GridArray) -> Self {
-        let cols = rows.cols();
-        let boxes = rows.boxes();
-        let empty_cell_count = rows.iter().fold(0, |mut acc, row| {
-            acc += row.iter().filter(|c| c == &&Cell::Empty).count();
-            acc
-        });
+    /// Constructs a `Grid` from a 2D array of `Cell`s. Returns an error if
+    /// a value is repeated in a row, column or box, or if the clues leave a
+    /// cell with no valid candidates.
+    pub fn from_rows(rows: GridArray) -> Result<Self, GridError> {
+        Self::from_rows_with_rules(rows, Rules::standard())
+    }
+
+    /// Constructs a `Grid` following the rules of `variant` from a 2D array of
+    /// `Cell`s.
+    pub fn from_rows_with_variant(rows: GridArray, variant: Variant) -> Result<Self, GridError> {
+        Self::from_rows_with_rules(rows, variant.rules())
+    }
+
+    /// Constructs a `Grid` following `rules` from a 2D array of `Cell`s.
+    /// Returns an error if the clues break a rule, or if they leave a cell
+    /// with no valid candidates.
+    pub fn from_rows_with_rules(
+        rows: GridArray,
+        rules: impl Into<Arc<Rules>>,
+    ) -> Result<Self, GridError> {
+        let rules = rules.into();
+        let empty_cell_count = rows.iter().flatten().filter(|&&c| c == Cell::Empty).count();
+
+        if let Some(c) = rules.conflicts(&rows).first() {
+            return Err(GridError::conflict(c));
+        }
 
         let mut g = Grid {
             rows,
-            cols,
-            boxes,
-            candidate_matrix: CandidateMatrix::from(&rows),
+            rules,
+            candidate_matrix: CandidateMatrix::new(),
             empty_cell_count,
             solved: false,
         };
 
+        g.reset_candidate_matrix()?;
         g.check_solved();
-        g
+        Ok(g)
     }
 
     /// Set all `Filled` cells to `Clue`s of the same value. Used in puzzle
@@ -148,30 +167,37 @@ impl Grid {
         for row in 0..SIZE {
             for col in 0..SIZE {
                 if let Cell::Filled(val) = self.rows[row][col] {
-                    let box_coords = row_coords_to_box_coords((row, col).into());
                     self.rows[row][col] = Cell::Clue(val);
-                    self.cols[col][row] = Cell::Clue(val);
-                    self.boxes[box_coords.row][box_coords.col] = Cell::Clue(val);
                 }
             }
         }
     }
 
-    pub fn reset_candidate_matrix(&mut self) {
-        self.candidate_matrix = CandidateMatrix::from(&self.rows);
+    /// Rebuild the candidate matrix from the clues currently in the grid.
+    /// Returns an error if the clues leave a cell with no valid candidates.
+    pub fn reset_candidate_matrix(&mut self) -> Result<(), GridError> {
+        self.candidate_matrix = self
+            .rules
+            .candidates(&self.rows)
+            .map_err(|cell| GridError::new(ErrorKind::ContradictoryClues, cell, 0))?;
+        Ok(())
     }
 }
 
 /// Enable conversion from 2D array of cells into `Grid`.
-impl From<GridArray> for Grid {
-    fn from(rows: GridArray) -> Grid {
+impl TryFrom<GridArray> for Grid {
+    type Error = GridError;
+
+    fn try_from(rows: GridArray) -> Result<Grid, GridError> {
         Self::from_rows(rows)
     }
 }
 
 /// Enable conversion from `DisplayableGrid` into `Grid`.
-impl From<DisplayableGrid<Cell>> for Grid {
-    fn from(dg: DisplayableGrid<Cell>) -> Self {
+impl TryFrom<DisplayableGrid<Cell>> for Grid {
+    type Error = GridError;
+
+    fn try_from(dg: DisplayableGrid<Cell>) -> Result<Self, GridError> {
         Self::from_rows(dg.0)
     }
 }
@@ -190,7 +216,7 @@ pub struct GridError {
 }
 
 impl GridError {
-    fn new(kind: ErrorKind, cell: Coord, val: u8) -> Self {
+    pub(super) fn new(kind: ErrorKind, cell: Coord, val: u8) -> Self {
         let cell = Coord::from((cell.row + 1, cell.col + 1));
         let details = match kind {
             ErrorKind::ClearedClue => format!(
@@ -204,11 +230,112 @@ impl GridError {
             ),
             ErrorKind::NotInCandidates => format!(
                 "Updating cell {cell} with value {val} is a mistake. Check again"
-                )
+                ),
+            ErrorKind::ContradictoryClues => format!(
+                "The clues leave cell {cell} with no possible values"
+            ),
+            ErrorKind::DuplicateInRow
+            | ErrorKind::DuplicateInCol
+            | ErrorKind::DuplicateInBox
+            | ErrorKind::DuplicateInDiagonal
+            | ErrorKind::DuplicateInWindow
+            | ErrorKind::DuplicateInCage => format!(
+                "Value `{val}` at {cell} is repeated in its {}",
+                kind.group_name()
+            ),
+            ErrorKind::BrokenConstraint => format!(
+                "Value `{val}` at {cell} breaks one of the puzzle's constraints"
+            ),
+            ErrorKind::AntiKnight | ErrorKind::AntiKing => format!(
+                "Value `{val}` at {cell} is a {} away from the same value",
+                kind.group_name()
+            ),
+            ErrorKind::WrongSum => format!(
+                "Value `{val}` at {cell} makes its cage add up to the wrong sum"
+            ),
+            ErrorKind::BrokenDot => format!(
+                "Value `{val}` at {cell} doesn't match the dot next to it"
+            ),
+            ErrorKind::ConsecutiveNeighbours => format!(
+                "Value `{val}` at {cell} is next to a consecutive value"
+            ),
+            ErrorKind::MissingDot => format!(
+                "Value `{val}` at {cell} is next to a value it needs a dot with"
+            ),
+            ErrorKind::BrokenThermometer => format!(
+                "Value `{val}` at {cell} doesn't increase along its thermometer"
+            ),
+            ErrorKind::BrokenArrow => format!(
+                "Value `{val}` at {cell} makes an arrow add up to the wrong sum"
+            ),
+            ErrorKind::BrokenSandwich => format!(
+                "Value `{val}` at {cell} makes a sandwich add up to the wrong sum"
+            ),
+            ErrorKind::BrokenInequality => format!(
+                "Value `{val}` at {cell} is on the wrong side of a greater-than sign"
+            ),
+            ErrorKind::WrongParity => format!(
+                "Value `{val}` at {cell} doesn't match the cell's even or odd shading"
+            ),
+            ErrorKind::NoSolution => String::from("The puzzle has no solution"),
+            ErrorKind::InvalidFormat => String::from("The puzzle is in an invalid format"),
+            ErrorKind::TimedOut => String::from("Ran out of time before finding a puzzle"),
         };
 
         GridError { details, kind }
     }
+
+    /// Construct an error for input that couldn't be parsed, explained by
+    /// `details`.
+    pub(crate) fn invalid_format(details: String) -> Self {
+        Self::with_details(ErrorKind::InvalidFormat, details)
+    }
+
+    /// Construct an error of `kind` with a custom explanation.
+    pub(super) fn with_details(kind: ErrorKind, details: String) -> Self {
+        GridError { details, kind }
+    }
+
+    /// Returns the error with the grid it happened in, `i` counting from 0,
+    /// named before its explanation, for puzzles made of several grids.
+    pub(super) fn in_grid(self, i: usize) -> Self {
+        let details = format!("Grid {}: {}", i + 1, self.details);
+        GridError { details, ..self }
+    }
+
+    /// Construct an error for the first way `conflict` breaks the rules.
+    fn conflict(conflict: &Conflict) -> Self {
+        match conflict.kind {
+            ErrorKind::BrokenConstraint
+            | ErrorKind::WrongSum
+            | ErrorKind::AntiKnight
+            | ErrorKind::AntiKing
+            | ErrorKind::BrokenDot
+            | ErrorKind::ConsecutiveNeighbours
+            | ErrorKind::MissingDot
+            | ErrorKind::BrokenThermometer
+            | ErrorKind::BrokenArrow
+            | ErrorKind::BrokenSandwich
+            | ErrorKind::BrokenInequality
+            | ErrorKind::WrongParity => Self::with_details(conflict.kind, conflict.to_string()),
+            kind => Self::duplicate(kind, conflict.cells[0], conflict.cells[1], conflict.val),
+        }
+    }
+
+    /// Construct an error for `val` appearing at both `first` and `second`
+    /// in the same group.
+    fn duplicate(kind: ErrorKind, first: Coord, second: Coord, val: u8) -> Self {
+        let (first, second) = (
+            Coord::from((first.row + 1, first.col + 1)),
+            Coord::from((second.row + 1, second.col + 1)),
+        );
+        let details = format!(
+            "Value `{val}` appears at both {first} and {second} in the same {}",
+            kind.group_name()
+        );
+
+        GridError { details, kind }
+    }
 }
 
 impl fmt::Display for GridError {
@@ -223,30 +350,60 @@ impl std::error::Error for GridError {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ErrorKind {
+    AntiKing,
+    AntiKnight,
+    BrokenArrow,
+    BrokenConstraint,
+    BrokenDot,
+    BrokenInequality,
+    BrokenSandwich,
+    BrokenThermometer,
     ClearedClue,
+    ConsecutiveNeighbours,
+    ContradictoryClues,
+    DuplicateInBox,
+    DuplicateInCage,
+    DuplicateInCol,
+    DuplicateInDiagonal,
+    DuplicateInRow,
+    DuplicateInWindow,
+    InvalidFormat,
+    MissingDot,
+    NoSolution,
     NotInCandidates,
+    TimedOut,
     UpdatedClue,
+    WrongParity,
+    WrongSum,
     ZeroCandidates,
 }
 
-/// Helper function to convert row coordinates to box coordinates.
-pub fn row_coords_to_box_coords(cell: Coord) -> Coord {
-    let (row, col) = cell.into();
-    (
-        (row / ORDER) * ORDER + col / ORDER,
-        (row % ORDER) * ORDER + col % ORDER,
-    )
-        .into()
-}
-
-/// Helper function to get the coordinates of all cells in the box containing
-/// `cell`.
-pub fn get_box_coords_containing(cell: Coord) -> [Coord; SIZE] {
-    let (row, col) = cell.into();
-    let (row_offset, col_offset) = ((row / ORDER) * ORDER, (col / ORDER) * ORDER);
-    array::from_fn(|i| (row_offset + i / ORDER, col_offset + i % ORDER).into())
+impl ErrorKind {
+    /// The name of the group a duplicate error refers to, for messages.
+    pub(super) fn group_name(&self) -> &'static str {
+        match self {
+            ErrorKind::DuplicateInRow => "row",
+            ErrorKind::DuplicateInCol => "column",
+            ErrorKind::DuplicateInBox => "box",
+            ErrorKind::DuplicateInDiagonal => "diagonal",
+            ErrorKind::DuplicateInWindow => "window",
+            ErrorKind::DuplicateInCage | ErrorKind::WrongSum => "cage",
+            ErrorKind::BrokenConstraint => "constraint",
+            ErrorKind::AntiKnight => "knight's move",
+            ErrorKind::AntiKing => "king's move",
+            ErrorKind::BrokenDot => "dot",
+            ErrorKind::ConsecutiveNeighbours => "non-consecutive constraint",
+            ErrorKind::MissingDot => "negative constraint",
+            ErrorKind::BrokenThermometer => "thermometer",
+            ErrorKind::BrokenArrow => "arrow",
+            ErrorKind::BrokenSandwich => "sandwich",
+            ErrorKind::BrokenInequality => "greater-than sign",
+            ErrorKind::WrongParity => "even/odd cell",
+            _ => "group",
+        }
+    }
 }
 
 /// Helper function to return a valid grid for testing purposes.
@@ -256,10 +413,11 @@ pub fn get_base_solution() -> GridArray {
     })
 }
 
+/// Displays the grid, with borders between its boxes, which are irregular in
+/// Jigsaw Sudoku, and the cells in shaded regions, such as the diagonals in
+/// Sudoku X, shaded.
 impl fmt::Display for Grid {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let g = DisplayableGrid(self.rows);
-        write!(f, "{g}")?;
-        Ok(())
+        write!(f, "{}", self.rules.display(&DisplayableGrid(self.rows)))
     }
 }
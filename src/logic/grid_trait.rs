@@ -1,27 +1,17 @@
 use std::{array, fmt};
 
-use super::{grid::row_coords_to_box_coords, CELL_WIDTH, ORDER, SIZE};
+use super::{cage::Cage, edge::Edge, layout::Layout, lines::Sandwich, Coord, CELL_WIDTH, SIZE};
 
 /// Trait that allows transforming from a representation of a Sudoku grid with
-/// inner arrays as rows into one where they're columns or boxes.
+/// inner arrays as rows into one where they're columns.
 pub trait GridTrait<T> {
     fn cols(&self) -> [[T; SIZE]; SIZE];
-    fn boxes(&self) -> [[T; SIZE]; SIZE];
 }
 
 impl<T: Clone> GridTrait<T> for [[T; SIZE]; SIZE] {
     fn cols(&self) -> [[T; SIZE]; SIZE] {
         array::from_fn(|i| array::from_fn(|j| self[j][i].clone()))
     }
-
-    fn boxes(&self) -> [[T; SIZE]; SIZE] {
-        array::from_fn(|i| {
-            array::from_fn(|j| {
-                let (box_i, box_j) = row_coords_to_box_coords((i, j).into()).into();
-                self[box_i][box_j].clone()
-            })
-        })
-    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -29,39 +19,296 @@ impl<T: Clone> GridTrait<T> for [[T; SIZE]; SIZE] {
 /// Sudoku grid.
 pub struct DisplayableGrid<T>(pub [[T; SIZE]; SIZE]);
 
+impl<T: fmt::Display> DisplayableGrid<T> {
+    /// Returns a value that displays the grid with the lines and labels of
+    /// `outline`, rather than with borders between the boxes.
+    pub fn outlined<'a>(&'a self, outline: &'a Outline) -> OutlinedGrid<'a, T> {
+        OutlinedGrid {
+            grid: self,
+            outline,
+        }
+    }
+}
+
 impl<T: fmt::Display> fmt::Display for DisplayableGrid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.outlined(&Outline::new(Layout::standard())))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// Enum representing the line drawn between two neighbouring cells.
+enum Line {
+    None,
+    /// Between cages.
+    Dotted,
+    /// Between regions of the layout.
+    Solid,
+}
+
+impl Line {
+    /// The character used to draw the line between cells side by side.
+    fn vertical(self) -> char {
+        match self {
+            Line::None => ' ',
+            Line::Dotted => ':',
+            Line::Solid => '|',
+        }
+    }
+
+    /// The character used to draw the line between cells one above the other.
+    fn horizontal(self) -> char {
+        match self {
+            Line::None => ' ',
+            Line::Dotted => '.',
+            Line::Solid => '-',
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Struct describing what's drawn around the cells of a displayed grid:
+/// solid lines between the regions of a layout, dotted lines between cages,
+/// labels, such as cage sums, on the line above a cell, marks, such as Kropki
+/// dots, between two neighbouring cells, and labels outside the grid for a
+/// row or column, such as sandwich sums.
+pub struct Outline {
+    layout: Layout,
+    /// The index of the cage each cell is in, if any.
+    cages: [[Option<usize>; SIZE]; SIZE],
+    labels: Vec<(Coord, String)>,
+    /// Marks between two cells, which touch along a side or at a corner,
+    /// with the cell above or left first.
+    marks: Vec<((Coord, Coord), char)>,
+    row_labels: Vec<(usize, String)>,
+    col_labels: Vec<(usize, String)>,
+}
+
+impl Outline {
+    /// Construct an outline with lines between the regions of `layout`.
+    pub fn new(layout: Layout) -> Self {
+        Outline {
+            layout,
+            cages: [[None; SIZE]; SIZE],
+            labels: Vec::new(),
+            marks: Vec::new(),
+            row_labels: Vec::new(),
+            col_labels: Vec::new(),
+        }
+    }
+
+    /// Add dotted lines around `cages`, with each sum shown above the first
+    /// cell of its cage.
+    pub fn with_cages(mut self, cages: &[Cage]) -> Self {
+        for (i, cage) in cages.iter().enumerate() {
+            for cell in cage.cells.iter() {
+                self.cages[cell.row][cell.col] = Some(i);
+            }
+            if let Some(&first) = cage.cells.first() {
+                self.labels.push((first, cage.sum.to_string()));
+            }
+        }
+        self
+    }
+
+    /// Add the dots of `dots` between their cells. Edges without a dot are
+    /// left out.
+    pub fn with_dots(self, dots: &[&Edge]) -> Self {
+        self.with_marks(
+            dots.iter()
+                .filter_map(|edge| Some((edge.cells, edge.relation.dot()?))),
+        )
+    }
+
+    /// Add each mark between its two cells, which can touch along a side or
+    /// at a corner. A mark between cells that already have one isn't shown.
+    pub fn with_marks(mut self, marks: impl IntoIterator<Item = ((Coord, Coord), char)>) -> Self {
+        self.marks.extend(
+            marks
+                .into_iter()
+                .map(|((a, b), mark)| ((a.min(b), a.max(b)), mark)),
+        );
+        self
+    }
+
+    /// Add each sandwich sum outside the grid, to the right of its row or
+    /// below its column.
+    pub fn with_sandwiches(mut self, sandwiches: &[&Sandwich]) -> Self {
+        for sandwich in sandwiches.iter() {
+            let first = sandwich.cells[0];
+            match sandwich.is_row() {
+                true => self.row_labels.push((first.row, sandwich.sum.to_string())),
+                false => self.col_labels.push((first.col, sandwich.sum.to_string())),
+            }
+        }
+        self
+    }
+
+    /// Returns the line drawn between the neighbouring cells `a` and `b`.
+    fn line(&self, a: Coord, b: Coord) -> Line {
+        let cage = |c: Coord| self.cages[c.row][c.col];
+        if self.layout.is_border(a, b) {
+            Line::Solid
+        } else if cage(a) != cage(b) {
+            Line::Dotted
+        } else {
+            Line::None
+        }
+    }
+
+    /// Returns the label shown above `cell`, if it has one.
+    fn label(&self, cell: Coord) -> Option<&str> {
+        self.labels
+            .iter()
+            .find(|(c, _)| *c == cell)
+            .map(|(_, label)| label.as_str())
+    }
+
+    /// Returns the mark drawn between the neighbouring cells `a` and `b`, if
+    /// they have one, where `a` is above or left of `b`.
+    fn mark(&self, a: Coord, b: Coord) -> Option<char> {
+        self.marks
+            .iter()
+            .find(|(cells, _)| *cells == (a, b))
+            .map(|&(_, mark)| mark)
+    }
+
+    /// Returns the mark drawn where the corners of `cell` and the cells
+    /// right, below and diagonally below and right of it meet, if there's one
+    /// between two of them that only touch at that corner.
+    fn corner_mark(&self, cell: Coord) -> Option<char> {
+        let (i, j) = cell.into();
+        self.mark(cell, (i + 1, j + 1).into())
+            .or_else(|| self.mark((i, j + 1).into(), (i + 1, j).into()))
+    }
+}
+
+/// Struct for displaying a `DisplayableGrid` with the lines and labels of an
+/// `Outline`, created by `DisplayableGrid::outlined`.
+pub struct OutlinedGrid<'a, T> {
+    grid: &'a DisplayableGrid<T>,
+    outline: &'a Outline,
+}
+
+/// Displays the grid with a column of separators after every column, and a
+/// line of separators after every row, that has a line or mark next to it
+/// anywhere. For the boxes of classic Sudoku, that's the usual `|` and `-`
+/// lines. Labels replace the line above their cell, and labels in the first
+/// row get a line of their own. Marks are drawn over the line between their
+/// cells, under the digits for cells one above the other, and where the
+/// lines cross for cells touching at a corner. Labels for a row follow it,
+/// and labels for a column go on a line below the grid.
+impl<T: fmt::Display> fmt::Display for OutlinedGrid<'_, T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let cell_width = CELL_WIDTH as usize;
-        let box_width: usize = cell_width * ORDER + cell_width - 1;
         let gutter_width = cell_width * 2;
+        let line = |a: (usize, usize), b: (usize, usize)| self.outline.line(a.into(), b.into());
+        let label = |i: usize, j: usize| self.outline.label((i, j).into());
+        let mark = |a: (usize, usize), b: (usize, usize)| self.outline.mark(a.into(), b.into());
+        let corner = |i: usize, j: usize| self.outline.corner_mark((i, j).into());
+        let col_sep = |j: usize| {
+            j + 1 < SIZE
+                && (0..SIZE).any(|i| {
+                    line((i, j), (i, j + 1)) != Line::None
+                        || mark((i, j), (i, j + 1)).is_some()
+                        || corner(i, j).is_some()
+                })
+        };
+        let row_sep = |i: usize| {
+            i + 1 < SIZE
+                && (0..SIZE).any(|j| {
+                    line((i, j), (i + 1, j)) != Line::None
+                        || mark((i, j), (i + 1, j)).is_some()
+                        || corner(i, j).is_some()
+                })
+        };
+        let side_label = |labels: &[(usize, String)], k: usize| {
+            labels
+                .iter()
+                .find(|(l, _)| *l == k)
+                .map(|(_, label)| label.clone())
+        };
+
+        if (0..SIZE).any(|j| label(0, j).is_some()) {
+            write!(f, "{:^gutter_width$}", " ")?;
+            for j in 0..SIZE {
+                write!(f, "{:>cell_width$}", label(0, j).unwrap_or_default())?;
+                if col_sep(j) {
+                    write!(f, "{:>cell_width$}", " ")?;
+                }
+            }
+            writeln!(f)?;
+        }
 
-        for (i, row) in self.0.iter().enumerate() {
+        for (i, row) in self.grid.0.iter().enumerate() {
             write!(f, "{:^gutter_width$}", i + 1)?;
             for (j, cell) in row.iter().enumerate() {
                 write!(f, "{:>cell_width$}", cell)?;
-                if j != SIZE - 1 && j % ORDER == ORDER - 1 {
-                    write!(f, "{:>cell_width$}", "|")?;
+                if col_sep(j) {
+                    let sep = mark((i, j), (i, j + 1))
+                        .unwrap_or_else(|| line((i, j), (i, j + 1)).vertical());
+                    write!(f, "{sep:>cell_width$}")?;
                 }
             }
+            if let Some(label) = side_label(&self.outline.row_labels, i) {
+                write!(f, "{:>gutter_width$}", label)?;
+            }
 
             writeln!(f)?;
+            if i == SIZE - 1 && !self.outline.col_labels.is_empty() {
+                write!(f, "{:^gutter_width$}", " ")?;
+                for j in 0..SIZE {
+                    let label = side_label(&self.outline.col_labels, j).unwrap_or_default();
+                    write!(f, "{label:>cell_width$}")?;
+                    if col_sep(j) {
+                        write!(f, "{:>cell_width$}", " ")?;
+                    }
+                }
+                writeln!(f)?;
+            }
             if i == SIZE - 1 {
                 // offset by cell width to fit the numbers down the side
                 write!(f, "\n{:^gutter_width$}", " ")?;
-                for col_index in 1..=SIZE {
-                    write!(f, "{:>cell_width$}", col_index)?;
-                    if col_index != SIZE && (col_index - 1) % ORDER == ORDER - 1 {
-                        write!(f, "{:>cell_width$}", "|")?;
+                for j in 0..SIZE {
+                    write!(f, "{:>cell_width$}", j + 1)?;
+                    if col_sep(j) {
+                        write!(f, "{:>cell_width$}", line((i, j), (i, j + 1)).vertical())?;
                     }
                 }
-            } else if i % ORDER == ORDER - 1 {
+            } else if row_sep(i) {
                 // offset to fit the numbers down the side
                 write!(f, "{:^gutter_width$}", " ")?;
-                let line = format!("{:->box_width$}", "-");
-                for _ in 1..ORDER {
-                    write!(f, "{line}+")?;
+                for j in 0..SIZE {
+                    let below = line((i, j), (i + 1, j));
+                    let rule = below.horizontal().to_string();
+                    match (label(i + 1, j), mark((i, j), (i + 1, j))) {
+                        (Some(label), _) => write!(f, "{label:>cell_width$}")?,
+                        (None, Some(mark)) => write!(f, "{}{mark}", rule.repeat(cell_width - 1))?,
+                        (None, None) => write!(f, "{}", rule.repeat(cell_width))?,
+                    }
+                    if j == SIZE - 1 {
+                        writeln!(f, "{}", below.horizontal())?;
+                    } else if col_sep(j) {
+                        let arms = [
+                            below,
+                            line((i, j + 1), (i + 1, j + 1)),
+                            line((i, j), (i, j + 1)),
+                            line((i + 1, j), (i + 1, j + 1)),
+                        ];
+                        let across = arms[..2].contains(&Line::Solid);
+                        let down = arms[2..].contains(&Line::Solid);
+                        let junction = match (down, across) {
+                            (true, true) => '+',
+                            (true, false) => '|',
+                            (false, true) => '-',
+                            (false, false) if arms.contains(&Line::Dotted) => '.',
+                            (false, false) => ' ',
+                        };
+                        let junction = corner(i, j).unwrap_or(junction);
+                        let line = below.horizontal().to_string().repeat(cell_width - 1);
+                        write!(f, "{line}{junction}")?;
+                    }
                 }
-                writeln!(f, "{line}")?;
             }
         }
         Ok(())
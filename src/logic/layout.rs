@@ -0,0 +1,213 @@
+use std::{array, fmt, str::FromStr};
+
+use rand::{seq::SliceRandom, Rng};
+
+use super::{grid::GridError, Coord, ORDER, SIZE};
+
+/// Number of times `Layout::random` tries to swap a pair of cells between
+/// neighbouring regions, enough to leave no trace of the boxes it starts from.
+const LAYOUT_SWAPS: usize = 200;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// Struct representing how a grid is split into `SIZE` connected regions of
+/// `SIZE` cells, by holding the index of the region each cell is in. Classic
+/// Sudoku uses the boxes, and Jigsaw Sudoku uses irregular regions.
+pub struct Layout([[u8; SIZE]; SIZE]);
+
+impl Layout {
+    /// The layout of classic Sudoku, where the regions are the boxes.
+    pub fn standard() -> Self {
+        Layout(array::from_fn(|i| {
+            array::from_fn(|j| ((i / ORDER) * ORDER + j / ORDER) as u8)
+        }))
+    }
+
+    /// Generate a random layout, using `rng` as the only source of randomness.
+    /// Starting from the boxes, cells are swapped between neighbouring regions
+    /// as long as both stay connected. Not every layout has a solution.
+    pub fn random<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        let mut layout = Self::standard();
+        for _ in 0..LAYOUT_SWAPS {
+            layout.try_swap(rng);
+        }
+        layout
+    }
+
+    /// Construct the layout with the cells of `regions[i]` in region `i`. Cells
+    /// missing from `regions` are put in the first region.
+    pub(super) fn from_regions(regions: &[&[Coord]]) -> Self {
+        let mut layout = Layout([[0; SIZE]; SIZE]);
+        for (i, cells) in regions.iter().enumerate() {
+            for cell in cells.iter() {
+                layout.0[cell.row][cell.col] = i as u8;
+            }
+        }
+        layout
+    }
+
+    /// Returns the index of the region containing `cell`.
+    pub fn region(&self, cell: Coord) -> usize {
+        self.0[cell.row][cell.col] as usize
+    }
+
+    /// Returns the coordinates of the cells in each region, in region order.
+    pub fn regions(&self) -> Vec<Vec<Coord>> {
+        let mut regions = vec![Vec::new(); SIZE];
+        for (i, row) in self.0.iter().enumerate() {
+            for (j, &region) in row.iter().enumerate() {
+                regions[region as usize].push((i, j).into());
+            }
+        }
+        regions
+    }
+
+    /// Returns `true` if `a` and `b` are in different regions, so a border is
+    /// drawn between them.
+    pub fn is_border(&self, a: Coord, b: Coord) -> bool {
+        self.region(a) != self.region(b)
+    }
+
+    /// Move a random cell on a border into the neighbouring region, and a
+    /// cell of that region bordering the first one back, undoing the swap if
+    /// either region is no longer connected.
+    fn try_swap<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        let borders: Vec<(Coord, Coord)> = (0..SIZE.pow(2))
+            .map(|i| Coord::from((i / SIZE, i % SIZE)))
+            .flat_map(|a| neighbours(a).into_iter().map(move |b| (a, b)))
+            .filter(|&(a, b)| self.is_border(a, b))
+            .collect();
+        let Some(&(a, b)) = borders.choose(rng) else {
+            return;
+        };
+        let (from, to) = (self.region(a), self.region(b));
+        let previous = self.0;
+
+        self.0[a.row][a.col] = to as u8;
+        let back: Vec<Coord> = self.regions()[to]
+            .iter()
+            .copied()
+            .filter(|&c| c != a && neighbours(c).iter().any(|&n| self.region(n) == from))
+            .collect();
+        let Some(&c) = back.choose(rng) else {
+            self.0 = previous;
+            return;
+        };
+        self.0[c.row][c.col] = from as u8;
+
+        if !self.is_connected(from) || !self.is_connected(to) {
+            self.0 = previous;
+        }
+    }
+
+    /// Returns `true` if every cell of `region` can be reached from every
+    /// other without leaving it.
+    fn is_connected(&self, region: usize) -> bool {
+        let cells = &self.regions()[region];
+        let Some(&start) = cells.first() else {
+            return true;
+        };
+
+        let mut seen = vec![start];
+        let mut stack = vec![start];
+        while let Some(cell) = stack.pop() {
+            for n in neighbours(cell) {
+                if self.region(n) == region && !seen.contains(&n) {
+                    seen.push(n);
+                    stack.push(n);
+                }
+            }
+        }
+
+        seen.len() == cells.len()
+    }
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+/// Formats the layout as a single line, with each region given a letter from
+/// `A`, in row order.
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for &region in self.0.iter().flatten() {
+            write!(f, "{}", (b'A' + region) as char)?;
+        }
+        Ok(())
+    }
+}
+
+/// Enable parsing a layout from `SIZE` squared characters in row order,
+/// ignoring whitespace, where cells in the same region share a character.
+/// Returns an error unless there are `SIZE` connected regions of `SIZE` cells.
+impl FromStr for Layout {
+    type Err = GridError;
+
+    fn from_str(s: &str) -> Result<Self, GridError> {
+        let chars: Vec<char> = s.chars().filter(|c| !c.is_whitespace()).collect();
+        if chars.len() != SIZE.pow(2) {
+            return Err(GridError::invalid_format(format!(
+                "Expected {} region letters but found {}",
+                SIZE.pow(2),
+                chars.len()
+            )));
+        }
+
+        let mut labels: Vec<char> = Vec::new();
+        let mut layout = Layout([[0; SIZE]; SIZE]);
+        for (i, &c) in chars.iter().enumerate() {
+            let region = match labels.iter().position(|&l| l == c) {
+                Some(region) => region,
+                None if labels.len() < SIZE => {
+                    labels.push(c);
+                    labels.len() - 1
+                }
+                None => {
+                    return Err(GridError::invalid_format(format!(
+                        "Found more than {SIZE} regions, `{c}` is one too many"
+                    )))
+                }
+            };
+            layout.0[i / SIZE][i % SIZE] = region as u8;
+        }
+
+        for (region, cells) in layout.regions().iter().enumerate() {
+            if cells.len() != SIZE {
+                return Err(GridError::invalid_format(format!(
+                    "Region `{}` has {} cells, but needs {SIZE}",
+                    labels[region],
+                    cells.len()
+                )));
+            } else if !layout.is_connected(region) {
+                return Err(GridError::invalid_format(format!(
+                    "Region `{}` isn't connected",
+                    labels[region]
+                )));
+            }
+        }
+
+        Ok(layout)
+    }
+}
+
+/// Helper function to get the cells above, below, left and right of `cell`
+/// that are inside the grid.
+pub(super) fn neighbours(cell: Coord) -> Vec<Coord> {
+    let (row, col) = cell.into();
+    let mut cells = Vec::with_capacity(4);
+    if row > 0 {
+        cells.push((row - 1, col).into());
+    }
+    if row + 1 < SIZE {
+        cells.push((row + 1, col).into());
+    }
+    if col > 0 {
+        cells.push((row, col - 1).into());
+    }
+    if col + 1 < SIZE {
+        cells.push((row, col + 1).into());
+    }
+    cells
+}
@@ -0,0 +1,184 @@
+use std::str::FromStr;
+
+use super::{
+    format_cages, format_dots, format_lines, grid::GridError, parse_cages, parse_dots, parse_lines,
+    Cell, Grid, GridArray, Layout, Rules, Toggles, Variant, SIZE,
+};
+
+/// Characters accepted as an empty cell in line format.
+const EMPTY_CHARS: [char; 4] = ['.', '0', '_', '-'];
+
+/// Parse a puzzle in line format: one character per cell, reading the grid
+/// row by row. Values are written as base-36 digits, so `1`-`9` followed by
+/// `a`, `b`, ... for grids larger than 9x9, and empty cells as any of `.`,
+/// `0`, `_` or `-`. Filled cells become clues.
+pub fn parse_line(line: &str) -> Result<GridArray, GridError> {
+    let chars: Vec<char> = line.trim().chars().collect();
+    if chars.len() != SIZE.pow(2) {
+        return Err(GridError::invalid_format(format!(
+            "Expected {} cells but found {}",
+            SIZE.pow(2),
+            chars.len()
+        )));
+    }
+
+    let mut rows = [[Cell::Empty; SIZE]; SIZE];
+    for (i, c) in chars.into_iter().enumerate() {
+        if EMPTY_CHARS.contains(&c) {
+            continue;
+        }
+
+        match c.to_digit(36) {
+            Some(n) if (1..=SIZE as u32).contains(&n) => {
+                rows[i / SIZE][i % SIZE] = Cell::Clue(n as u8)
+            }
+            _ => {
+                return Err(GridError::invalid_format(format!(
+                    "Unexpected character `{c}` at position {}",
+                    i + 1
+                )))
+            }
+        }
+    }
+
+    Ok(rows)
+}
+
+/// Write `rows` in line format, using `.` for empty cells.
+pub fn to_line(rows: &GridArray) -> String {
+    let to_char = |cell: &Cell| match cell {
+        Cell::Clue(n) | Cell::Filled(n) => char::from_digit(*n as u32, 36).unwrap_or('?'),
+        Cell::Empty => '.',
+    };
+    rows.iter().flatten().map(to_char).collect()
+}
+
+/// Character separating a puzzle's cells from its layout in line format.
+const LAYOUT_SEPARATOR: char = ':';
+
+/// Character separating a puzzle's cages from the rest of it, and the cage
+/// map from the sums, in line format.
+const CAGE_SEPARATOR: char = ';';
+
+/// Character separating a puzzle's Kropki dots from the rest of it in line
+/// format.
+const DOT_SEPARATOR: char = '=';
+
+/// Character separating a puzzle's thermometers, arrows and sandwich sums
+/// from the rest of it in line format.
+const LINES_SEPARATOR: char = '+';
+
+/// Parse a puzzle following the rules of `variant`, with the constraints in
+/// `toggles` switched on, in line format. The cells
+/// can be followed by `:` and the layout of the boxes, as region letters (see
+/// `Layout`), which Jigsaw puzzles need. Killer puzzles then need `;`, the
+/// cage map, `;` and the cage sums (see `parse_cages`), and Kropki puzzles
+/// need `=` and the dots (see `parse_dots`). Any puzzle can end with `+` and
+/// thermometers, arrows and sandwich sums (see `parse_lines`).
+pub fn parse_puzzle(line: &str, variant: Variant, toggles: Toggles) -> Result<Grid, GridError> {
+    let (rows, rules) = parse_parts(line, variant, toggles)?;
+    Grid::from_rows_with_rules(rows, rules)
+}
+
+/// Parse a puzzle in line format as `parse_puzzle` does, but return its cells
+/// and rules without checking that the clues follow the rules.
+pub fn parse_parts(
+    line: &str,
+    variant: Variant,
+    toggles: Toggles,
+) -> Result<(GridArray, Rules), GridError> {
+    let (line, lines) = match line.trim().split_once(LINES_SEPARATOR) {
+        Some((line, lines)) => (line, parse_lines(lines)?),
+        None => (line.trim(), Vec::new()),
+    };
+    let (line, dots) = match line.split_once(DOT_SEPARATOR) {
+        Some((line, dots)) => (line, Some(parse_dots(dots)?)),
+        None => (line, None),
+    };
+    let (line, cages) = match line.split_once(CAGE_SEPARATOR) {
+        Some((line, cages)) => match cages.split_once(CAGE_SEPARATOR) {
+            Some((map, sums)) => (line, Some(parse_cages(map, sums)?)),
+            None => {
+                return Err(GridError::invalid_format(format!(
+                    "Expected the cage sums after the cage map, separated by `{CAGE_SEPARATOR}`"
+                )))
+            }
+        },
+        None => (line, None),
+    };
+    let (cells, layout) = match line.split_once(LAYOUT_SEPARATOR) {
+        Some((cells, layout)) => (cells, Some(layout.parse::<Layout>()?)),
+        None => (line, None),
+    };
+
+    let rules = match layout {
+        Some(layout) => variant.rules().with_layout(&layout),
+        None if variant == Variant::Jigsaw => {
+            return Err(GridError::invalid_format(format!(
+            "Jigsaw puzzles need their layout after the cells, separated by `{LAYOUT_SEPARATOR}`"
+        )))
+        }
+        None => variant.rules(),
+    };
+    let rules = match cages {
+        Some(cages) => cages.into_iter().fold(rules, Rules::with_cage),
+        None if variant == Variant::Killer => {
+            return Err(GridError::invalid_format(format!(
+                "Killer puzzles need their cages after the cells, separated by `{CAGE_SEPARATOR}`"
+            )))
+        }
+        None => rules,
+    };
+    let rules = match dots {
+        Some((dots, negative)) => rules.with_dots(&dots, negative),
+        None if variant == Variant::Kropki => {
+            return Err(GridError::invalid_format(format!(
+                "Kropki puzzles need their dots after the cells, separated by `{DOT_SEPARATOR}`"
+            )))
+        }
+        None => rules,
+    };
+    let rules = lines.into_iter().fold(rules, Rules::with_constraint);
+    Ok((parse_line(cells)?, toggles.apply(rules)))
+}
+
+/// Enable parsing a `Grid` from a string in line format, with an optional
+/// layout.
+impl FromStr for Grid {
+    type Err = GridError;
+
+    fn from_str(s: &str) -> Result<Self, GridError> {
+        parse_puzzle(s, Variant::Standard, Toggles::default())
+    }
+}
+
+impl Grid {
+    /// Write the grid in line format, followed by its layout if the boxes are
+    /// irregular, and its cages, dots, thermometers, arrows and sandwich sums
+    /// if it has any. See `parse_puzzle` for the format.
+    pub fn to_line(&self) -> String {
+        let mut line = to_line(self.rows());
+        let layout = self.rules().layout();
+        if layout != Layout::standard() {
+            line = format!("{line}{LAYOUT_SEPARATOR}{layout}");
+        }
+
+        let cages: Vec<_> = self.rules().cages().into_iter().cloned().collect();
+        if !cages.is_empty() {
+            let (map, sums) = format_cages(&cages);
+            line = format!("{line}{CAGE_SEPARATOR}{map}{CAGE_SEPARATOR}{sums}");
+        }
+
+        let dots = self.rules().dots();
+        let negative = self.rules().has_negative_dots();
+        if !dots.is_empty() || negative {
+            line = format!("{line}{DOT_SEPARATOR}{}", format_dots(&dots, negative));
+        }
+
+        let lines = self.rules().lines();
+        if !lines.is_empty() {
+            line = format!("{line}{LINES_SEPARATOR}{}", format_lines(&lines));
+        }
+        line
+    }
+}
@@ -0,0 +1,373 @@
+use std::cmp::Ordering;
+
+use super::{
+    edge::{Edge, Relation},
+    grid::GridError,
+    rules::{Constraint, Parity},
+    Coord, GridArray, SIZE,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Struct representing a thermometer: a line of cells, starting from its bulb,
+/// whose values strictly increase along it.
+pub struct Thermometer {
+    pub cells: Vec<Coord>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Struct representing an arrow: a line of cells whose values add up to the
+/// value in the circle it starts from. Values can repeat along an arrow unless
+/// a region rules it out.
+pub struct Arrow {
+    pub circle: Coord,
+    pub cells: Vec<Coord>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Struct representing a sandwich sum, given outside a row or column: the
+/// values between the lowest and highest values in it add up to `sum`. The
+/// cells are the whole row or column, in order.
+pub struct Sandwich {
+    pub cells: Vec<Coord>,
+    pub sum: u32,
+}
+
+impl Thermometer {
+    /// Returns the lowest and highest value each cell along the thermometer
+    /// can hold, given `bounds`, the lowest and highest value each cell could
+    /// hold on its own.
+    pub(super) fn limits(bounds: &[(u8, u8)]) -> Vec<(u8, u8)> {
+        let mut limits = bounds.to_vec();
+        for i in 1..limits.len() {
+            limits[i].0 = limits[i].0.max(limits[i - 1].0 + 1);
+        }
+        for i in (0..limits.len().saturating_sub(1)).rev() {
+            limits[i].1 = limits[i].1.min(limits[i + 1].1.saturating_sub(1));
+        }
+        limits
+    }
+
+    /// Returns the marks joining each cell of the thermometer to the one
+    /// before, for displaying it. Steps along a row or column are drawn as
+    /// the sign comparing the two values, so `<` and `>` side by side, and
+    /// `^` and `v` for when the value above is smaller or bigger. Diagonal
+    /// steps are drawn as `\` or `/`.
+    pub fn marks(&self) -> Vec<((Coord, Coord), char)> {
+        self.cells
+            .windows(2)
+            .map(|w| {
+                let (a, b) = (w[0], w[1]);
+                let mark = match (a.row.cmp(&b.row), a.col.cmp(&b.col)) {
+                    (Ordering::Equal, Ordering::Less) => '<',
+                    (Ordering::Equal, _) => '>',
+                    (Ordering::Less, Ordering::Equal) => '^',
+                    (_, Ordering::Equal) => 'v',
+                    (rows, cols) if rows == cols => '\\',
+                    _ => '/',
+                };
+                ((a, b), mark)
+            })
+            .collect()
+    }
+
+    /// Returns the first two filled cells in `rows` whose values don't leave
+    /// room for the cells between them to increase.
+    pub(super) fn broken_by(&self, rows: &GridArray) -> Option<(Coord, Coord)> {
+        let filled: Vec<(usize, Coord, u8)> = self
+            .cells
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &c)| Some((i, c, rows[c.row][c.col].value()?)))
+            .collect();
+        filled
+            .windows(2)
+            .find(|w| (w[1].2 as usize) < w[0].2 as usize + (w[1].0 - w[0].0))
+            .map(|w| (w[0].1, w[1].1))
+    }
+}
+
+impl Arrow {
+    /// Returns the marks joining each cell of the arrow to the one before,
+    /// starting from the circle, for displaying it.
+    pub fn marks(&self) -> Vec<((Coord, Coord), char)> {
+        let cells: Vec<Coord> = [self.circle]
+            .into_iter()
+            .chain(self.cells.clone())
+            .collect();
+        cells
+            .windows(2)
+            .map(|w| ((w[0], w[1]), ARROW_MARK))
+            .collect()
+    }
+}
+
+impl Sandwich {
+    /// Construct the sandwich sum for row `i`.
+    pub fn row(i: usize, sum: u32) -> Self {
+        Sandwich {
+            cells: (0..SIZE).map(|j| (i, j).into()).collect(),
+            sum,
+        }
+    }
+
+    /// Construct the sandwich sum for column `j`.
+    pub fn column(j: usize, sum: u32) -> Self {
+        Sandwich {
+            cells: (0..SIZE).map(|i| (i, j).into()).collect(),
+            sum,
+        }
+    }
+
+    /// Returns `true` if the sandwich is for a row rather than a column.
+    pub fn is_row(&self) -> bool {
+        self.cells.iter().all(|c| c.row == self.cells[0].row)
+    }
+
+    /// Returns the positions along the sandwich of the lowest and highest
+    /// values in `rows`, in order, or `None` if either hasn't been placed yet.
+    pub(super) fn ends(&self, rows: &GridArray) -> Option<(usize, usize)> {
+        let position = |n: usize| {
+            self.cells
+                .iter()
+                .position(|c| rows[c.row][c.col].value() == Some(n as u8))
+        };
+        let (a, b) = (position(1)?, position(SIZE)?);
+        Some((a.min(b), a.max(b)))
+    }
+
+    /// Returns `true` if the cells between positions `a` and `b` along the
+    /// sandwich could still add up to its sum, given the values in `rows`.
+    /// The empty cells are only checked against the smallest and biggest
+    /// values not used elsewhere in the row or column.
+    pub(super) fn could_fill(&self, rows: &GridArray, a: usize, b: usize) -> bool {
+        let value = |c: &Coord| rows[c.row][c.col].value();
+        let used: Vec<u8> = self.cells.iter().filter_map(value).collect();
+        let between = &self.cells[a + 1..b];
+        let placed: u32 = between.iter().filter_map(value).map(u32::from).sum();
+        let empty = between.iter().filter(|c| value(c).is_none()).count();
+        let Some(left) = self.sum.checked_sub(placed) else {
+            return false;
+        };
+
+        let free: Vec<u32> = (2..SIZE as u8)
+            .filter(|n| !used.contains(n))
+            .map(u32::from)
+            .collect();
+        if free.len() < empty {
+            return false;
+        }
+        let least: u32 = free[..empty].iter().sum();
+        let most: u32 = free[free.len() - empty..].iter().sum();
+        (least..=most).contains(&left)
+    }
+}
+
+/// Character joining the cells of an arrow when it's displayed.
+const ARROW_MARK: char = '#';
+
+/// Character separating the constraints in line format.
+const SEPARATOR: char = '/';
+
+/// Character separating the row or column of a sandwich sum from the sum in
+/// line format.
+const SUM_MARK: char = '=';
+
+/// Parse thermometers, arrows, sandwich sums, greater-than signs and even and
+/// odd cells separated by `/`. Cells are written as their row then their
+/// column, each a base-36 digit counting from 1, and each constraint starts
+/// with a letter for its kind:
+///
+/// - `t` and its cells from the bulb, e.g. `t112233` for a thermometer from
+///   the top-left corner down the diagonal.
+/// - `a`, its circle and the cells along it, e.g. `a5556` for an arrow from
+///   the centre cell to its right.
+/// - `r` or `c`, the row or column, `=` and the sum, e.g. `r1=15`.
+/// - `g`, a cell and a neighbouring cell with a smaller value, e.g. `g1112`
+///   for a greater-than sign between the first two cells of the top row.
+/// - `e` or `o` and any number of cells that hold even or odd values, e.g.
+///   `e1199`.
+pub fn parse_lines(s: &str) -> Result<Vec<Constraint>, GridError> {
+    let mut constraints = Vec::new();
+    for item in s.split(SEPARATOR).map(str::trim) {
+        let mut chars = item.chars();
+        let kind = chars.next().unwrap_or_default().to_ascii_lowercase();
+        let rest = chars.as_str();
+        match kind {
+            'e' | 'o' => {
+                let parity = if kind == 'e' {
+                    Parity::Even
+                } else {
+                    Parity::Odd
+                };
+                let cells = parse_cells(rest, 1)?;
+                constraints.extend(cells.into_iter().map(|c| Constraint::Parity(c, parity)));
+            }
+            _ if item.is_empty() => {}
+            _ => constraints.push(parse_constraint(item, kind, rest)?),
+        }
+    }
+    Ok(constraints)
+}
+
+/// Helper function to parse the constraint `item` other than even and odd
+/// cells, made of the letter `kind` followed by `rest`.
+fn parse_constraint(item: &str, kind: char, rest: &str) -> Result<Constraint, GridError> {
+    match kind {
+        't' => {
+            let cells = parse_path(rest, 2)?;
+            Ok(Constraint::Thermometer(Thermometer { cells }))
+        }
+        'a' => {
+            let cells = parse_path(rest, 2)?;
+            Ok(Constraint::Arrow(Arrow {
+                circle: cells[0],
+                cells: cells[1..].to_vec(),
+            }))
+        }
+        'r' | 'c' => {
+            let (index, sum) = rest.split_once(SUM_MARK).ok_or_else(|| {
+                GridError::invalid_format(format!("Expected `{SUM_MARK}` and a sum in `{item}`"))
+            })?;
+            let index = parse_index(index)?;
+            let sum = sum.parse::<u32>().map_err(|_| {
+                GridError::invalid_format(format!("Unexpected sum `{sum}` in `{item}`"))
+            })?;
+            let sandwich = match kind {
+                'r' => Sandwich::row(index, sum),
+                _ => Sandwich::column(index, sum),
+            };
+            Ok(Constraint::Sandwich(sandwich))
+        }
+        'g' => match parse_path(rest, 2)?[..] {
+            [a, b] if a.row.abs_diff(b.row) + a.col.abs_diff(b.col) == 1 => {
+                Ok(Constraint::Edge(Edge::new(a, b, Relation::Greater)))
+            }
+            _ => Err(GridError::invalid_format(format!(
+                "Expected two cells side by side or one above the other in `{item}`"
+            ))),
+        },
+        _ => Err(GridError::invalid_format(format!(
+            "Unexpected constraint `{item}`, expected one starting with t, a, r, c, g, \
+                     e or o"
+        ))),
+    }
+}
+
+/// Write the thermometers, arrows, sandwich sums, greater-than signs and even
+/// and odd cells in `constraints` in the format read by `parse_lines`,
+/// skipping any other constraints. The even cells and the odd cells are each
+/// written together, after the rest.
+pub fn format_lines(constraints: &[&Constraint]) -> String {
+    let cells = |cells: &[Coord]| -> String {
+        cells
+            .iter()
+            .flat_map(|c| [index_char(c.row), index_char(c.col)])
+            .collect()
+    };
+    let parity_cells = |parity: Parity| -> Vec<Coord> {
+        constraints
+            .iter()
+            .filter_map(|constraint| match constraint {
+                &&Constraint::Parity(cell, p) if p == parity => Some(cell),
+                _ => None,
+            })
+            .collect()
+    };
+    let mut items: Vec<String> = constraints
+        .iter()
+        .filter_map(|constraint| match constraint {
+            Constraint::Thermometer(thermo) => Some(format!("t{}", cells(&thermo.cells))),
+            Constraint::Arrow(arrow) => Some(format!(
+                "a{}{}",
+                cells(&[arrow.circle]),
+                cells(&arrow.cells)
+            )),
+            Constraint::Sandwich(sandwich) => {
+                let (kind, index) = match sandwich.is_row() {
+                    true => ('r', sandwich.cells[0].row),
+                    false => ('c', sandwich.cells[0].col),
+                };
+                Some(format!(
+                    "{kind}{}{SUM_MARK}{}",
+                    index_char(index),
+                    sandwich.sum
+                ))
+            }
+            Constraint::Edge(edge) => {
+                let (a, b) = edge.cells;
+                match edge.relation {
+                    Relation::Greater => Some(format!("g{}", cells(&[a, b]))),
+                    Relation::Less => Some(format!("g{}", cells(&[b, a]))),
+                    _ => None,
+                }
+            }
+            _ => None,
+        })
+        .collect();
+    for (kind, parity) in [('e', Parity::Even), ('o', Parity::Odd)] {
+        let parity_cells = parity_cells(parity);
+        if !parity_cells.is_empty() {
+            items.push(format!("{kind}{}", cells(&parity_cells)));
+        }
+    }
+    items.join(&SEPARATOR.to_string())
+}
+
+/// Helper function to parse a line of at least `min_len` cells, each one
+/// touching the last, including diagonally.
+fn parse_path(s: &str, min_len: usize) -> Result<Vec<Coord>, GridError> {
+    let cells = parse_cells(s, min_len)?;
+    for w in cells.windows(2) {
+        if w[0].row.abs_diff(w[1].row) > 1 || w[0].col.abs_diff(w[1].col) > 1 {
+            return Err(GridError::invalid_format(format!(
+                "The cells in `{s}` don't form a line of touching cells"
+            )));
+        }
+    }
+    Ok(cells)
+}
+
+/// Helper function to parse at least `min_len` different cells, as row and
+/// column pairs.
+fn parse_cells(s: &str, min_len: usize) -> Result<Vec<Coord>, GridError> {
+    let chars: Vec<char> = s.chars().collect();
+    if !chars.len().is_multiple_of(2) || chars.len() < 2 * min_len {
+        return Err(GridError::invalid_format(format!(
+            "Expected at least {min_len} cells as row and column pairs in `{s}`"
+        )));
+    }
+
+    let mut cells: Vec<Coord> = Vec::new();
+    for pair in chars.chunks(2) {
+        let cell = Coord::from((
+            parse_index(&pair[0].to_string())?,
+            parse_index(&pair[1].to_string())?,
+        ));
+        if cells.contains(&cell) {
+            return Err(GridError::invalid_format(format!(
+                "The cell {} is repeated in `{s}`",
+                Coord::from((cell.row + 1, cell.col + 1))
+            )));
+        }
+        cells.push(cell);
+    }
+
+    Ok(cells)
+}
+
+/// Helper function to parse a row or column from a base-36 digit counting
+/// from 1.
+fn parse_index(s: &str) -> Result<usize, GridError> {
+    match s.parse::<char>().ok().and_then(|c| c.to_digit(36)) {
+        Some(n) if (1..=SIZE as u32).contains(&n) => Ok(n as usize - 1),
+        _ => Err(GridError::invalid_format(format!(
+            "Unexpected row or column `{s}`, expected 1 to {SIZE}"
+        ))),
+    }
+}
+
+/// Helper function to write a row or column as a base-36 digit counting from
+/// 1.
+fn index_char(i: usize) -> char {
+    char::from_digit(i as u32 + 1, 36).unwrap_or('?')
+}
@@ -1,18 +1,56 @@
+#[cfg(not(target_arch = "wasm32"))]
+pub use batch::{
+    generate_batch, solve_batch, BatchOptions, BatchResult, BatchStatus, GenerateOptions,
+    GeneratedPuzzle, Solver,
+};
+pub use cage::{format_cages, parse_cages, random_cages, Cage};
 pub use cell::Cell;
 pub use coord::Coord;
-pub use generator::{generate, Difficulty};
-pub use grid::{get_base_solution, Grid};
-pub use grid_trait::DisplayableGrid;
-pub use solver::solve_backtracking_heuristics;
+pub use edge::{format_dots, kropki_dots, neighbour_pairs, parse_dots, Edge, Relation};
+#[cfg(not(target_arch = "wasm32"))]
+pub use generator::generate;
+pub use generator::{
+    generate_from_mask, generate_from_solution, generate_with_rng, is_minimal, Difficulty, Mask,
+};
+pub use grid::{get_base_solution, ErrorKind, Grid, GridError};
+pub use grid_trait::{DisplayableGrid, Outline, OutlinedGrid};
+pub use layout::Layout;
+pub use line_format::{parse_line, parse_parts, parse_puzzle, to_line};
+pub use lines::{format_lines, parse_lines, Arrow, Sandwich, Thermometer};
+pub use multi_grid::{generate_samurai, samurai_offsets, MarkedGrid, MultiGrid};
+pub use rater::{rate, Rating};
+pub use rules::{Constraint, Parity, Piece, Region, RegionKind, Rules};
+pub use solver::{
+    count_solutions, find_solutions, solve_backtracking_heuristics, solve_with_limit,
+    solve_with_rng, Puzzle,
+};
+pub use style::Background;
+pub use symmetry::Symmetry;
+pub use validate::{Conflict, Validate};
+pub use variant::{Toggles, Variant};
 
 pub use crate::{CELL_WIDTH, NUM_WIDTH, ORDER, SIZE};
 
 pub type GridArray = [[Cell; SIZE]; SIZE];
 
+#[cfg(not(target_arch = "wasm32"))]
+mod batch;
+mod cage;
 mod candidate_matrix;
 mod cell;
 mod coord;
+mod edge;
 mod generator;
 mod grid;
 mod grid_trait;
+mod layout;
+mod line_format;
+mod lines;
+mod multi_grid;
+mod rater;
+mod rules;
 mod solver;
+mod style;
+mod symmetry;
+mod validate;
+mod variant;
@@ -0,0 +1,641 @@
+use std::{fmt, str::FromStr};
+
+use rand::{seq::SliceRandom, Rng};
+
+use super::{
+    grid::{ErrorKind, GridError},
+    parse_line,
+    solver::{solve_with_limit, Puzzle},
+    to_line, Cell, Coord, Difficulty, Grid, GridArray, CELL_WIDTH, ORDER, SIZE,
+};
+
+#[derive(Clone, Debug)]
+/// Struct representing a puzzle made of several grids that overlap, such as
+/// Samurai Sudoku, where five grids share their corner boxes. Each grid
+/// follows its own rules, and `offsets[i]` is where the top-left cell of grid
+/// `i` sits in the combined puzzle, so grids covering the same position share
+/// that cell and have to hold the same value in it.
+pub struct MultiGrid {
+    grids: Vec<Grid>,
+    offsets: Vec<Coord>,
+    pub solved: bool,
+}
+
+impl MultiGrid {
+    /// Construct a puzzle from `grids`, placed at `offsets` in the combined
+    /// puzzle. Returns an error if there isn't an offset for every grid, or
+    /// the grids have different values in a cell they share, or the clues of
+    /// one grid leave a cell of another with no candidates.
+    pub fn from_grids(grids: Vec<Grid>, offsets: Vec<Coord>) -> Result<Self, GridError> {
+        if grids.len() != offsets.len() || grids.is_empty() {
+            return Err(GridError::invalid_format(format!(
+                "Expected an offset for each of the {} grids but found {}",
+                grids.len(),
+                offsets.len()
+            )));
+        }
+
+        let mut g = MultiGrid {
+            grids,
+            offsets,
+            solved: false,
+        };
+        for (i, cell, k, other) in g.shared_cells() {
+            if g.grids[i].get_cell(cell) != g.grids[k].get_cell(other) {
+                let (cell, other) = (
+                    Coord::from((cell.row + 1, cell.col + 1)),
+                    Coord::from((other.row + 1, other.col + 1)),
+                );
+                return Err(GridError::with_details(
+                    ErrorKind::ContradictoryClues,
+                    format!(
+                        "Cell {cell} of grid {} and cell {other} of grid {} are the same cell, \
+                         but don't hold the same value",
+                        i + 1,
+                        k + 1
+                    ),
+                ));
+            }
+        }
+
+        g.share_candidates().map_err(|(i, cell)| {
+            GridError::new(ErrorKind::ContradictoryClues, cell, 0).in_grid(i)
+        })?;
+        g.check_solved();
+        Ok(g)
+    }
+
+    /// Construct an empty Samurai puzzle: five grids following the rules of
+    /// classic Sudoku, one in each corner and one in the centre, which shares
+    /// a corner box with each of the others.
+    pub fn samurai() -> Self {
+        Self::from_grids(vec![Grid::new(); SAMURAI_GRIDS], samurai_offsets())
+            .expect("Empty grids have no clues to contradict")
+    }
+
+    /// Construct a Samurai puzzle from the cells of its five grids, in the
+    /// order of `samurai_offsets`, following the rules of classic Sudoku.
+    pub fn samurai_from_rows(rows: &[GridArray]) -> Result<Self, GridError> {
+        if rows.len() != SAMURAI_GRIDS {
+            return Err(GridError::invalid_format(format!(
+                "Expected {SAMURAI_GRIDS} grids but found {}",
+                rows.len()
+            )));
+        }
+
+        let grids = rows
+            .iter()
+            .enumerate()
+            .map(|(i, &rows)| Grid::from_rows(rows).map_err(|e| e.in_grid(i)))
+            .collect::<Result<_, _>>()?;
+        Self::from_grids(grids, samurai_offsets())
+    }
+
+    /// Return a reference to the `grids` field.
+    pub fn grids(&self) -> &[Grid] {
+        &self.grids
+    }
+
+    /// Return a reference to the `offsets` field.
+    pub fn offsets(&self) -> &[Coord] {
+        &self.offsets
+    }
+
+    /// Returns the number of rows and columns the combined puzzle spans.
+    pub fn dimensions(&self) -> (usize, usize) {
+        self.offsets.iter().fold((0, 0), |(rows, cols), offset| {
+            (rows.max(offset.row + SIZE), cols.max(offset.col + SIZE))
+        })
+    }
+
+    /// Returns every grid covering `pos` in the combined puzzle, with the
+    /// coordinates of `pos` in that grid, in grid order.
+    pub fn grids_at(&self, pos: Coord) -> Vec<(usize, Coord)> {
+        self.offsets
+            .iter()
+            .enumerate()
+            .filter_map(|(i, offset)| {
+                let row = pos.row.checked_sub(offset.row).filter(|&r| r < SIZE)?;
+                let col = pos.col.checked_sub(offset.col).filter(|&c| c < SIZE)?;
+                Some((i, (row, col).into()))
+            })
+            .collect()
+    }
+
+    /// Returns the cells of other grids that are the same cell as `cell` of
+    /// grid `i`.
+    pub fn shared_with(&self, i: usize, cell: Coord) -> Vec<(usize, Coord)> {
+        let offset = self.offsets[i];
+        let pos = Coord::from((offset.row + cell.row, offset.col + cell.col));
+        self.grids_at(pos)
+            .into_iter()
+            .filter(|&(k, _)| k != i)
+            .collect()
+    }
+
+    /// Return a reference to the cell at `cell` of grid `i`.
+    pub fn get_cell(&self, i: usize, cell: Coord) -> &Cell {
+        self.grids[i].get_cell(cell)
+    }
+
+    /// Update the value at `cell` of grid `i` to `val`, in every grid sharing
+    /// the cell, then remove the candidates the other grids rule out from
+    /// the shared cells. Returns an error, naming the grid it happened in, if
+    /// `cell` is a clue, or the update would result in a cell having zero
+    /// valid candidates, in which case every grid is left as it was.
+    pub fn update(&mut self, i: usize, cell: Coord, val: u8) -> Result<(), GridError> {
+        // A later grid can fail after earlier ones are updated, so they're
+        // put back as they were.
+        let before = self.grids.clone();
+        let result = [(i, cell)]
+            .into_iter()
+            .chain(self.shared_with(i, cell))
+            .try_for_each(|(k, c)| self.grids[k].update(c, val).map_err(|e| e.in_grid(k)))
+            .and_then(|_| {
+                self.share_candidates()
+                    .map_err(|_| GridError::new(ErrorKind::ZeroCandidates, cell, val).in_grid(i))
+            });
+
+        if result.is_err() {
+            self.grids = before;
+        }
+        self.check_solved();
+        result
+    }
+
+    /// Set `cell` of grid `i` to empty, in every grid sharing the cell.
+    /// Returns an error if called on a clue in any of them, in which case
+    /// none are cleared.
+    pub fn clear(&mut self, i: usize, cell: Coord) -> Result<(), GridError> {
+        let cells: Vec<_> = [(i, cell)]
+            .into_iter()
+            .chain(self.shared_with(i, cell))
+            .collect();
+        if let Some(&(k, c)) = cells
+            .iter()
+            .find(|&&(k, c)| matches!(self.grids[k].get_cell(c), Cell::Clue(_)))
+        {
+            return Err(GridError::new(ErrorKind::ClearedClue, c, 0).in_grid(k));
+        }
+
+        let result = cells
+            .into_iter()
+            .try_for_each(|(k, c)| self.grids[k].clear(c).map_err(|e| e.in_grid(k)));
+
+        self.check_solved();
+        result
+    }
+
+    /// Remove candidate `val` from `cell` of grid `i`, in every grid sharing
+    /// the cell.
+    pub fn remove_candidate(&mut self, i: usize, cell: Coord, val: u8) -> bool {
+        [(i, cell)]
+            .into_iter()
+            .chain(self.shared_with(i, cell))
+            .fold(false, |removed, (k, c)| {
+                self.grids[k].remove_candidate(c, val) || removed
+            })
+    }
+
+    /// Set all `Filled` cells to `Clue`s of the same value, then rebuild the
+    /// candidates from the clues. Used in puzzle generation.
+    pub fn set_clues(&mut self) -> Result<(), GridError> {
+        for (i, g) in self.grids.iter_mut().enumerate() {
+            g.set_clues();
+            g.reset_candidate_matrix().map_err(|e| e.in_grid(i))?;
+        }
+        self.share_candidates()
+            .map_err(|(i, cell)| GridError::new(ErrorKind::ContradictoryClues, cell, 0).in_grid(i))
+    }
+
+    /// Returns the number of cells in the combined puzzle left to fill,
+    /// counting shared cells once.
+    pub fn empty_cell_count(&self) -> usize {
+        self.positions()
+            .into_iter()
+            .filter(|&(i, cell)| self.grids[i].get_cell(cell) == &Cell::Empty)
+            .count()
+    }
+
+    /// Returns each cell of the combined puzzle once, as the first grid it's
+    /// in and its coordinates there, in row order.
+    pub fn positions(&self) -> Vec<(usize, Coord)> {
+        let (rows, cols) = self.dimensions();
+        (0..rows * cols)
+            .filter_map(|p| self.grids_at((p / cols, p % cols).into()).first().copied())
+            .collect()
+    }
+
+    /// Write the puzzle in line format: each grid in line format, in grid
+    /// order, separated by `|`.
+    pub fn to_line(&self) -> String {
+        let lines: Vec<String> = self.grids.iter().map(|g| to_line(g.rows())).collect();
+        lines.join(&GRID_SEPARATOR.to_string())
+    }
+
+    /// Returns a value that displays the puzzle with `cell` of grid `i`
+    /// marked with `?`, in every grid sharing it.
+    pub fn marked(&self, i: usize, cell: Coord) -> MarkedGrid<'_> {
+        let offset = self.offsets[i];
+        MarkedGrid {
+            marked: Some((offset.row + cell.row, offset.col + cell.col).into()),
+            ..self.display()
+        }
+    }
+
+    /// Returns a value that displays the puzzle with no cell marked, which
+    /// can be given its own way of writing cells with `MarkedGrid::with_cells`.
+    pub fn display(&self) -> MarkedGrid<'_> {
+        MarkedGrid {
+            g: self,
+            marked: None,
+            show: Cell::to_string,
+        }
+    }
+
+    /// Helper function returning every pair of cells in different grids that
+    /// are the same cell, as the first grid, its cell, the second grid and
+    /// its cell, with the first grid before the second.
+    fn shared_cells(&self) -> Vec<(usize, Coord, usize, Coord)> {
+        (0..self.grids.len())
+            .flat_map(|i| {
+                (0..SIZE.pow(2)).flat_map(move |p| {
+                    let cell = Coord::from((p / SIZE, p % SIZE));
+                    self.shared_with(i, cell)
+                        .into_iter()
+                        .filter(move |&(k, _)| k > i)
+                        .map(move |(k, other)| (i, cell, k, other))
+                })
+            })
+            .collect()
+    }
+
+    /// Helper function to keep only the candidates of each empty shared cell
+    /// that every grid it's in allows, which is how what's placed in one grid
+    /// narrows down the others. Returns the grid and coordinate of the first
+    /// cell left with no candidates as an error.
+    fn share_candidates(&mut self) -> Result<(), (usize, Coord)> {
+        for (i, cell, k, other) in self.shared_cells() {
+            if self.grids[i].get_cell(cell).value().is_some() {
+                continue;
+            }
+
+            let (a, b) = (
+                self.grids[i].candidates_at(cell),
+                self.grids[k].candidates_at(other),
+            );
+            for &n in a.iter().filter(|n| !b.contains(n)) {
+                self.grids[i].remove_candidate(cell, n);
+            }
+            for &n in b.iter().filter(|n| !a.contains(n)) {
+                self.grids[k].remove_candidate(other, n);
+            }
+            if self.grids[i].candidates_at(cell).is_empty() {
+                return Err((i, cell));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check if the puzzle is solved, i.e. every grid is solved.
+    fn check_solved(&mut self) {
+        self.solved = self.grids.iter().all(|g| g.solved);
+    }
+}
+
+impl Puzzle for MultiGrid {
+    type Target = (usize, Coord);
+
+    fn is_solved(&self) -> bool {
+        self.solved
+    }
+
+    fn empty_cells(&self) -> usize {
+        self.empty_cell_count()
+    }
+
+    /// Returns the cell with the least valid candidates among the emptiest
+    /// cells of each grid, favouring earlier grids.
+    fn get_min_candidates_cell<R: Rng + ?Sized>(&self, rng: &mut R) -> (usize, Coord) {
+        self.grids
+            .iter()
+            .enumerate()
+            .filter(|(_, g)| g.empty_cell_count > 0)
+            .map(|(i, g)| (i, g.get_min_candidates_cell(rng)))
+            .min_by_key(|&(i, cell)| self.grids[i].candidates_at(cell).len())
+            .unwrap_or((0, (0, 0).into()))
+    }
+
+    fn candidates_at(&self, (i, cell): (usize, Coord)) -> Vec<u8> {
+        self.grids[i].candidates_at(cell)
+    }
+
+    fn update(&mut self, (i, cell): (usize, Coord), val: u8) -> Result<(), GridError> {
+        MultiGrid::update(self, i, cell, val)
+    }
+
+    fn remove_candidate(&mut self, (i, cell): (usize, Coord), val: u8) -> bool {
+        MultiGrid::remove_candidate(self, i, cell, val)
+    }
+}
+
+/// Number of grids in a Samurai puzzle.
+const SAMURAI_GRIDS: usize = 5;
+
+/// Character separating the grids of a multi-grid puzzle in line format.
+const GRID_SEPARATOR: char = '|';
+
+/// Returns where the top-left cell of each grid of a Samurai puzzle sits:
+/// top left, top right, centre, bottom left and bottom right. The corner
+/// grids are a box apart, and the centre grid overlaps a box of each.
+pub fn samurai_offsets() -> Vec<Coord> {
+    let (centre, far) = (SIZE - ORDER, 2 * (SIZE - ORDER));
+    vec![
+        (0, 0).into(),
+        (0, far).into(),
+        (centre, centre).into(),
+        (far, 0).into(),
+        (far, far).into(),
+    ]
+}
+
+/// Enable parsing a Samurai puzzle from its five grids in line format,
+/// separated by `|`, in the order of `samurai_offsets`.
+impl FromStr for MultiGrid {
+    type Err = GridError;
+
+    fn from_str(s: &str) -> Result<Self, GridError> {
+        let rows = s
+            .trim()
+            .split(GRID_SEPARATOR)
+            .enumerate()
+            .map(|(i, line)| parse_line(line).map_err(|e| e.in_grid(i)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::samurai_from_rows(&rows)
+    }
+}
+
+/// Number of times the search for a solved Samurai grid can backtrack before
+/// it's started again.
+const SAMURAI_BACKTRACK_LIMIT: usize = 1000;
+
+/// Number of searches for a solved Samurai grid tried before giving up.
+const SAMURAI_ATTEMPTS: usize = 100;
+
+/// Generate a Samurai puzzle with difficulty `d`, using `rng` as the only
+/// source of randomness. Cells are removed from a random solution one at a
+/// time in a random order, skipping any whose removal would give the puzzle
+/// more than one solution, until as many are removed as from a single grid
+/// of the same difficulty, or none can be for minimal puzzles. Returns an
+/// error of kind `TimedOut` if no solved grid is found.
+pub fn generate_samurai<R: Rng + ?Sized>(
+    d: Difficulty,
+    rng: &mut R,
+) -> Result<MultiGrid, GridError> {
+    let solution = (0..SAMURAI_ATTEMPTS)
+        .find_map(|_| solve_with_limit(MultiGrid::samurai(), SAMURAI_BACKTRACK_LIMIT, rng).ok())
+        .ok_or_else(|| {
+            GridError::with_details(
+                ErrorKind::TimedOut,
+                format!("Couldn't find a solved Samurai grid in {SAMURAI_ATTEMPTS} attempts"),
+            )
+        })?;
+
+    let mut cells = solution.positions();
+    cells.shuffle(rng);
+    let to_delete = match d {
+        Difficulty::Easy => 43,
+        Difficulty::Medium => 47,
+        Difficulty::Hard => 53,
+        Difficulty::Minimal => SIZE.pow(2),
+    } * cells.len()
+        / SIZE.pow(2);
+
+    let mut g = solution;
+    g.set_clues()?;
+    let mut deleted = 0;
+    for (i, cell) in cells.into_iter() {
+        if deleted >= to_delete {
+            break;
+        }
+        let rows = clue_rows(&g);
+        let mut without = rows.clone();
+        let offset = g.offsets[i];
+        for (k, c) in g.grids_at((offset.row + cell.row, offset.col + cell.col).into()) {
+            without[k][c.row][c.col] = Cell::Empty;
+        }
+
+        let Ok(candidate) = MultiGrid::samurai_from_rows(&without) else {
+            continue;
+        };
+        if super::count_solutions(candidate.clone(), 2) == 1 {
+            g = candidate;
+            deleted += 1;
+        }
+    }
+
+    Ok(g)
+}
+
+/// Helper function to get the cells of each grid of `g` as clues.
+fn clue_rows(g: &MultiGrid) -> Vec<GridArray> {
+    g.grids
+        .iter()
+        .map(|g| {
+            g.rows().map(|row| {
+                row.map(|cell| match cell.value() {
+                    Some(n) => Cell::Clue(n),
+                    None => Cell::Empty,
+                })
+            })
+        })
+        .collect()
+}
+
+impl fmt::Display for MultiGrid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.display())
+    }
+}
+
+/// Struct for displaying a `MultiGrid` with one cell marked with `?`, created
+/// by `MultiGrid::marked`, or with none, created by `MultiGrid::display`.
+pub struct MarkedGrid<'a> {
+    g: &'a MultiGrid,
+    /// The position of the marked cell in the combined puzzle.
+    marked: Option<Coord>,
+    /// Writes each cell, `CELL_WIDTH` characters wide.
+    show: fn(&Cell) -> String,
+}
+
+impl MarkedGrid<'_> {
+    /// Write each cell with `show` instead of its `Display`, e.g. to colour
+    /// the clues. `show` should keep cells `CELL_WIDTH` characters wide.
+    pub fn with_cells(self, show: fn(&Cell) -> String) -> Self {
+        MarkedGrid { show, ..self }
+    }
+}
+
+/// Displays the grids together, with borders between the boxes of each grid
+/// and around the grids, and each grid's number on the line above its
+/// top-left cell. Positions outside every grid are left blank.
+impl fmt::Display for MarkedGrid<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let g = self.g;
+        let cell_width = CELL_WIDTH as usize;
+        let gutter_width = cell_width * 2;
+        let (rows, cols) = g.dimensions();
+        let cell = |i: usize, j: usize| g.grids_at((i, j).into()).first().copied();
+        // A border is drawn between two cells in different boxes of every
+        // grid they share, or where a grid ends.
+        let border = |a: (usize, usize), b: (usize, usize)| {
+            let (a, b) = (g.grids_at(a.into()), g.grids_at(b.into()));
+            match (a.is_empty(), b.is_empty()) {
+                (true, true) => false,
+                (false, false) => !a.iter().any(|&(k, x)| {
+                    b.iter()
+                        .any(|&(l, y)| k == l && !g.grids[k].rules().layout().is_border(x, y))
+                }),
+                _ => true,
+            }
+        };
+        let label = |i: usize, j: usize| {
+            g.offsets
+                .iter()
+                .position(|&o| o == (i, j).into())
+                .map(|k| (k + 1).to_string())
+        };
+        let col_sep = |j: usize| j + 1 < cols && (0..rows).any(|i| border((i, j), (i, j + 1)));
+        let row_sep = |i: usize| i + 1 < rows && (0..cols).any(|j| border((i, j), (i + 1, j)));
+
+        if (0..cols).any(|j| label(0, j).is_some()) {
+            write!(f, "{:^gutter_width$}", " ")?;
+            for j in 0..cols {
+                write!(f, "{:>cell_width$}", label(0, j).unwrap_or_default())?;
+                if col_sep(j) {
+                    write!(f, "{:>cell_width$}", " ")?;
+                }
+            }
+            writeln!(f)?;
+        }
+
+        for i in 0..rows {
+            write!(f, "{:^gutter_width$}", " ")?;
+            for j in 0..cols {
+                match cell(i, j) {
+                    Some(_) if self.marked == Some((i, j).into()) => {
+                        write!(f, "{}", (self.show)(&Cell::Clue(0)))?
+                    }
+                    Some((k, c)) => write!(f, "{}", (self.show)(g.grids[k].get_cell(c)))?,
+                    None => write!(f, "{:>cell_width$}", " ")?,
+                }
+                if col_sep(j) {
+                    let sep = if border((i, j), (i, j + 1)) { '|' } else { ' ' };
+                    write!(f, "{sep:>cell_width$}")?;
+                }
+            }
+            writeln!(f)?;
+
+            if !row_sep(i) {
+                continue;
+            }
+            write!(f, "{:^gutter_width$}", " ")?;
+            for j in 0..cols {
+                let below = border((i, j), (i + 1, j));
+                let rule = if below { '-' } else { ' ' };
+                match label(i + 1, j) {
+                    Some(label) => write!(f, "{label:>cell_width$}")?,
+                    None => write!(f, "{}", rule.to_string().repeat(cell_width))?,
+                }
+                if col_sep(j) {
+                    let across = below || border((i, j + 1), (i + 1, j + 1));
+                    let down = border((i, j), (i, j + 1)) || border((i + 1, j), (i + 1, j + 1));
+                    let junction = match (down, across) {
+                        (true, true) => '+',
+                        (true, false) => '|',
+                        (false, true) => '-',
+                        (false, false) => ' ',
+                    };
+                    write!(f, "{}{junction}", rule.to_string().repeat(cell_width - 1))?;
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::logic::count_solutions;
+
+    /// The centre grid of a Samurai puzzle, whose top-left box is the
+    /// bottom-right box of the first grid.
+    const CENTRE: usize = 2;
+
+    #[test]
+    fn values_spread_across_the_overlap() {
+        let mut g = MultiGrid::samurai();
+        g.update(0, (8, 8).into(), 5).unwrap();
+        assert_eq!(g.get_cell(CENTRE, (2, 2).into()), &Cell::Filled(5));
+        assert!(!g.grids()[CENTRE].candidates_at((2, 6).into()).contains(&5));
+
+        // A 5 in the centre grid's top row, outside the overlap, rules 5 out
+        // of the shared cells, and so of the first grid's cells there.
+        let mut g = MultiGrid::samurai();
+        g.update(CENTRE, (0, 5).into(), 5).unwrap();
+        assert!(!g.grids()[0].candidates_at((6, 7).into()).contains(&5));
+        assert!(g.grids()[0].candidates_at((7, 7).into()).contains(&5));
+    }
+
+    #[test]
+    fn failed_updates_leave_every_grid_unchanged() {
+        let mut g = MultiGrid::samurai();
+        // Leave a cell of the centre grid's third row only able to hold 5,
+        // so a 5 placed in the shared cell of that row fails there, after
+        // the first grid has taken it.
+        for n in (1..=SIZE as u8).filter(|&n| n != 5) {
+            g.remove_candidate(CENTRE, (2, 6).into(), n);
+        }
+        let line = g.to_line();
+        let candidates = g.grids()[0].candidates_at((8, 0).into());
+
+        let e = g.update(0, (8, 8).into(), 5).unwrap_err();
+        assert_eq!(e.kind, ErrorKind::ZeroCandidates);
+        assert_eq!(g.to_line(), line);
+        assert_eq!(g.grids()[0].candidates_at((8, 0).into()), candidates);
+        assert_eq!(g.grids()[CENTRE].candidates_at((2, 6).into()), [5]);
+    }
+
+    #[test]
+    fn clearing_a_shared_cell_clears_every_grid() {
+        let mut g = MultiGrid::samurai();
+        g.update(CENTRE, (2, 2).into(), 5).unwrap();
+        g.clear(0, (8, 8).into()).unwrap();
+        assert_eq!(g.get_cell(0, (8, 8).into()), &Cell::Empty);
+        assert_eq!(g.get_cell(CENTRE, (2, 2).into()), &Cell::Empty);
+        assert_eq!(g.empty_cell_count(), g.positions().len());
+    }
+
+    #[test]
+    fn generated_samurai_puzzles_solve_uniquely() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let g = generate_samurai(Difficulty::Easy, &mut rng).unwrap();
+        assert_eq!(count_solutions(g.clone(), 2), 1);
+
+        let solution = solve_with_limit(g.clone(), usize::MAX, &mut rng).unwrap();
+        assert!(solution.is_solved());
+        for (i, cell) in g.positions() {
+            if let Cell::Clue(n) = g.get_cell(i, cell) {
+                assert_eq!(solution.get_cell(i, cell).value(), Some(*n));
+            }
+        }
+        for (i, cell, k, other) in solution.shared_cells() {
+            assert_eq!(solution.get_cell(i, cell), solution.get_cell(k, other));
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use std::fmt;
+
+use super::{
+    grid::{ErrorKind, GridError},
+    Cell, Coord, Difficulty, Grid, SIZE,
+};
+
+/// Score added for each cell filled as a naked single.
+const NAKED_SINGLE_SCORE: u32 = 1;
+/// Score added for each cell filled as a hidden single.
+const HIDDEN_SINGLE_SCORE: u32 = 2;
+/// Score added for each elimination made with locked candidates.
+const LOCKED_CANDIDATES_SCORE: u32 = 5;
+/// Score added for each elimination made with a naked pair.
+const NAKED_PAIR_SCORE: u32 = 8;
+/// Score added for each cell still empty once guessing is needed.
+const GUESS_SCORE: u32 = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Struct representing how hard a puzzle is for a person to solve. The
+/// difficulty is based on the hardest technique needed, and the score adds
+/// up the techniques used, so it can order puzzles of the same difficulty.
+pub struct Rating {
+    pub difficulty: Difficulty,
+    pub score: u32,
+}
+
+impl fmt::Display for Rating {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (score {})", self.difficulty, self.score)
+    }
+}
+
+/// Rate grid `g` by solving it the way a person would. Puzzles that only need
+/// naked and hidden singles are easy, ones that also need locked candidates or
+/// naked pairs are medium, and ones that need guessing are hard. Returns an
+/// error of kind `NoSolution` if a contradiction is found.
+pub fn rate(g: &Grid) -> Result<Rating, GridError> {
+    let mut g = g.clone();
+    let groups: Vec<Vec<Coord>> = g
+        .rules()
+        .regions()
+        .iter()
+        .map(|r| r.cells.clone())
+        .collect();
+    let mut rating = Rating {
+        difficulty: Difficulty::Easy,
+        score: 0,
+    };
+
+    while !g.solved {
+        if let Some((cell, val)) = find_naked_single(&g)? {
+            place(&mut g, cell, val)?;
+            rating.score += NAKED_SINGLE_SCORE;
+        } else if let Some((cell, val)) = find_hidden_single(&g, &groups)? {
+            place(&mut g, cell, val)?;
+            rating.score += HIDDEN_SINGLE_SCORE;
+        } else if eliminate_locked_candidates(&mut g, &groups) {
+            rating.difficulty = Difficulty::Medium;
+            rating.score += LOCKED_CANDIDATES_SCORE;
+        } else if eliminate_naked_pairs(&mut g, &groups) {
+            rating.difficulty = Difficulty::Medium;
+            rating.score += NAKED_PAIR_SCORE;
+        } else {
+            // No technique applies, so the rest of the puzzle needs guessing.
+            rating.difficulty = Difficulty::Hard;
+            rating.score += GUESS_SCORE * g.empty_cell_count as u32;
+            break;
+        }
+    }
+
+    Ok(rating)
+}
+
+/// Helper function for the error returned when rating finds a contradiction.
+fn no_solution() -> GridError {
+    GridError::new(ErrorKind::NoSolution, (0, 0).into(), 0)
+}
+
+/// Fill `cell` with `val`, treating a failure as a contradiction.
+fn place(g: &mut Grid, cell: Coord, val: u8) -> Result<(), GridError> {
+    g.update(cell, val).map_err(|_| no_solution())
+}
+
+/// Returns the candidates at `cell` if it's empty.
+fn empty_candidates(g: &Grid, cell: Coord) -> Option<Vec<u8>> {
+    match g.get_cell(cell) {
+        Cell::Empty => Some(g.candidates_at(cell)),
+        _ => None,
+    }
+}
+
+/// Find an empty cell with only one candidate.
+fn find_naked_single(g: &Grid) -> Result<Option<(Coord, u8)>, GridError> {
+    for row in 0..SIZE {
+        for col in 0..SIZE {
+            let cell = Coord::from((row, col));
+            match empty_candidates(g, cell).as_deref() {
+                Some([]) => return Err(no_solution()),
+                Some([val]) => return Ok(Some((cell, *val))),
+                _ => {}
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Returns the empty cells in `group` that have `val` as a candidate.
+fn positions_of(g: &Grid, group: &[Coord], val: u8) -> Vec<Coord> {
+    group
+        .iter()
+        .copied()
+        .filter(|&c| empty_candidates(g, c).is_some_and(|cs| cs.contains(&val)))
+        .collect()
+}
+
+/// Find a value that can only go in one cell of a group. Only groups with a
+/// cell for every value have to contain each one.
+fn find_hidden_single(g: &Grid, groups: &[Vec<Coord>]) -> Result<Option<(Coord, u8)>, GridError> {
+    for group in groups.iter().filter(|group| group.len() == SIZE) {
+        for val in 1..=SIZE as u8 {
+            let placed =
+                |c: &Coord| matches!(g.get_cell(*c), Cell::Clue(n) | Cell::Filled(n) if *n == val);
+            if group.iter().any(placed) {
+                continue;
+            }
+
+            match positions_of(g, group, val).as_slice() {
+                [] => return Err(no_solution()),
+                [cell] => return Ok(Some((*cell, val))),
+                _ => {}
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// If every position for a value in one group lies inside a second group, the
+/// value can be removed from the rest of the second group. The first group has
+/// to have a cell for every value, so that it must contain the value. Returns
+/// `true` if any candidates were removed.
+fn eliminate_locked_candidates(g: &mut Grid, groups: &[Vec<Coord>]) -> bool {
+    for group in groups.iter().filter(|group| group.len() == SIZE) {
+        for val in 1..=SIZE as u8 {
+            let positions = positions_of(g, group, val);
+            if positions.len() < 2 {
+                continue;
+            }
+
+            for other in groups.iter().filter(|&other| other != group) {
+                if !positions.iter().all(|c| other.contains(c)) {
+                    continue;
+                }
+
+                let mut removed = false;
+                for &cell in other.iter().filter(|c| !group.contains(c)) {
+                    removed |= g.remove_candidate(cell, val);
+                }
+                if removed {
+                    return true;
+                }
+            }
+        }
+    }
+
+    false
+}
+
+/// If two cells in a group have the same two candidates, those values can be
+/// removed from the rest of the group. Returns `true` if any candidates were
+/// removed.
+fn eliminate_naked_pairs(g: &mut Grid, groups: &[Vec<Coord>]) -> bool {
+    for group in groups.iter() {
+        let pairs: Vec<(Coord, Vec<u8>)> = group
+            .iter()
+            .filter_map(|&c| empty_candidates(g, c).map(|cs| (c, cs)))
+            .filter(|(_, cs)| cs.len() == 2)
+            .collect();
+
+        for (i, (first, pair)) in pairs.iter().enumerate() {
+            let Some((second, _)) = pairs[i + 1..].iter().find(|(_, cs)| cs == pair) else {
+                continue;
+            };
+
+            let mut removed = false;
+            for &cell in group.iter().filter(|&c| c != first && c != second) {
+                for &val in pair.iter() {
+                    removed |= g.remove_candidate(cell, val);
+                }
+            }
+            if removed {
+                return true;
+            }
+        }
+    }
+
+    false
+}
@@ -0,0 +1,1104 @@
+use std::{array, fmt};
+
+use super::{
+    cage::{progress, Cage},
+    candidate_matrix::CandidateMatrix,
+    edge::{neighbour_pairs, Edge, Relation},
+    grid::ErrorKind,
+    grid_trait::{DisplayableGrid, GridTrait, Outline},
+    layout::Layout,
+    lines::{Arrow, Sandwich, Thermometer},
+    style::Background,
+    validate::{find_conflicts, Conflict},
+    variant::{get_diagonal_coords, get_window_coords},
+    Cell, Coord, GridArray, SIZE,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+/// Enum representing the kinds of region a puzzle can have. The kind is only
+/// used to name a region in messages and to decide how it's drawn, as every
+/// region follows the same rule.
+pub enum RegionKind {
+    Row,
+    Column,
+    Box,
+    Diagonal,
+    Window,
+    Cage,
+}
+
+impl RegionKind {
+    /// The kind of error reported when a value is repeated in a region of
+    /// this kind.
+    pub fn duplicate_error(&self) -> ErrorKind {
+        match self {
+            RegionKind::Row => ErrorKind::DuplicateInRow,
+            RegionKind::Column => ErrorKind::DuplicateInCol,
+            RegionKind::Box => ErrorKind::DuplicateInBox,
+            RegionKind::Diagonal => ErrorKind::DuplicateInDiagonal,
+            RegionKind::Window => ErrorKind::DuplicateInWindow,
+            RegionKind::Cage => ErrorKind::DuplicateInCage,
+        }
+    }
+
+    /// Returns `true` if cells in regions of this kind are shaded when the
+    /// grid is displayed, because they can't be told apart by position alone.
+    pub fn is_shaded(&self) -> bool {
+        matches!(self, RegionKind::Diagonal | RegionKind::Window)
+    }
+}
+
+impl fmt::Display for RegionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.duplicate_error().group_name())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Struct representing a group of cells that can't contain the same value
+/// twice. A region with `SIZE` cells must contain every value exactly once.
+pub struct Region {
+    pub kind: RegionKind,
+    pub cells: Vec<Coord>,
+}
+
+impl Region {
+    /// Construct a region of `kind` from the coordinates of its cells.
+    pub fn new(kind: RegionKind, cells: impl IntoIterator<Item = Coord>) -> Self {
+        Region {
+            kind,
+            cells: cells.into_iter().collect(),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+/// Enum representing the chess pieces whose moves can't join two cells with
+/// the same value, for the anti-knight and anti-king constraints.
+pub enum Piece {
+    Knight,
+    King,
+}
+
+impl Piece {
+    /// The row and column offsets of a move, leaving out moves along a row or
+    /// column, as those cells can't repeat a value anyway.
+    fn offsets(&self) -> &'static [(isize, isize)] {
+        match self {
+            Piece::Knight => &[
+                (-2, -1),
+                (-2, 1),
+                (-1, -2),
+                (-1, 2),
+                (1, -2),
+                (1, 2),
+                (2, -1),
+                (2, 1),
+            ],
+            Piece::King => &[(-1, -1), (-1, 1), (1, -1), (1, 1)],
+        }
+    }
+
+    /// Returns the cells inside the grid a move away from `cell`.
+    pub fn moves_from(&self, cell: Coord) -> Vec<Coord> {
+        self.offsets()
+            .iter()
+            .filter_map(|&(dr, dc)| {
+                let row = cell.row.checked_add_signed(dr).filter(|&r| r < SIZE)?;
+                let col = cell.col.checked_add_signed(dc).filter(|&c| c < SIZE)?;
+                Some((row, col).into())
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Piece {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Piece::Knight => "knight",
+            Piece::King => "king",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+/// Enum representing whether a shaded cell has to hold an even or an odd
+/// value.
+pub enum Parity {
+    Even,
+    Odd,
+}
+
+impl Parity {
+    /// Returns `true` if `n` has this parity.
+    pub fn allows(&self, n: u8) -> bool {
+        match self {
+            Parity::Even => n.is_multiple_of(2),
+            Parity::Odd => !n.is_multiple_of(2),
+        }
+    }
+}
+
+impl fmt::Display for Parity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Parity::Even => "even",
+            Parity::Odd => "odd",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Enum representing the rules a puzzle can have on top of its regions.
+pub enum Constraint {
+    /// The two cells can't contain the same value, even though they don't
+    /// share a region.
+    Different(Coord, Coord),
+    /// The values in the cage add up to its sum.
+    Cage(Cage),
+    /// No two cells a move of the piece apart contain the same value.
+    Chess(Piece),
+    /// The values in two neighbouring cells are related as the edge says,
+    /// such as by a Kropki dot or a greater-than sign.
+    Edge(Edge),
+    /// The cell holds a value of the parity.
+    Parity(Coord, Parity),
+    /// The values strictly increase along the thermometer.
+    Thermometer(Thermometer),
+    /// The values along the arrow add up to the value in its circle.
+    Arrow(Arrow),
+    /// The values between the lowest and highest in a row or column add up
+    /// to the sandwich sum.
+    Sandwich(Sandwich),
+}
+
+impl Constraint {
+    /// The kind of error reported when the constraint is broken.
+    pub fn error_kind(&self) -> ErrorKind {
+        match self {
+            Constraint::Different(..) => ErrorKind::BrokenConstraint,
+            Constraint::Cage(_) => ErrorKind::WrongSum,
+            Constraint::Chess(Piece::Knight) => ErrorKind::AntiKnight,
+            Constraint::Chess(Piece::King) => ErrorKind::AntiKing,
+            Constraint::Edge(edge) => match edge.relation {
+                Relation::Consecutive | Relation::Double => ErrorKind::BrokenDot,
+                Relation::NotConsecutive => ErrorKind::ConsecutiveNeighbours,
+                Relation::Neither => ErrorKind::MissingDot,
+                Relation::Less | Relation::Greater => ErrorKind::BrokenInequality,
+            },
+            Constraint::Parity(..) => ErrorKind::WrongParity,
+            Constraint::Thermometer(_) => ErrorKind::BrokenThermometer,
+            Constraint::Arrow(_) => ErrorKind::BrokenArrow,
+            Constraint::Sandwich(_) => ErrorKind::BrokenSandwich,
+        }
+    }
+
+    /// Remove the candidates in `cm` that the constraint rules out, given the
+    /// values already in `rows`. Returns the coordinate of the first cell left
+    /// with no candidates as an error.
+    fn restrict(&self, rows: &GridArray, cm: &mut CandidateMatrix) -> Result<(), Coord> {
+        match self {
+            &Constraint::Different(a, b) => {
+                for (from, to) in [(a, b), (b, a)] {
+                    if let Some(n) = rows[from.row][from.col].value() {
+                        cm.eliminate(&[to], n)?;
+                    }
+                }
+                Ok(())
+            }
+            Constraint::Cage(cage) => {
+                let (placed, empty) = cage.progress(rows);
+                match (cage.sum.checked_sub(placed), empty.first()) {
+                    (_, None) => Ok(()),
+                    (Some(left), Some(_)) => cm.restrict_to_sum(&empty, left),
+                    (None, Some(&cell)) => Err(cell),
+                }
+            }
+            Constraint::Chess(piece) => {
+                for (i, row) in rows.iter().enumerate() {
+                    for (j, cell) in row.iter().enumerate() {
+                        if let Some(n) = cell.value() {
+                            cm.eliminate(&piece.moves_from((i, j).into()), n)?;
+                        }
+                    }
+                }
+                Ok(())
+            }
+            Constraint::Edge(edge) => {
+                let (a, b) = edge.cells;
+                // Values that can't be either side of an edge rule out little
+                // until one side is filled, so only dots and signs are checked
+                // against the candidates of the other side.
+                let negative = edge.relation.is_negative();
+                let flipped = edge.relation.flipped();
+                for (from, to, relation) in [(a, b, edge.relation), (b, a, flipped)] {
+                    let placed = rows[from.row][from.col].value();
+                    if rows[to.row][to.col].value().is_none() && (!negative || placed.is_some()) {
+                        cm.retain_related(to, from, placed, |x, y| relation.allows(x, y))?;
+                    }
+                }
+                Ok(())
+            }
+            &Constraint::Parity(cell, parity) => {
+                if rows[cell.row][cell.col].value().is_none() {
+                    let wrong = (1..=SIZE as u8).filter(|&n| !parity.allows(n));
+                    for n in wrong {
+                        cm.eliminate(&[cell], n)?;
+                    }
+                }
+                Ok(())
+            }
+            Constraint::Thermometer(thermo) => {
+                let limits = Thermometer::limits(&bounds(rows, cm, &thermo.cells));
+                for (&cell, &(lo, hi)) in thermo.cells.iter().zip(limits.iter()) {
+                    if lo > hi {
+                        return Err(cell);
+                    }
+                    cm.restrict_to_range(cell, lo, hi)?;
+                }
+                Ok(())
+            }
+            Constraint::Arrow(arrow) => {
+                let (circle_lo, circle_hi) = bounds(rows, cm, &[arrow.circle])[0];
+                let bounds = bounds(rows, cm, &arrow.cells);
+                let (sum_lo, sum_hi) = bounds
+                    .iter()
+                    .fold((0, 0), |(lo, hi), &(l, h)| (lo + l as u32, hi + h as u32));
+                if sum_lo > circle_hi as u32 || sum_hi < circle_lo as u32 {
+                    return Err(arrow.circle);
+                }
+
+                let clamp = |n: u32| n.min(u8::MAX as u32) as u8;
+                cm.restrict_to_range(arrow.circle, clamp(sum_lo), clamp(sum_hi))?;
+                for (&cell, &(lo, hi)) in arrow.cells.iter().zip(bounds.iter()) {
+                    // Each cell makes up whatever the others can't.
+                    let least = (circle_lo as u32 + hi as u32).saturating_sub(sum_hi);
+                    let most = (circle_hi as u32 + lo as u32).saturating_sub(sum_lo);
+                    cm.restrict_to_range(cell, clamp(least), clamp(most))?;
+                }
+                Ok(())
+            }
+            Constraint::Sandwich(sandwich) => {
+                let Some((a, b)) = sandwich.ends(rows) else {
+                    // Only keep the lowest and highest values where a
+                    // sandwich with the right sum could start or end.
+                    let cells = &sandwich.cells;
+                    let can_hold = |i: usize, n: u8| match rows[cells[i].row][cells[i].col] {
+                        Cell::Empty => cm.contains(cells[i], n),
+                        cell => cell.value() == Some(n),
+                    };
+                    let (low, high) = (1, SIZE as u8);
+                    let mut ends = vec![false; cells.len()];
+                    for a in 0..cells.len() {
+                        for b in a + 1..cells.len() {
+                            let fits = (can_hold(a, low) && can_hold(b, high))
+                                || (can_hold(a, high) && can_hold(b, low));
+                            if fits && sandwich.could_fill(rows, a, b) {
+                                ends[a] = true;
+                                ends[b] = true;
+                            }
+                        }
+                    }
+                    for (&cell, _) in cells.iter().zip(ends).filter(|&(_, end)| !end) {
+                        match rows[cell.row][cell.col].value() {
+                            Some(n) if n == low || n == high => return Err(cell),
+                            Some(_) => {}
+                            None => {
+                                cm.eliminate(&[cell], low)?;
+                                cm.eliminate(&[cell], high)?;
+                            }
+                        }
+                    }
+                    return Ok(());
+                };
+                // The ends can be placed after the cells between them, so
+                // a wrong sum with nothing left to fill is an error too.
+                let (placed, empty) = progress(rows, &sandwich.cells[a + 1..b]);
+                match (sandwich.sum.checked_sub(placed), empty.first()) {
+                    (Some(0), None) => Ok(()),
+                    (_, None) => Err(sandwich.cells[b]),
+                    (Some(left), Some(_)) => cm.restrict_to_sum(&empty, left),
+                    (None, Some(&cell)) => Err(cell),
+                }
+            }
+        }
+    }
+
+    /// Returns the value and the cells involved if the values in `rows` break
+    /// the constraint. Empty cells never break it, and for a cage the value
+    /// is the sum of the values placed so far.
+    fn broken_by(&self, rows: &GridArray) -> Option<(u8, Vec<Coord>)> {
+        match self {
+            &Constraint::Different(a, b) => {
+                let n = rows[a.row][a.col].value()?;
+                (rows[b.row][b.col].value() == Some(n)).then(|| (n, vec![a, b]))
+            }
+            Constraint::Cage(cage) => {
+                let (placed, empty) = cage.progress(rows);
+                let broken = placed > cage.sum || (empty.is_empty() && placed != cage.sum);
+                broken.then(|| {
+                    let filled = cage.cells.iter().filter(|c| !empty.contains(c));
+                    (placed as u8, filled.copied().collect())
+                })
+            }
+            Constraint::Chess(piece) => (0..SIZE.pow(2))
+                .map(|i| Coord::from((i / SIZE, i % SIZE)))
+                .find_map(|a| {
+                    let n = rows[a.row][a.col].value()?;
+                    piece
+                        .moves_from(a)
+                        .into_iter()
+                        .find(|&b| b > a && rows[b.row][b.col].value() == Some(n))
+                        .map(|b| (n, vec![a, b]))
+                }),
+            Constraint::Edge(edge) => {
+                let (a, b) = edge.cells;
+                let (x, y) = (rows[a.row][a.col].value()?, rows[b.row][b.col].value()?);
+                (!edge.relation.allows(x, y)).then(|| (x, vec![a, b]))
+            }
+            &Constraint::Parity(cell, parity) => {
+                let n = rows[cell.row][cell.col].value()?;
+                (!parity.allows(n)).then(|| (n, vec![cell]))
+            }
+            Constraint::Thermometer(thermo) => {
+                let (a, b) = thermo.broken_by(rows)?;
+                Some((rows[b.row][b.col].value()?, vec![a, b]))
+            }
+            Constraint::Arrow(arrow) => {
+                let circle = rows[arrow.circle.row][arrow.circle.col].value()? as u32;
+                let (placed, empty) = progress(rows, &arrow.cells);
+                let broken =
+                    placed + empty.len() as u32 > circle || (empty.is_empty() && placed != circle);
+                broken.then(|| {
+                    let filled = arrow.cells.iter().filter(|c| !empty.contains(c));
+                    let cells = [arrow.circle].into_iter().chain(filled.copied()).collect();
+                    (placed as u8, cells)
+                })
+            }
+            Constraint::Sandwich(sandwich) => {
+                let (a, b) = sandwich.ends(rows)?;
+                let (placed, empty) = progress(rows, &sandwich.cells[a + 1..b]);
+                let broken = placed > sandwich.sum || (empty.is_empty() && placed != sandwich.sum);
+                broken.then(|| {
+                    let filled = sandwich.cells[a..=b].iter().filter(|c| !empty.contains(c));
+                    (placed as u8, filled.copied().collect())
+                })
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Struct describing the rules of a puzzle: a list of regions that can't
+/// contain the same value twice, and any extra constraints. Classic Sudoku
+/// is the rows, columns and boxes with no extra constraints, and variants are
+/// other lists of regions and constraints.
+pub struct Rules {
+    regions: Vec<Region>,
+    constraints: Vec<Constraint>,
+    /// For each cell, in row order, the other cells sharing a region with it.
+    peers: Vec<Vec<Coord>>,
+}
+
+impl Rules {
+    /// Construct rules from `regions` and extra `constraints`.
+    pub fn new(regions: Vec<Region>, constraints: Vec<Constraint>) -> Self {
+        let mut rules = Rules {
+            regions,
+            constraints,
+            peers: Vec::new(),
+        };
+        rules.find_peers();
+        rules
+    }
+
+    /// The rules of classic Sudoku: every row, column and box contains each
+    /// value once.
+    pub fn standard() -> Self {
+        let coords: [[Coord; SIZE]; SIZE] = array::from_fn(|i| array::from_fn(|j| (i, j).into()));
+        let regions = [
+            (RegionKind::Row, coords.to_vec()),
+            (RegionKind::Column, coords.cols().to_vec()),
+        ]
+        .into_iter()
+        .flat_map(|(kind, groups)| {
+            groups
+                .into_iter()
+                .map(move |cells| Region::new(kind, cells))
+        })
+        .chain(
+            Layout::standard()
+                .regions()
+                .into_iter()
+                .map(|cells| Region::new(RegionKind::Box, cells)),
+        )
+        .collect();
+
+        Self::new(regions, Vec::new())
+    }
+
+    /// The rules of Jigsaw Sudoku: every row, column and region of `layout`
+    /// contains each value once.
+    pub fn jigsaw(layout: &Layout) -> Self {
+        Self::standard().with_layout(layout)
+    }
+
+    /// Replace the boxes with the regions of `layout`.
+    pub fn with_layout(mut self, layout: &Layout) -> Self {
+        self.regions.retain(|r| r.kind != RegionKind::Box);
+        let at = self
+            .regions
+            .iter()
+            .position(|r| !matches!(r.kind, RegionKind::Row | RegionKind::Column))
+            .unwrap_or(self.regions.len());
+        let boxes = layout
+            .regions()
+            .into_iter()
+            .map(|cells| Region::new(RegionKind::Box, cells));
+        self.regions.splice(at..at, boxes);
+        self.find_peers();
+        self
+    }
+
+    /// Add the two main diagonals as regions, for Sudoku X.
+    pub fn with_diagonals(self) -> Self {
+        get_diagonal_coords()
+            .into_iter()
+            .fold(self, |rules, cells| {
+                rules.with_region(Region::new(RegionKind::Diagonal, cells))
+            })
+    }
+
+    /// Add the extra boxes of Hyper Sudoku (Windoku), one box in from the
+    /// edges of the grid and with a gap of one cell between them.
+    pub fn with_windows(self) -> Self {
+        get_window_coords().into_iter().fold(self, |rules, cells| {
+            rules.with_region(Region::new(RegionKind::Window, cells))
+        })
+    }
+
+    /// Add the anti-knight constraint, so no two cells a knight's move apart
+    /// contain the same value.
+    pub fn with_anti_knight(self) -> Self {
+        self.with_constraint(Constraint::Chess(Piece::Knight))
+    }
+
+    /// Add the anti-king constraint, so no two diagonally neighbouring cells
+    /// contain the same value.
+    pub fn with_anti_king(self) -> Self {
+        self.with_constraint(Constraint::Chess(Piece::King))
+    }
+
+    /// Add the non-consecutive constraint, so no two orthogonally neighbouring
+    /// cells contain consecutive values.
+    pub fn with_non_consecutive(self) -> Self {
+        neighbour_pairs().into_iter().fold(self, |rules, (a, b)| {
+            rules.with_constraint(Constraint::Edge(Edge::new(a, b, Relation::NotConsecutive)))
+        })
+    }
+
+    /// Add the dots of Kropki Sudoku. With the `negative` constraint, the
+    /// neighbours without a dot are neither consecutive nor one double the
+    /// other.
+    pub fn with_dots(self, dots: &[Edge], negative: bool) -> Self {
+        let rules = dots.iter().fold(self, |rules, &dot| {
+            rules.with_constraint(Constraint::Edge(dot))
+        });
+        if !negative {
+            return rules;
+        }
+        neighbour_pairs()
+            .into_iter()
+            .filter(|&pair| dots.iter().all(|dot| dot.cells != pair))
+            .fold(rules, |rules, (a, b)| {
+                rules.with_constraint(Constraint::Edge(Edge::new(a, b, Relation::Neither)))
+            })
+    }
+
+    /// Add `cage` to the rules, for Killer Sudoku: a region whose values also
+    /// have to add up to its sum.
+    pub fn with_cage(self, cage: Cage) -> Self {
+        self.with_region(Region::new(RegionKind::Cage, cage.cells.iter().copied()))
+            .with_constraint(Constraint::Cage(cage))
+    }
+
+    /// Add `region` to the rules.
+    pub fn with_region(mut self, region: Region) -> Self {
+        self.regions.push(region);
+        self.find_peers();
+        self
+    }
+
+    /// Add `constraint` to the rules.
+    pub fn with_constraint(mut self, constraint: Constraint) -> Self {
+        self.constraints.push(constraint);
+        self
+    }
+
+    /// Return a reference to the `regions` field.
+    pub fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+
+    /// Return a reference to the `constraints` field.
+    pub fn constraints(&self) -> &[Constraint] {
+        &self.constraints
+    }
+
+    /// Returns the layout of the boxes, which are irregular for Jigsaw
+    /// Sudoku.
+    pub fn layout(&self) -> Layout {
+        let regions: Vec<&[Coord]> = self
+            .regions
+            .iter()
+            .filter(|r| r.kind == RegionKind::Box)
+            .map(|r| r.cells.as_slice())
+            .collect();
+        Layout::from_regions(&regions)
+    }
+
+    /// Returns the cages of Killer Sudoku, in the order they were added.
+    pub fn cages(&self) -> Vec<&Cage> {
+        self.constraints
+            .iter()
+            .filter_map(|c| match c {
+                Constraint::Cage(cage) => Some(cage),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the dots of Kropki Sudoku, in the order they were added.
+    pub fn dots(&self) -> Vec<&Edge> {
+        self.constraints
+            .iter()
+            .filter_map(|c| match c {
+                Constraint::Edge(edge) if edge.relation.dot().is_some() => Some(edge),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the thermometers, arrows, sandwich sums, greater-than signs
+    /// and even and odd cells, in the order they were added.
+    pub fn lines(&self) -> Vec<&Constraint> {
+        self.constraints
+            .iter()
+            .filter(|c| match c {
+                Constraint::Thermometer(_)
+                | Constraint::Arrow(_)
+                | Constraint::Sandwich(_)
+                | Constraint::Parity(..) => true,
+                Constraint::Edge(edge) => {
+                    matches!(edge.relation, Relation::Less | Relation::Greater)
+                }
+                _ => false,
+            })
+            .collect()
+    }
+
+    /// Returns the parity of `cell` if it's shaded as an even or odd cell.
+    pub fn parity(&self, cell: Coord) -> Option<Parity> {
+        self.constraints.iter().find_map(|c| match c {
+            &Constraint::Parity(c, parity) if c == cell => Some(parity),
+            _ => None,
+        })
+    }
+
+    /// Returns `true` if the rules use the negative constraint of Kropki
+    /// Sudoku.
+    pub fn has_negative_dots(&self) -> bool {
+        self.constraints
+            .iter()
+            .any(|c| matches!(c, Constraint::Edge(e) if e.relation == Relation::Neither))
+    }
+
+    /// Returns the cells that share a region with `cell`, not including
+    /// `cell` itself.
+    pub fn peers(&self, cell: Coord) -> &[Coord] {
+        &self.peers[cell.row * SIZE + cell.col]
+    }
+
+    /// Returns `true` if `cell` is in a region of a kind that's shaded when
+    /// displayed.
+    pub fn is_shaded(&self, cell: Coord) -> bool {
+        self.regions
+            .iter()
+            .any(|r| r.kind.is_shaded() && r.cells.contains(&cell))
+    }
+
+    /// Returns the colour `cell` is shaded with when displayed in colour:
+    /// blue for arrow circles, cyan for even cells, magenta for odd cells and
+    /// grey for cells in shaded regions, or `None` if it isn't shaded.
+    pub fn background(&self, cell: Coord) -> Option<Background> {
+        let is_circle = self
+            .constraints
+            .iter()
+            .any(|c| matches!(c, Constraint::Arrow(arrow) if arrow.circle == cell));
+        if is_circle {
+            Some(Background::Blue)
+        } else if let Some(parity) = self.parity(cell) {
+            match parity {
+                Parity::Even => Some(Background::Cyan),
+                Parity::Odd => Some(Background::Magenta),
+            }
+        } else if self.is_shaded(cell) {
+            Some(Background::Grey)
+        } else {
+            None
+        }
+    }
+
+    /// Returns `g` as it's displayed under these rules, with borders between
+    /// the boxes of the layout, dotted lines around any cages with their sums,
+    /// any Kropki dots, greater-than signs, thermometers and arrows between
+    /// cells, and sandwich sums outside the grid. The cells are written as
+    /// they are, so colours, such as those from `background`, are up to the
+    /// caller.
+    pub fn display<T: fmt::Display>(&self, g: &DisplayableGrid<T>) -> String {
+        let cages: Vec<Cage> = self.cages().into_iter().cloned().collect();
+        let mut sandwiches = Vec::new();
+        let mut marks = Vec::new();
+        for constraint in self.constraints.iter() {
+            match constraint {
+                Constraint::Thermometer(thermo) => marks.extend(thermo.marks()),
+                Constraint::Arrow(arrow) => marks.extend(arrow.marks()),
+                Constraint::Sandwich(sandwich) => sandwiches.push(sandwich),
+                Constraint::Edge(edge) if edge.relation.dot().is_none() => {
+                    marks.extend(edge.mark().map(|mark| (edge.cells, mark)))
+                }
+                _ => {}
+            }
+        }
+        let outline = Outline::new(self.layout())
+            .with_cages(&cages)
+            .with_dots(&self.dots())
+            .with_marks(marks)
+            .with_sandwiches(&sandwiches);
+        g.outlined(&outline).to_string()
+    }
+
+    /// Helper function to work out the peers of every cell from the regions.
+    fn find_peers(&mut self) {
+        self.peers = vec![Vec::new(); SIZE.pow(2)];
+        for region in self.regions.iter() {
+            for &cell in region.cells.iter() {
+                let peers = &mut self.peers[cell.row * SIZE + cell.col];
+                peers.extend(region.cells.iter().filter(|&&c| c != cell));
+            }
+        }
+        for peers in self.peers.iter_mut() {
+            peers.sort();
+            peers.dedup();
+        }
+    }
+
+    /// Build the candidate matrix for the clues in `rows`. Returns the
+    /// coordinate of the first cell left with no candidates as an error.
+    pub(super) fn candidates(&self, rows: &GridArray) -> Result<CandidateMatrix, Coord> {
+        let mut cm = CandidateMatrix::new();
+
+        for (row_i, row) in rows.iter().enumerate() {
+            for (col_i, cell) in row.iter().enumerate() {
+                if let Cell::Clue(n) = cell {
+                    let coord = (row_i, col_i).into();
+                    cm.set_fixed(coord);
+                    cm.eliminate(self.peers(coord), *n)?;
+                }
+            }
+        }
+        self.restrict(rows, &mut cm)?;
+
+        Ok(cm)
+    }
+
+    /// Remove `val` from the candidates of every peer of `cell` after it's
+    /// placed there, then apply the extra constraints. Returns the
+    /// coordinate of the first cell left with no candidates as an error.
+    pub(super) fn eliminate(
+        &self,
+        rows: &GridArray,
+        cm: &mut CandidateMatrix,
+        cell: Coord,
+        val: u8,
+    ) -> Result<(), Coord> {
+        cm.eliminate(self.peers(cell), val)?;
+        self.restrict(rows, cm)
+    }
+
+    /// Helper function to apply every extra constraint to `cm`.
+    fn restrict(&self, rows: &GridArray, cm: &mut CandidateMatrix) -> Result<(), Coord> {
+        self.constraints
+            .iter()
+            .try_for_each(|constraint| constraint.restrict(rows, cm))
+    }
+
+    /// Returns every way the values in `rows` break the rules: values repeated
+    /// in a region, in the order the regions were given, then broken
+    /// constraints. Each is numbered among the regions or constraints of the
+    /// same kind.
+    pub fn conflicts(&self, rows: &GridArray) -> Vec<Conflict> {
+        let mut counts = Vec::new();
+        let mut conflicts = Vec::new();
+
+        for region in self.regions.iter() {
+            let group = counts.iter().filter(|&&kind| kind == region.kind).count();
+            counts.push(region.kind);
+            conflicts.extend(find_conflicts(
+                rows,
+                region.kind.duplicate_error(),
+                group,
+                &region.cells,
+            ));
+        }
+
+        let mut counts = Vec::new();
+        for constraint in self.constraints.iter() {
+            let kind = constraint.error_kind();
+            let group = counts.iter().filter(|&&k| k == kind).count();
+            counts.push(kind);
+            if let Some((val, cells)) = constraint.broken_by(rows) {
+                conflicts.push(Conflict {
+                    kind,
+                    group,
+                    val,
+                    cells,
+                });
+            }
+        }
+
+        conflicts
+    }
+
+    /// Returns `true` if every cell in `rows` is filled without breaking the
+    /// rules.
+    pub fn is_solution(&self, rows: &GridArray) -> bool {
+        rows.iter().flatten().all(|c| c.value().is_some()) && self.conflicts(rows).is_empty()
+    }
+}
+
+/// Helper function to get the lowest and highest value each of `cells` could
+/// hold: its value if it's filled in `rows`, otherwise its lowest and highest
+/// candidates in `cm`.
+fn bounds(rows: &GridArray, cm: &CandidateMatrix, cells: &[Coord]) -> Vec<(u8, u8)> {
+    cells
+        .iter()
+        .map(|&c| match rows[c.row][c.col].value() {
+            Some(n) => (n, n),
+            None => cm.bounds(c).unwrap_or((1, SIZE as u8)),
+        })
+        .collect()
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::{Grid, Toggles, Variant};
+
+    /// Returns standard rules with a cage of `cells` adding up to `sum`.
+    fn caged(cells: &[(usize, usize)], sum: u32) -> Rules {
+        let cells = cells.iter().map(|&c| Coord::from(c));
+        Rules::standard().with_cage(Cage::new(cells, sum))
+    }
+
+    #[test]
+    fn cages_restrict_candidates_to_their_sum() {
+        let g = Grid::with_rules(caged(&[(0, 0), (0, 1)], 3)).unwrap();
+        assert_eq!(g.candidates_at((0, 0).into()), [1, 2]);
+        assert_eq!(g.candidates_at((0, 1).into()), [1, 2]);
+        assert_eq!(g.candidates_at((0, 2).into()).len(), SIZE);
+
+        let g = Grid::with_rules(caged(&[(4, 4), (4, 5), (5, 4)], 24)).unwrap();
+        assert_eq!(g.candidates_at((5, 4).into()), [7, 8, 9]);
+    }
+
+    #[test]
+    fn filling_a_cage_cell_restricts_the_rest() {
+        let mut g = Grid::with_rules(caged(&[(0, 0), (0, 1), (1, 0)], 10)).unwrap();
+        g.update((0, 0).into(), 1).unwrap();
+        // The others make 9 from two different values other than 1.
+        assert_eq!(g.candidates_at((0, 1).into()), [2, 3, 4, 5, 6, 7]);
+        g.update((0, 1).into(), 2).unwrap();
+        assert_eq!(g.candidates_at((1, 0).into()), [7]);
+    }
+
+    #[test]
+    fn contradictory_cages_are_errors() {
+        let empty = [[Cell::Empty; SIZE]; SIZE];
+        let e = Grid::from_rows_with_rules(empty, caged(&[(0, 0), (0, 1)], 18)).unwrap_err();
+        assert_eq!(e.kind, ErrorKind::ContradictoryClues);
+        assert!(Grid::with_rules(caged(&[(0, 0), (0, 1)], 18)).is_err());
+
+        let mut rows = empty;
+        rows[0][0] = Cell::Clue(1);
+        rows[0][1] = Cell::Clue(3);
+        let e = Grid::from_rows_with_rules(rows, caged(&[(0, 0), (0, 1)], 3)).unwrap_err();
+        assert_eq!(e.kind, ErrorKind::WrongSum);
+    }
+
+    #[test]
+    fn chess_moves_remove_candidates() {
+        let mut g = Grid::with_rules(Rules::standard().with_anti_knight()).unwrap();
+        g.update((4, 4).into(), 5).unwrap();
+        for cell in Piece::Knight.moves_from((4, 4).into()) {
+            assert!(!g.candidates_at(cell).contains(&5), "{cell}");
+        }
+        // Diagonal neighbours in other boxes aren't a knight's move away.
+        assert!(g.candidates_at((2, 2).into()).contains(&5));
+
+        let mut g = Grid::with_rules(Rules::standard().with_anti_king()).unwrap();
+        g.update((2, 2).into(), 5).unwrap();
+        assert!(!g.candidates_at((3, 3).into()).contains(&5));
+        assert!(!g.candidates_at((3, 1).into()).contains(&5));
+        assert!(g.candidates_at((4, 3).into()).contains(&5));
+    }
+
+    #[test]
+    fn chess_moves_reject_repeated_values() {
+        let mut g = Grid::with_rules(Rules::standard().with_anti_knight()).unwrap();
+        g.update((4, 4).into(), 3).unwrap();
+        assert!(g.update((2, 3).into(), 3).is_err());
+        assert!(g.update((6, 5).into(), 3).is_err());
+        g.update((2, 2).into(), 3).unwrap();
+
+        let mut g = Grid::with_rules(Rules::standard().with_anti_king()).unwrap();
+        g.update((2, 2).into(), 3).unwrap();
+        assert!(g.update((3, 3).into(), 3).is_err());
+        g.update((3, 4).into(), 3).unwrap();
+
+        let mut rows = [[Cell::Empty; SIZE]; SIZE];
+        rows[4][4] = Cell::Clue(3);
+        rows[2][3] = Cell::Clue(3);
+        let rules = Rules::standard().with_anti_knight();
+        assert_eq!(rules.conflicts(&rows)[0].kind, ErrorKind::AntiKnight);
+    }
+
+    /// Returns an edge with `relation` between cells `a` and `b`.
+    fn dot(a: (usize, usize), b: (usize, usize), relation: Relation) -> Edge {
+        Edge::new(a.into(), b.into(), relation)
+    }
+
+    #[test]
+    fn dots_restrict_their_neighbours() {
+        let rules =
+            Rules::standard().with_dots(&[dot((0, 0), (0, 1), Relation::Consecutive)], false);
+        let mut g = Grid::with_rules(rules).unwrap();
+        g.update((0, 0).into(), 5).unwrap();
+        assert_eq!(g.candidates_at((0, 1).into()), [4, 6]);
+
+        // 5, 7 and 9 have neither a half nor a double, even before either
+        // side is placed.
+        let rules = Rules::standard().with_dots(&[dot((0, 0), (1, 0), Relation::Double)], false);
+        let mut g = Grid::with_rules(rules).unwrap();
+        assert_eq!(g.candidates_at((1, 0).into()), [1, 2, 3, 4, 6, 8]);
+        g.update((1, 0).into(), 4).unwrap();
+        assert_eq!(g.candidates_at((0, 0).into()), [2, 8]);
+        assert!(g.update((0, 0).into(), 3).is_err());
+    }
+
+    #[test]
+    fn negative_constraints_wait_for_a_placed_value() {
+        let mut g = Grid::with_rules(Rules::standard().with_non_consecutive()).unwrap();
+        assert_eq!(g.candidates_at((4, 5).into()).len(), SIZE);
+        g.update((4, 4).into(), 5).unwrap();
+        for cell in [(3, 4), (5, 4), (4, 3), (4, 5)] {
+            assert_eq!(g.candidates_at(cell.into()), [1, 2, 3, 7, 8, 9], "{cell:?}");
+        }
+        assert!(g.candidates_at((4, 6).into()).contains(&4));
+
+        // Without a dot, 2 rules out 1 and 3 as consecutive, and 4 as double.
+        let rules =
+            Rules::standard().with_dots(&[dot((0, 0), (0, 1), Relation::Consecutive)], true);
+        let mut g = Grid::with_rules(rules).unwrap();
+        assert_eq!(g.candidates_at((1, 0).into()).len(), SIZE);
+        g.update((0, 0).into(), 2).unwrap();
+        assert_eq!(g.candidates_at((1, 0).into()), [5, 6, 7, 8, 9]);
+        assert_eq!(g.candidates_at((0, 1).into()), [1, 3]);
+    }
+
+    #[test]
+    fn edges_given_either_way_round_agree() {
+        // The value in (0, 1) is less than the one in (0, 0), so the edge is
+        // stored from (0, 0) as greater.
+        let edge = Edge::new((0, 1).into(), (0, 0).into(), Relation::Less);
+        assert_eq!(edge.cells, ((0, 0).into(), (0, 1).into()));
+        assert_eq!(edge.relation, Relation::Greater);
+        assert_eq!(Relation::Consecutive.flipped(), Relation::Consecutive);
+
+        let rules = Rules::standard().with_constraint(Constraint::Edge(edge));
+        let mut g = Grid::with_rules(rules.clone()).unwrap();
+        g.update((0, 0).into(), 3).unwrap();
+        assert_eq!(g.candidates_at((0, 1).into()), [1, 2]);
+
+        let mut g = Grid::with_rules(rules).unwrap();
+        g.update((0, 1).into(), 7).unwrap();
+        assert_eq!(g.candidates_at((0, 0).into()), [8, 9]);
+    }
+
+    /// Returns standard rules with `constraint` added.
+    fn with(constraint: Constraint) -> Rules {
+        Rules::standard().with_constraint(constraint)
+    }
+
+    /// Returns `cells` as coordinates.
+    fn coords(cells: &[(usize, usize)]) -> Vec<Coord> {
+        cells.iter().map(|&c| Coord::from(c)).collect()
+    }
+
+    #[test]
+    fn thermometers_increase_from_the_bulb() {
+        let cells = coords(&[(0, 0), (0, 1), (0, 2)]);
+        let mut g = Grid::with_rules(with(Constraint::Thermometer(Thermometer { cells }))).unwrap();
+        assert_eq!(g.candidates_at((0, 0).into()), [1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(g.candidates_at((0, 2).into()), [3, 4, 5, 6, 7, 8, 9]);
+
+        g.update((0, 1).into(), 4).unwrap();
+        assert_eq!(g.candidates_at((0, 0).into()), [1, 2, 3]);
+        assert_eq!(g.candidates_at((0, 2).into()), [5, 6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn arrows_bound_their_circle_and_cells() {
+        let arrow = Arrow {
+            circle: (4, 4).into(),
+            cells: coords(&[(4, 5), (5, 6)]),
+        };
+        let mut g = Grid::with_rules(with(Constraint::Arrow(arrow))).unwrap();
+        // Two cells add up to at least 2, and leave each other at least 1.
+        assert_eq!(g.candidates_at((4, 4).into()), [2, 3, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(g.candidates_at((4, 5).into()), [1, 2, 3, 4, 5, 6, 7, 8]);
+
+        g.update((4, 4).into(), 5).unwrap();
+        assert_eq!(g.candidates_at((4, 5).into()), [1, 2, 3, 4]);
+        g.update((4, 5).into(), 3).unwrap();
+        assert_eq!(g.candidates_at((5, 6).into()), [2]);
+    }
+
+    #[test]
+    fn sandwiches_find_their_ends() {
+        // Only 2 to 8 add up to 35, so the 1 and 9 are at either end.
+        let g = Grid::with_rules(with(Constraint::Sandwich(Sandwich::row(0, 35)))).unwrap();
+        for j in 1..SIZE - 1 {
+            assert_eq!(g.candidates_at((0, j).into()), [2, 3, 4, 5, 6, 7, 8]);
+        }
+        assert_eq!(g.candidates_at((0, 0).into()).len(), SIZE);
+
+        let mut g = Grid::with_rules(with(Constraint::Sandwich(Sandwich::row(0, 10)))).unwrap();
+        g.update((0, 0).into(), 1).unwrap();
+        g.update((0, 4).into(), 9).unwrap();
+        // Three values from 2 to 8 add up to 10 only as 2, 3 and 5.
+        assert_eq!(g.candidates_at((0, 2).into()), [2, 3, 5]);
+    }
+
+    #[test]
+    fn broken_lines_are_conflicts() {
+        let mut rows = [[Cell::Empty; SIZE]; SIZE];
+        rows[0][0] = Cell::Clue(5);
+        rows[0][1] = Cell::Clue(3);
+        let thermo = Thermometer {
+            cells: coords(&[(0, 0), (0, 1)]),
+        };
+        let conflicts = with(Constraint::Thermometer(thermo)).conflicts(&rows);
+        assert_eq!(conflicts[0].kind, ErrorKind::BrokenThermometer);
+
+        let mut rows = [[Cell::Empty; SIZE]; SIZE];
+        rows[4][4] = Cell::Clue(5);
+        rows[4][5] = Cell::Clue(1);
+        rows[5][6] = Cell::Clue(1);
+        let arrow = Arrow {
+            circle: (4, 4).into(),
+            cells: coords(&[(4, 5), (5, 6)]),
+        };
+        let conflicts = with(Constraint::Arrow(arrow)).conflicts(&rows);
+        assert_eq!(conflicts[0].kind, ErrorKind::BrokenArrow);
+
+        let mut rows = [[Cell::Empty; SIZE]; SIZE];
+        rows[0][0] = Cell::Clue(1);
+        rows[0][1] = Cell::Clue(5);
+        rows[0][2] = Cell::Clue(9);
+        let conflicts = with(Constraint::Sandwich(Sandwich::row(0, 3))).conflicts(&rows);
+        assert_eq!(conflicts[0].kind, ErrorKind::BrokenSandwich);
+        assert_eq!(conflicts[0].cells, coords(&[(0, 0), (0, 1), (0, 2)]));
+    }
+
+    #[test]
+    fn lines_are_read_from_a_puzzle_line() {
+        let line = format!("{}+t111213/a555667/r1=35", "0".repeat(SIZE.pow(2)));
+        let g = crate::logic::parse_puzzle(&line, Variant::Standard, Toggles::default()).unwrap();
+        assert_eq!(g.rules().constraints().len(), 3);
+        // The thermometer rules out the top of the range, and the sandwich
+        // the 1 and 9.
+        assert_eq!(g.candidates_at((0, 1).into()), [2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(g.candidates_at((4, 4).into()), [2, 3, 4, 5, 6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn parity_restricts_and_conflicts() {
+        let rules = with(Constraint::Parity((0, 0).into(), Parity::Even))
+            .with_constraint(Constraint::Parity((0, 1).into(), Parity::Odd));
+        let mut g = Grid::with_rules(rules.clone()).unwrap();
+        assert_eq!(g.candidates_at((0, 0).into()), [2, 4, 6, 8]);
+        assert_eq!(g.candidates_at((0, 1).into()), [1, 3, 5, 7, 9]);
+        assert!(g.update((0, 0).into(), 3).is_err());
+        g.update((0, 0).into(), 4).unwrap();
+
+        let mut rows = [[Cell::Empty; SIZE]; SIZE];
+        rows[0][1] = Cell::Clue(2);
+        let conflicts = rules.conflicts(&rows);
+        assert_eq!(conflicts[0].kind, ErrorKind::WrongParity);
+        assert_eq!(conflicts[0].cells, coords(&[(0, 1)]));
+        let e = Grid::from_rows_with_rules(rows, rules).unwrap_err();
+        assert_eq!(e.kind, ErrorKind::WrongParity);
+    }
+
+    #[test]
+    fn shades_circles_parity_and_shaded_regions() {
+        let arrow = Arrow {
+            circle: (4, 4).into(),
+            cells: coords(&[(4, 5)]),
+        };
+        let rules = Variant::Hyper
+            .rules()
+            .with_constraint(Constraint::Arrow(arrow))
+            .with_constraint(Constraint::Parity((0, 0).into(), Parity::Even))
+            .with_constraint(Constraint::Parity((0, 1).into(), Parity::Odd));
+        assert_eq!(rules.background((4, 4).into()), Some(Background::Blue));
+        assert_eq!(rules.background((0, 0).into()), Some(Background::Cyan));
+        assert_eq!(rules.background((0, 1).into()), Some(Background::Magenta));
+        assert_eq!(rules.background((1, 1).into()), Some(Background::Grey));
+        assert_eq!(rules.background((0, 4).into()), None);
+
+        // Displaying only writes the cells, leaving the colours to the caller.
+        let mut rows = [[Cell::Empty; SIZE]; SIZE];
+        rows[4][4] = Cell::Clue(5);
+        let shown = rules.display(&DisplayableGrid(rows));
+        assert!(shown.contains('5') && !shown.contains('\x1b'));
+    }
+
+    #[test]
+    fn greater_than_signs_restrict_and_conflict() {
+        let sign = Edge::new((0, 0).into(), (1, 0).into(), Relation::Greater);
+        let rules = with(Constraint::Edge(sign));
+        let mut g = Grid::with_rules(rules.clone()).unwrap();
+        assert_eq!(g.candidates_at((0, 0).into()), [2, 3, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(g.candidates_at((1, 0).into()), [1, 2, 3, 4, 5, 6, 7, 8]);
+        g.update((1, 0).into(), 6).unwrap();
+        assert_eq!(g.candidates_at((0, 0).into()), [7, 8, 9]);
+        assert!(g.update((0, 0).into(), 5).is_err());
+
+        let mut rows = [[Cell::Empty; SIZE]; SIZE];
+        rows[0][0] = Cell::Clue(3);
+        rows[1][0] = Cell::Clue(6);
+        let conflicts = rules.conflicts(&rows);
+        assert_eq!(conflicts[0].kind, ErrorKind::BrokenInequality);
+        assert_eq!(conflicts[0].cells, coords(&[(0, 0), (1, 0)]));
+    }
+}
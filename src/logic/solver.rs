@@ -1,34 +1,125 @@
 use std::cmp::Ordering;
 
-use super::{Coord, Grid};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use super::{
+    grid::{ErrorKind, GridError},
+    Coord, Grid,
+};
+
+/// Trait for the puzzles the solver works on: a single `Grid`, or a
+/// `MultiGrid` of grids sharing cells, where placing a value in a shared cell
+/// also places it in every grid it's in.
+pub trait Puzzle: Clone {
+    /// The type identifying a cell of the puzzle.
+    type Target: Copy;
+
+    /// Returns `true` if every cell is filled without breaking the rules.
+    fn is_solved(&self) -> bool;
+
+    /// Returns the number of cells left to fill.
+    fn empty_cells(&self) -> usize;
+
+    /// Returns the empty cell with the least valid candidates, using `rng`
+    /// to choose between cells with the same number.
+    fn get_min_candidates_cell<R: Rng + ?Sized>(&self, rng: &mut R) -> Self::Target;
+
+    /// Gets the candidates at `target` in ascending order.
+    fn candidates_at(&self, target: Self::Target) -> Vec<u8>;
+
+    /// Place `val` at `target`, returning an error if it isn't a candidate or
+    /// leaves another cell with no candidates.
+    fn update(&mut self, target: Self::Target, val: u8) -> Result<(), GridError>;
+
+    /// Remove candidate `val` from `target`.
+    fn remove_candidate(&mut self, target: Self::Target, val: u8) -> bool;
+}
+
+impl Puzzle for Grid {
+    type Target = Coord;
+
+    fn is_solved(&self) -> bool {
+        self.solved
+    }
+
+    fn empty_cells(&self) -> usize {
+        self.empty_cell_count
+    }
+
+    fn get_min_candidates_cell<R: Rng + ?Sized>(&self, rng: &mut R) -> Coord {
+        Grid::get_min_candidates_cell(self, rng)
+    }
+
+    fn candidates_at(&self, cell: Coord) -> Vec<u8> {
+        Grid::candidates_at(self, cell)
+    }
+
+    fn update(&mut self, cell: Coord, val: u8) -> Result<(), GridError> {
+        Grid::update(self, cell, val)
+    }
+
+    fn remove_candidate(&mut self, cell: Coord, val: u8) -> bool {
+        Grid::remove_candidate(self, cell, val)
+    }
+}
 
 /// Struct to model a decision point in the algorithm.
-struct Decision {
-    state: Grid,
+struct Decision<P: Puzzle> {
+    state: P,
     val: u8,
-    target: Coord,
+    target: P::Target,
 }
 
-/// Solve grid `g` using a backtracking algorithm with heurisitics
-pub fn solve_backtracking_heuristics(mut g: Grid) -> Option<Grid> {
-    let mut history: Vec<Decision> = Vec::new();
+/// Solve puzzle `g` using a backtracking algorithm with heurisitics. Returns
+/// an error of kind `NoSolution` if every branch of the search fails.
+pub fn solve_backtracking_heuristics<P: Puzzle>(g: P) -> Result<P, GridError> {
+    // A fixed seed means a puzzle with more than one solution always gives the
+    // same one, and keeps the solver free of any source of entropy.
+    solve_with_rng(g, &mut StdRng::seed_from_u64(0))
+}
+
+/// Solve puzzle `g` like `solve_backtracking_heuristics`, using `rng` to make
+/// the choices between equally good options. When the puzzle has more than
+/// one solution, the same `rng` state always finds the same one.
+pub fn solve_with_rng<P: Puzzle, R: Rng + ?Sized>(g: P, rng: &mut R) -> Result<P, GridError> {
+    solve_with_limit(g, usize::MAX, rng)
+}
+
+/// Solve puzzle `g` like `solve_with_rng`, but give up with an error of kind
+/// `TimedOut` after backtracking `limit` times. Searches that go wrong early
+/// can take far longer than usual, so it's often quicker to start again with
+/// different choices than to finish them.
+pub fn solve_with_limit<P: Puzzle, R: Rng + ?Sized>(
+    mut g: P,
+    limit: usize,
+    rng: &mut R,
+) -> Result<P, GridError> {
+    let mut history: Vec<Decision<P>> = Vec::new();
+    let mut backtracks = 0;
+
+    'outer: while !g.is_solved() {
+        if backtracks > limit {
+            return Err(GridError::with_details(
+                ErrorKind::TimedOut,
+                format!("Gave up after backtracking {limit} times"),
+            ));
+        }
 
-    'outer: while !g.solved {
         // Get cell with least valid candidates
-        let target = g.get_min_candidates_cell();
-        let candidates = g.candidates_at(target);
+        let target = g.get_min_candidates_cell(rng);
+        let mut candidates = g.candidates_at(target);
 
         match candidates.len().cmp(&1) {
             // If the cell has more than one candidate, the choice we make
             // may be wrong, so we store the current state of the grid.
             Ordering::Greater => {
-                let backtrack = g.clone();
-
-                for val in candidates.clone().into_iter() {
-                    if g.update(target, val).is_ok() {
+                candidates.shuffle(rng);
+                for val in candidates.into_iter() {
+                    let mut attempt = g.clone();
+                    if attempt.update(target, val).is_ok() {
                         // If candidate valid, push decision onto history stack; continue while loop
                         history.push(Decision {
-                            state: backtrack,
+                            state: std::mem::replace(&mut g, attempt),
                             val,
                             target,
                         });
@@ -36,6 +127,10 @@ pub fn solve_backtracking_heuristics(mut g: Grid) -> Option<Grid> {
                         continue 'outer;
                     }
                 }
+
+                // None of the candidates were valid, so a previous decision
+                // was incorrect.
+                g = backtrack(&mut history, &mut backtracks)?;
             }
             // If the cell has exactly one candidate, that's not recorded as
             // a decision point, because we're choosing the only option.
@@ -43,18 +138,110 @@ pub fn solve_backtracking_heuristics(mut g: Grid) -> Option<Grid> {
             // we know a previous decision was incorrect, so we backtrack.
             Ordering::Equal => {
                 if g.update(target, candidates[0]).is_err() {
-                    let dec = history.pop().expect("Shouldn't run out of history");
+                    g = backtrack(&mut history, &mut backtracks)?;
+                }
+            }
+            // A cell with no candidates means a previous decision was
+            // incorrect, or the puzzle can't be solved at all.
+            Ordering::Less => g = backtrack(&mut history, &mut backtracks)?,
+        }
+    }
+
+    Ok(g)
+}
+
+/// Return the puzzle as it was at the last decision point, with the value that
+/// was chosen there removed as a candidate, because it led to an error. If
+/// there are no decisions left to undo, the puzzle has no solution. Counts the
+/// backtrack in `backtracks`.
+fn backtrack<P: Puzzle>(
+    history: &mut Vec<Decision<P>>,
+    backtracks: &mut usize,
+) -> Result<P, GridError> {
+    *backtracks += 1;
+    let dec = history
+        .pop()
+        .ok_or_else(|| GridError::new(ErrorKind::NoSolution, (0, 0).into(), 0))?;
+
+    let mut g = dec.state;
+    g.remove_candidate(dec.target, dec.val);
+    Ok(g)
+}
+
+/// Count the solutions of puzzle `g`, stopping once `limit` have been found.
+/// A puzzle is uniquely solvable if this returns 1 with a `limit` of 2.
+pub fn count_solutions<P: Puzzle>(g: P, limit: usize) -> usize {
+    find_solutions(g, limit, usize::MAX).map_or(0, |(_, count)| count)
+}
 
-                    // Set grid to the state at the last decision point, then
-                    // remove the value that was chosen for the target as a
-                    // candidate, because it led to an error.
-                    g = dec.state;
-                    g.remove_candidate(dec.target, dec.val);
+/// Search puzzle `g` for solutions as `count_solutions` does, stopping once
+/// `limit` have been found, and return the first one found along with the
+/// count, so a puzzle can be solved and checked for uniqueness in one search.
+/// Returns an error of kind `NoSolution` if there are none, or `TimedOut`
+/// after reaching `backtrack_limit` dead ends.
+pub fn find_solutions<P: Puzzle>(
+    g: P,
+    limit: usize,
+    backtrack_limit: usize,
+) -> Result<(P, usize), GridError> {
+    // The order cells are tried in doesn't change the count, so a fixed seed
+    // keeps the search repeatable.
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut first = None;
+    let mut count = 0;
+    let mut backtracks = 0;
+    let mut stack = vec![g];
+
+    'outer: while let Some(mut g) = stack.pop() {
+        while !g.is_solved() {
+            if backtracks > backtrack_limit {
+                return Err(GridError::with_details(
+                    ErrorKind::TimedOut,
+                    format!("Gave up after backtracking {backtrack_limit} times"),
+                ));
+            }
+            if g.empty_cells() == 0 {
+                backtracks += 1;
+                continue 'outer;
+            }
+
+            let target = g.get_min_candidates_cell(&mut rng);
+            let candidates = g.candidates_at(target);
+
+            match candidates.len().cmp(&1) {
+                // Every candidate is a separate branch of the search, so
+                // they're all explored.
+                Ordering::Greater => {
+                    for val in candidates.into_iter() {
+                        let mut branch = g.clone();
+                        if branch.update(target, val).is_ok() {
+                            stack.push(branch);
+                        }
+                    }
+                    continue 'outer;
+                }
+                Ordering::Equal => {
+                    if g.update(target, candidates[0]).is_err() {
+                        backtracks += 1;
+                        continue 'outer;
+                    }
+                }
+                Ordering::Less => {
+                    backtracks += 1;
+                    continue 'outer;
                 }
             }
-            Ordering::Less => panic!("This should never happen"),
+        }
+
+        count += 1;
+        first.get_or_insert(g);
+        if count >= limit {
+            break;
         }
     }
 
-    Some(g)
+    match first {
+        Some(g) => Ok((g, count)),
+        None => Err(GridError::new(ErrorKind::NoSolution, (0, 0).into(), 0)),
+    }
 }
@@ -0,0 +1,11 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Enum representing the background colours cells can be shaded with when a
+/// grid is displayed somewhere with colour, such as the terminal. The logic
+/// only says which cells are shaded; drawing the colour is left to the
+/// interface.
+pub enum Background {
+    Blue,
+    Cyan,
+    Magenta,
+    Grey,
+}
@@ -0,0 +1,139 @@
+use std::{fmt, str::FromStr};
+
+use super::{grid::GridError, Coord, SIZE};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+/// Enum representing the symmetries the pattern of clues in a generated
+/// puzzle can have. Clues are removed in orbits, i.e. all the cells a cell is
+/// mapped to by the symmetry are cleared together.
+pub enum Symmetry {
+    #[default]
+    None,
+    /// Rotating the grid by 180° leaves the pattern unchanged.
+    Rotational180,
+    /// Rotating the grid by 90° leaves the pattern unchanged.
+    Rotational90,
+    /// Reflecting the grid in its main (top-left to bottom-right) diagonal.
+    Diagonal,
+    /// Reflecting the grid top to bottom, in its horizontal centre line.
+    MirrorHorizontal,
+    /// Reflecting the grid left to right, in its vertical centre line.
+    MirrorVertical,
+    /// All rotations and reflections of the square.
+    Dihedral,
+}
+
+impl Symmetry {
+    /// Every symmetry, in the order they're offered to the player.
+    pub const ALL: [Symmetry; 7] = [
+        Symmetry::None,
+        Symmetry::Rotational180,
+        Symmetry::Rotational90,
+        Symmetry::Diagonal,
+        Symmetry::MirrorHorizontal,
+        Symmetry::MirrorVertical,
+        Symmetry::Dihedral,
+    ];
+
+    /// Returns the cells `cell` is mapped to by the symmetry, including
+    /// itself, in ascending order without repeats.
+    pub fn orbit(&self, cell: Coord) -> Vec<Coord> {
+        let n = SIZE - 1;
+        let (r, c) = cell.into();
+        let images = match self {
+            Symmetry::None => vec![(r, c)],
+            Symmetry::Rotational180 => vec![(r, c), (n - r, n - c)],
+            Symmetry::Rotational90 => vec![(r, c), (c, n - r), (n - r, n - c), (n - c, r)],
+            Symmetry::Diagonal => vec![(r, c), (c, r)],
+            Symmetry::MirrorHorizontal => vec![(r, c), (n - r, c)],
+            Symmetry::MirrorVertical => vec![(r, c), (r, n - c)],
+            Symmetry::Dihedral => vec![
+                (r, c),
+                (c, n - r),
+                (n - r, n - c),
+                (n - c, r),
+                (c, r),
+                (n - r, c),
+                (r, n - c),
+                (n - c, n - r),
+            ],
+        };
+
+        let mut orbit: Vec<Coord> = images.into_iter().map(Coord::from).collect();
+        orbit.sort();
+        orbit.dedup();
+        orbit
+    }
+
+    /// Returns every orbit of the grid under the symmetry, so each cell
+    /// appears in exactly one of them.
+    pub fn orbits(&self) -> Vec<Vec<Coord>> {
+        (0..SIZE.pow(2))
+            .map(|i| Coord::from((i / SIZE, i % SIZE)))
+            .map(|cell| (cell, self.orbit(cell)))
+            .filter(|(cell, orbit)| orbit[0] == *cell)
+            .map(|(_, orbit)| orbit)
+            .collect()
+    }
+}
+
+impl fmt::Display for Symmetry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Symmetry::None => "none",
+            Symmetry::Rotational180 => "rotational",
+            Symmetry::Rotational90 => "rotational-90",
+            Symmetry::Diagonal => "diagonal",
+            Symmetry::MirrorHorizontal => "horizontal",
+            Symmetry::MirrorVertical => "vertical",
+            Symmetry::Dihedral => "dihedral",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Enable parsing a symmetry from its name, ignoring case.
+impl FromStr for Symmetry {
+    type Err = GridError;
+
+    fn from_str(s: &str) -> Result<Self, GridError> {
+        let s = s.to_lowercase();
+        Symmetry::ALL
+            .into_iter()
+            .find(|sym| sym.to_string() == s)
+            .ok_or_else(|| {
+                let names: Vec<String> = Symmetry::ALL.iter().map(|s| s.to_string()).collect();
+                GridError::invalid_format(format!(
+                    "Unknown symmetry `{s}`, expected one of {}",
+                    names.join(", ")
+                ))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orbits_split_the_grid() {
+        for symmetry in Symmetry::ALL {
+            let mut seen = [[false; SIZE]; SIZE];
+            for orbit in symmetry.orbits() {
+                for &cell in &orbit {
+                    let (r, c) = cell.into();
+                    assert!(!seen[r][c], "{symmetry}: {cell} is in two orbits");
+                    seen[r][c] = true;
+                    assert!(
+                        symmetry.orbit(cell) == orbit,
+                        "{symmetry}: {cell} maps outside its orbit"
+                    );
+                }
+            }
+            assert!(
+                seen.iter().flatten().all(|&s| s),
+                "{symmetry}: missing cells"
+            );
+        }
+    }
+}
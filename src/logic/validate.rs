@@ -0,0 +1,113 @@
+use std::{array, fmt};
+
+use super::{grid::ErrorKind, Coord, Grid, GridArray, Rules, SIZE};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Struct representing a value that appears more than once in a region, or a
+/// broken constraint. `group` is the index of the region among those of the
+/// same kind, or of the constraint, and `cells` holds the coordinates of every
+/// cell involved.
+pub struct Conflict {
+    pub kind: ErrorKind,
+    pub group: usize,
+    pub val: u8,
+    pub cells: Vec<Coord>,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (val, group, n) = (self.val, self.kind.group_name(), self.group + 1);
+        match self.kind {
+            ErrorKind::BrokenConstraint
+            | ErrorKind::BrokenDot
+            | ErrorKind::BrokenInequality
+            | ErrorKind::WrongParity => {
+                write!(f, "Value `{val}` breaks {group} {n} at")
+            }
+            ErrorKind::WrongSum | ErrorKind::BrokenArrow | ErrorKind::BrokenSandwich => {
+                write!(f, "Values adding up to `{val}` break {group} {n} at")
+            }
+            ErrorKind::AntiKnight | ErrorKind::AntiKing => {
+                write!(f, "Value `{val}` is repeated a {group} apart at")
+            }
+            ErrorKind::BrokenThermometer => {
+                write!(f, "Value `{val}` doesn't increase along {group} {n} at")
+            }
+            ErrorKind::ConsecutiveNeighbours => {
+                write!(f, "Value `{val}` is next to a consecutive value at")
+            }
+            ErrorKind::MissingDot => {
+                write!(
+                    f,
+                    "Value `{val}` is next to a consecutive or double value without a dot at"
+                )
+            }
+            _ => write!(f, "Value `{val}` is repeated in {group} {n} at"),
+        }?;
+        for (i, cell) in self.cells.iter().enumerate() {
+            let sep = if i == 0 { " " } else { ", " };
+            write!(f, "{sep}{}", Coord::from((cell.row + 1, cell.col + 1)))?;
+        }
+        Ok(())
+    }
+}
+
+/// Trait for types that can report conflicting values, i.e. the same value
+/// appearing more than once in a region, or values that break a constraint.
+pub trait Validate {
+    /// Returns every conflict, ordered by regions, then constraints.
+    fn conflicts(&self) -> Vec<Conflict>;
+
+    /// Returns `true` if no value is repeated in any group.
+    fn is_valid(&self) -> bool {
+        self.conflicts().is_empty()
+    }
+
+    /// Returns the coordinates of every cell involved in a conflict.
+    fn conflicting_cells(&self) -> Vec<Coord> {
+        let mut cells: Vec<Coord> = self.conflicts().into_iter().flat_map(|c| c.cells).collect();
+        cells.sort();
+        cells.dedup();
+        cells
+    }
+}
+
+impl Validate for GridArray {
+    fn conflicts(&self) -> Vec<Conflict> {
+        Rules::standard().conflicts(self)
+    }
+}
+
+impl Validate for Grid {
+    fn conflicts(&self) -> Vec<Conflict> {
+        self.rules().conflicts(self.rows())
+    }
+}
+
+/// Helper function to find the values repeated within the region made of
+/// `cells` in `rows`, reported as conflicts of `kind` in group `group`.
+pub(super) fn find_conflicts(
+    rows: &GridArray,
+    kind: ErrorKind,
+    group: usize,
+    cells: &[Coord],
+) -> Vec<Conflict> {
+    let mut seen: [Vec<Coord>; SIZE] = array::from_fn(|_| Vec::new());
+    for &coord in cells.iter() {
+        match rows[coord.row][coord.col].value() {
+            Some(n) if (n as usize) <= SIZE => seen[n as usize - 1].push(coord),
+            _ => {}
+        }
+    }
+
+    seen.into_iter()
+        .enumerate()
+        .filter(|(_, cells)| cells.len() > 1)
+        .map(|(i, cells)| Conflict {
+            kind,
+            group,
+            val: (i + 1) as u8,
+            cells,
+        })
+        .collect()
+}
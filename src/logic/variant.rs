@@ -0,0 +1,169 @@
+use std::{array, fmt, str::FromStr};
+
+use super::{grid::GridError, Coord, Rules, ORDER, SIZE};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+/// Enum representing the named rule sets a `Grid` can follow. `Standard` is
+/// classic Sudoku, `Diagonal` (Sudoku X) also needs both main diagonals to
+/// contain each value once, `Hyper` (Windoku) adds four extra boxes, `Jigsaw`
+/// replaces the boxes with irregular regions, `Killer` adds cages whose
+/// values add up to a given sum, and `Kropki` adds dots between neighbouring
+/// cells whose values are consecutive or one double the other.
+pub enum Variant {
+    #[default]
+    Standard,
+    Diagonal,
+    Hyper,
+    Jigsaw,
+    Killer,
+    Kropki,
+}
+
+impl Variant {
+    /// Returns the rules of the variant. Jigsaw, Killer and Kropki puzzles
+    /// each have their own layout, cages or dots, so they use the classic
+    /// rules until those are given with `Rules::with_layout`,
+    /// `Rules::with_cage` or `Rules::with_dots`.
+    pub fn rules(&self) -> Rules {
+        match self {
+            Variant::Standard | Variant::Jigsaw | Variant::Killer | Variant::Kropki => {
+                Rules::standard()
+            }
+            Variant::Diagonal => Rules::standard().with_diagonals(),
+            Variant::Hyper => Rules::standard().with_windows(),
+        }
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Variant::Standard => "standard",
+            Variant::Diagonal => "diagonal",
+            Variant::Hyper => "hyper",
+            Variant::Jigsaw => "jigsaw",
+            Variant::Killer => "killer",
+            Variant::Kropki => "kropki",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Enable parsing a variant from its name, ignoring case.
+impl FromStr for Variant {
+    type Err = GridError;
+
+    fn from_str(s: &str) -> Result<Self, GridError> {
+        match s.to_lowercase().as_str() {
+            "standard" | "classic" => Ok(Variant::Standard),
+            "diagonal" | "x" => Ok(Variant::Diagonal),
+            "hyper" | "windoku" | "w" => Ok(Variant::Hyper),
+            "jigsaw" | "j" => Ok(Variant::Jigsaw),
+            "killer" | "k" => Ok(Variant::Killer),
+            "kropki" | "dots" | "d" => Ok(Variant::Kropki),
+            _ => Err(GridError::invalid_format(format!(
+                "Unknown variant `{s}`, expected standard, diagonal, hyper, jigsaw, killer or kropki"
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+/// Struct representing the constraints that can be switched on for any
+/// variant, on top of its own rules.
+pub struct Toggles {
+    /// No two cells a knight's move apart contain the same value.
+    pub anti_knight: bool,
+    /// No two diagonally neighbouring cells contain the same value.
+    pub anti_king: bool,
+    /// No two orthogonally neighbouring cells contain consecutive values.
+    pub non_consecutive: bool,
+}
+
+impl Toggles {
+    /// Returns `rules` with the constraints that are switched on added.
+    pub fn apply(&self, mut rules: Rules) -> Rules {
+        if self.anti_knight {
+            rules = rules.with_anti_knight();
+        }
+        if self.anti_king {
+            rules = rules.with_anti_king();
+        }
+        if self.non_consecutive {
+            rules = rules.with_non_consecutive();
+        }
+        rules
+    }
+
+    /// Returns the names of the constraints that are switched on.
+    fn names(&self) -> Vec<&'static str> {
+        [
+            (self.anti_knight, "anti-knight"),
+            (self.anti_king, "anti-king"),
+            (self.non_consecutive, "non-consecutive"),
+        ]
+        .into_iter()
+        .filter_map(|(on, name)| on.then_some(name))
+        .collect()
+    }
+}
+
+/// Formats the constraints that are switched on separated by commas, or
+/// `none`.
+impl fmt::Display for Toggles {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.names().as_slice() {
+            [] => write!(f, "none"),
+            names => write!(f, "{}", names.join(",")),
+        }
+    }
+}
+
+/// Enable parsing the constraints to switch on from their names separated by
+/// commas, ignoring case, where `none` switches none on.
+impl FromStr for Toggles {
+    type Err = GridError;
+
+    fn from_str(s: &str) -> Result<Self, GridError> {
+        let mut toggles = Toggles::default();
+        for name in s.split(',').map(str::trim) {
+            match name.to_lowercase().as_str() {
+                "anti-knight" | "knight" | "n" => toggles.anti_knight = true,
+                "anti-king" | "king" | "k" => toggles.anti_king = true,
+                "non-consecutive" | "nonconsecutive" | "c" => toggles.non_consecutive = true,
+                "none" | "" => {}
+                _ => {
+                    return Err(GridError::invalid_format(format!(
+                        "Unknown constraint `{name}`, expected anti-knight, anti-king, non-consecutive or none"
+                    )))
+                }
+            }
+        }
+        Ok(toggles)
+    }
+}
+
+/// Helper function to get the coordinates of the cells on the two main
+/// diagonals, top-left to bottom-right first.
+pub fn get_diagonal_coords() -> [[Coord; SIZE]; 2] {
+    [
+        array::from_fn(|i| (i, i).into()),
+        array::from_fn(|i| (i, SIZE - 1 - i).into()),
+    ]
+}
+
+/// Helper function to get the coordinates of the cells in the extra boxes of
+/// Hyper Sudoku, in row order. Each starts one cell further in than a box, so
+/// for a 9x9 grid they're at rows and columns 2-4 and 6-8.
+pub fn get_window_coords() -> Vec<Vec<Coord>> {
+    let starts: Vec<usize> = (0..ORDER - 1).map(|k| k * (ORDER + 1) + 1).collect();
+    starts
+        .iter()
+        .flat_map(|&row| starts.iter().map(move |&col| (row, col)))
+        .map(|(row, col)| {
+            (0..SIZE)
+                .map(|i| Coord::from((row + i / ORDER, col + i % ORDER)))
+                .collect()
+        })
+        .collect()
+}
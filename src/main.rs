@@ -1,3 +1,10 @@
+use std::process;
+
 fn main() {
-    scanlan_sudoku::run();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() {
+        scanlan_sudoku::run();
+    } else {
+        process::exit(scanlan_sudoku::run_command(&args));
+    }
 }
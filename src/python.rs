@@ -0,0 +1,165 @@
+use std::str::FromStr;
+
+use pyo3::{create_exception, exceptions::PyValueError, prelude::*};
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::logic::{
+    self, count_solutions, generate_with_rng, parse_puzzle, rate, solve_backtracking_heuristics,
+    Difficulty, Grid, Symmetry, Toggles, Variant,
+};
+
+create_exception!(
+    scanlan_sudoku,
+    GridError,
+    PyValueError,
+    "Raised when a puzzle can't be parsed, breaks its rules or has no solution. \
+     The arguments are the message and the kind of error, e.g. `NoSolution`."
+);
+
+/// Turn `e` into a `GridError` exception carrying its message and kind.
+fn error(e: logic::GridError) -> PyErr {
+    GridError::new_err((
+        e.to_string().trim_end().to_string(),
+        format!("{:?}", e.kind),
+    ))
+}
+
+#[pyclass(name = "Grid", module = "scanlan_sudoku", frozen)]
+/// A puzzle. Grids can't be changed from Python, so solving returns a new
+/// one.
+pub struct PyGrid(Grid);
+
+#[pymethods]
+impl PyGrid {
+    /// Parse a puzzle in line format, as `parse` does.
+    #[new]
+    #[pyo3(signature = (line, variant=None, constraints=None))]
+    fn new(line: &str, variant: Option<&str>, constraints: Option<&str>) -> PyResult<Self> {
+        parse(line, variant, constraints)
+    }
+
+    /// Returns a solved copy of the grid. Raises `GridError` if it has no
+    /// solution.
+    fn solve(&self, py: Python<'_>) -> PyResult<PyGrid> {
+        let g = self.0.clone();
+        py.detach(|| solve_backtracking_heuristics(g))
+            .map(PyGrid)
+            .map_err(error)
+    }
+
+    /// Count the solutions of the grid, stopping once `limit` have been found.
+    #[pyo3(signature = (limit=2))]
+    fn count_solutions(&self, py: Python<'_>, limit: usize) -> usize {
+        let g = self.0.clone();
+        py.detach(|| count_solutions(g, limit))
+    }
+
+    /// Rate how hard the grid is for a person to solve, returning the
+    /// difficulty's name and the score.
+    fn rate(&self) -> PyResult<(String, u32)> {
+        let rating = rate(&self.0).map_err(error)?;
+        Ok((rating.difficulty.to_string(), rating.score))
+    }
+
+    /// Returns the values of the grid as a list of rows, with 0 for empty
+    /// cells.
+    fn rows(&self) -> Vec<Vec<u32>> {
+        // `u8`s would become `bytes` rather than a list of numbers.
+        self.0
+            .rows()
+            .iter()
+            .map(|row| row.iter().map(|c| c.value().unwrap_or(0).into()).collect())
+            .collect()
+    }
+
+    /// Write the grid in line format.
+    fn to_line(&self) -> String {
+        self.0.to_line()
+    }
+
+    /// Whether every cell is filled without breaking the rules.
+    #[getter]
+    fn solved(&self) -> bool {
+        self.0.solved
+    }
+
+    /// The number of cells left to fill.
+    #[getter]
+    fn empty_cells(&self) -> usize {
+        self.0.empty_cell_count
+    }
+
+    fn __str__(&self) -> String {
+        self.0.to_line()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Grid({:?})", self.0.to_line())
+    }
+}
+
+/// Parse a puzzle in line format. `variant` and `constraints` are named as on
+/// the command line, and default to standard with none.
+#[pyfunction]
+#[pyo3(signature = (line, variant=None, constraints=None))]
+fn parse(line: &str, variant: Option<&str>, constraints: Option<&str>) -> PyResult<PyGrid> {
+    parse_puzzle(
+        line,
+        named(variant, Variant::default())?,
+        named(constraints, Toggles::default())?,
+    )
+    .map(PyGrid)
+    .map_err(error)
+}
+
+/// Generate a puzzle of `difficulty`. Giving a `seed` always gives the same
+/// puzzle. `symmetry`, `variant` and `constraints` are named as on the command
+/// line.
+#[pyfunction]
+#[pyo3(signature = (difficulty="medium", seed=None, symmetry=None, variant=None, constraints=None))]
+fn generate(
+    py: Python<'_>,
+    difficulty: &str,
+    seed: Option<u64>,
+    symmetry: Option<&str>,
+    variant: Option<&str>,
+    constraints: Option<&str>,
+) -> PyResult<PyGrid> {
+    let d = Difficulty::from_str(difficulty).map_err(error)?;
+    let symmetry = named(symmetry, Symmetry::default())?;
+    let variant = named(variant, Variant::default())?;
+    let toggles = named(constraints, Toggles::default())?;
+
+    py.detach(|| match seed {
+        Some(seed) => generate_with_rng(
+            d,
+            symmetry,
+            variant,
+            toggles,
+            &mut StdRng::seed_from_u64(seed),
+        ),
+        None => generate_with_rng(d, symmetry, variant, toggles, &mut rand::thread_rng()),
+    })
+    .map(PyGrid)
+    .map_err(error)
+}
+
+/// Parse `val` as one of a set of names, or return `default` if it wasn't
+/// given.
+fn named<T: FromStr<Err = logic::GridError>>(val: Option<&str>, default: T) -> PyResult<T> {
+    match val {
+        Some(val) => val.parse().map_err(error),
+        None => Ok(default),
+    }
+}
+
+/// Sudoku puzzles: parsing, solving, counting solutions, rating and
+/// generating.
+#[pymodule]
+fn scanlan_sudoku(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyGrid>()?;
+    m.add("GridError", m.py().get_type::<GridError>())?;
+    m.add_function(wrap_pyfunction!(parse, m)?)?;
+    m.add_function(wrap_pyfunction!(generate, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,786 @@
+use std::fmt;
+
+use crate::logic::{
+    Cell, Constraint, Coord, Edge, Grid, GridArray, Layout, Parity, Relation, Rules, Sandwich,
+    ORDER, SIZE,
+};
+
+pub use html::to_html;
+pub use pdf::to_pdf;
+pub use png::{to_png, PageSizeError};
+pub use svg::to_svg;
+
+/// Width of an A4 page in points.
+pub const PAGE_WIDTH: f32 = 595.0;
+/// Height of an A4 page in points.
+pub const PAGE_HEIGHT: f32 = 842.0;
+
+/// Space left around the edges of a page.
+const MARGIN: f32 = 48.0;
+/// Space left between neighbouring puzzles on a page.
+const GAP: f32 = 24.0;
+/// Height of the title above each puzzle.
+const TITLE_HEIGHT: f32 = 18.0;
+/// Width of the lines between cells in the same box.
+const THIN_LINE: f32 = 0.5;
+/// Width of the lines between boxes and around the grid.
+const THICK_LINE: f32 = 2.0;
+/// Length of the dashes and gaps of a dashed line, as a multiple of its
+/// width.
+const DASH: f32 = 3.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Struct representing a colour by its red, green and blue components.
+pub struct Colour(pub u8, pub u8, pub u8);
+
+impl Colour {
+    /// Colour of lines, titles and clues.
+    pub const BLACK: Colour = Colour(0, 0, 0);
+    /// Colour of the page, and of white Kropki dots.
+    pub const WHITE: Colour = Colour(0xff, 0xff, 0xff);
+    /// Colour of values that aren't clues, such as those of a solution.
+    pub const SOLUTION: Colour = Colour(0x1f, 0x4e, 0xb4);
+    /// Colour of candidate pencil marks.
+    pub const PENCIL: Colour = Colour(0x80, 0x80, 0x80);
+    /// Colour of the cells in shaded regions.
+    pub const SHADE: Colour = Colour(0xe4, 0xe4, 0xe4);
+    /// Colour of thermometers and of the marks of even and odd cells.
+    pub const MARK: Colour = Colour(0xc8, 0xc8, 0xc8);
+    /// Colour of arrows and their circles.
+    pub const ARROW: Colour = Colour(0x70, 0x70, 0x70);
+    /// Colour of selected cells.
+    pub const SELECTED: Colour = Colour(0xff, 0xf0, 0xa0);
+    /// Colour of cells whose values break the rules.
+    pub const CONFLICT_FILL: Colour = Colour(0xfa, 0xd0, 0xd0);
+    /// Colour of values that break the rules.
+    pub const CONFLICT: Colour = Colour(0xc0, 0x1c, 0x1c);
+}
+
+/// Formats the colour as a hex code, e.g. `#1f4eb4`.
+impl fmt::Display for Colour {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Enum representing how a line is drawn.
+pub enum LineStyle {
+    /// A solid line with square ends, so lines drawn in pieces meet cleanly.
+    Solid,
+    /// A dashed line, as around a Killer cage.
+    Dashed,
+    /// A solid line with round ends, as along a thermometer or arrow.
+    Round,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Enum representing something drawn on a page. Positions are in points from
+/// the top-left corner of the page.
+pub enum Shape {
+    /// A straight line `width` points wide.
+    Line {
+        from: (f32, f32),
+        to: (f32, f32),
+        width: f32,
+        colour: Colour,
+        style: LineStyle,
+    },
+    /// A filled rectangle with its top-left corner at `x` and `y`.
+    Rect {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        fill: Colour,
+    },
+    /// A circle, filled and outlined with a colour and width if given.
+    Circle {
+        centre: (f32, f32),
+        radius: f32,
+        fill: Option<Colour>,
+        stroke: Option<(Colour, f32)>,
+    },
+    /// Text in a sans-serif font, Helvetica or the bundled DejaVu Sans, centred
+    /// on `x`, with its baseline at `y`.
+    Text {
+        x: f32,
+        y: f32,
+        size: f32,
+        bold: bool,
+        fill: Colour,
+        text: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Struct representing a page of shapes, with its size in points.
+pub struct Page {
+    pub width: f32,
+    pub height: f32,
+    pub shapes: Vec<Shape>,
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Options for `book`. `per_page` is the number of puzzles on each page, and
+/// `candidates` is whether the candidates of empty cells are drawn as pencil
+/// marks.
+pub struct RenderOptions {
+    pub per_page: usize,
+    pub candidates: bool,
+}
+
+impl Default for RenderOptions {
+    /// Four puzzles to a page, without pencil marks.
+    fn default() -> Self {
+        RenderOptions {
+            per_page: 4,
+            candidates: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// Cells to pick out when drawing a grid: `selected` cells are highlighted,
+/// and if `conflicts` is set, so are the cells whose values break the rules,
+/// with their values in red.
+pub struct Highlights {
+    pub selected: Vec<Coord>,
+    pub conflicts: bool,
+}
+
+/// Lay out `puzzles` on A4 pages, `options.per_page` to a page, followed by
+/// pages of `answers` as the answer key. Each is titled with its position, so
+/// the answers should be given in the same order as the puzzles, and can be
+/// left empty to leave out the answer key.
+pub fn book(puzzles: &[Grid], answers: &[Grid], options: &RenderOptions) -> Vec<Page> {
+    let per_page = options.per_page.max(1);
+    let mut pages: Vec<Page> = puzzles
+        .chunks(per_page)
+        .enumerate()
+        .map(|(i, chunk)| layout_page(chunk, i * per_page, "Puzzle", per_page, options))
+        .collect();
+
+    let answer_options = RenderOptions {
+        candidates: false,
+        ..*options
+    };
+    pages.extend(
+        answers.chunks(per_page).enumerate().map(|(i, chunk)| {
+            layout_page(chunk, i * per_page, "Solution", per_page, &answer_options)
+        }),
+    );
+
+    pages
+}
+
+/// Lay out `grids` on a page with room for `per_page` of them, in rows
+/// filled from left to right. Each is titled with `title` and its position,
+/// counting from `first + 1`.
+fn layout_page(
+    grids: &[Grid],
+    first: usize,
+    title: &str,
+    per_page: usize,
+    options: &RenderOptions,
+) -> Page {
+    let (rows, cols) = slots(per_page);
+    let slot_width = (PAGE_WIDTH - 2.0 * MARGIN + GAP) / cols as f32;
+    let slot_height = (PAGE_HEIGHT - 2.0 * MARGIN + GAP) / rows as f32;
+    let band = grids.iter().map(|g| band(g.rules())).fold(0.0, f32::max);
+    let cell_size = (slot_width - GAP).min(slot_height - GAP - TITLE_HEIGHT) / (SIZE as f32 + band);
+    let (grid_size, band) = (SIZE as f32 * cell_size, band * cell_size);
+
+    let mut shapes = Vec::new();
+    for (i, g) in grids.iter().enumerate() {
+        let (slot_x, slot_y) = (
+            MARGIN + (i % cols) as f32 * slot_width,
+            MARGIN + (i / cols) as f32 * slot_height,
+        );
+        let x = slot_x + (slot_width - GAP - grid_size - band) / 2.0 + band;
+        let y = slot_y + TITLE_HEIGHT + band;
+        shapes.push(Shape::Text {
+            x: x + grid_size / 2.0,
+            y: slot_y + TITLE_HEIGHT / 2.0,
+            size: 11.0,
+            bold: false,
+            fill: Colour::BLACK,
+            text: format!("{title} {}", first + i + 1),
+        });
+        shapes.extend(draw_grid(
+            g,
+            (x, y),
+            cell_size,
+            options.candidates,
+            &Highlights::default(),
+        ));
+    }
+
+    Page {
+        width: PAGE_WIDTH,
+        height: PAGE_HEIGHT,
+        shapes,
+    }
+}
+
+/// Returns the number of rows and columns of puzzles to fit `n` on a portrait
+/// page, with no more columns than rows.
+fn slots(n: usize) -> (usize, usize) {
+    let rows = (1..).find(|r| r * r >= n).unwrap_or(1);
+    (rows, n.div_ceil(rows))
+}
+
+/// Returns a page just big enough for `g`, with cells `cell_size` points
+/// wide and a margin of half a cell, drawn as by `draw_grid`.
+pub fn grid_page(g: &Grid, cell_size: f32, candidates: bool, highlights: &Highlights) -> Page {
+    let candidates = |cell| match candidates {
+        true => g.candidates_at(cell),
+        false => Vec::new(),
+    };
+    page(g.rules(), g.rows(), candidates, cell_size, highlights)
+}
+
+/// Returns a page just big enough for `rows` under `rules`, as for
+/// `grid_page`. Unlike a `Grid`, the values don't have to follow the rules,
+/// so this can show where they're broken.
+pub fn rows_page(rules: &Rules, rows: &GridArray, cell_size: f32, highlights: &Highlights) -> Page {
+    page(rules, rows, |_| Vec::new(), cell_size, highlights)
+}
+
+/// Helper function to lay out a page just big enough for `rows`, with a
+/// margin of half a cell.
+fn page(
+    rules: &Rules,
+    rows: &GridArray,
+    candidates: impl Fn(Coord) -> Vec<u8>,
+    cell_size: f32,
+    highlights: &Highlights,
+) -> Page {
+    let margin = cell_size / 2.0;
+    let band = band(rules) * cell_size;
+    let size = SIZE as f32 * cell_size + 2.0 * margin + band;
+    let origin = (margin + band, margin + band);
+
+    Page {
+        width: size,
+        height: size,
+        shapes: draw(rules, rows, candidates, origin, cell_size, highlights),
+    }
+}
+
+/// Returns the width, in cells, of the space a grid under `rules` needs
+/// above and left of it for sandwich sums.
+fn band(rules: &Rules) -> f32 {
+    let sandwiches = rules
+        .constraints()
+        .iter()
+        .any(|c| matches!(c, Constraint::Sandwich(_)));
+    if sandwiches {
+        0.6
+    } else {
+        0.0
+    }
+}
+
+/// Returns the shapes that draw `g` with its top-left corner at `origin` and
+/// cells `cell_size` points wide: the cells of shaded regions, thin lines
+/// between cells and thick ones between boxes and around the grid, the
+/// decorations of its constraints, clues in bold, other values in the
+/// solution colour, and, if `candidates` is set, the candidates of each empty
+/// cell as pencil marks. Cells are picked out as `highlights` says.
+pub fn draw_grid(
+    g: &Grid,
+    origin: (f32, f32),
+    cell_size: f32,
+    candidates: bool,
+    highlights: &Highlights,
+) -> Vec<Shape> {
+    let candidates = |cell| match candidates {
+        true => g.candidates_at(cell),
+        false => Vec::new(),
+    };
+    draw(
+        g.rules(),
+        g.rows(),
+        candidates,
+        origin,
+        cell_size,
+        highlights,
+    )
+}
+
+/// Helper function to draw `rows` under `rules` as `draw_grid` describes,
+/// with `candidates` giving the pencil marks of each empty cell.
+fn draw(
+    rules: &Rules,
+    rows: &GridArray,
+    candidates: impl Fn(Coord) -> Vec<u8>,
+    origin: (f32, f32),
+    cell_size: f32,
+    highlights: &Highlights,
+) -> Vec<Shape> {
+    let conflicts: Vec<Coord> = match highlights.conflicts {
+        true => rules
+            .conflicts(rows)
+            .into_iter()
+            .flat_map(|c| c.cells)
+            .collect(),
+        false => Vec::new(),
+    };
+    let background = |cell: Coord| {
+        if conflicts.contains(&cell) {
+            Colour::CONFLICT_FILL
+        } else if highlights.selected.contains(&cell) {
+            Colour::SELECTED
+        } else if rules.is_shaded(cell) {
+            Colour::SHADE
+        } else {
+            Colour::WHITE
+        }
+    };
+    let pen = Pen { origin, cell_size };
+    let mut shapes = Vec::new();
+
+    for cell in cells() {
+        let (x, y) = pen.corner(cell);
+        let fill = background(cell);
+        if fill != Colour::WHITE {
+            shapes.push(Shape::Rect {
+                x,
+                y,
+                width: cell_size,
+                height: cell_size,
+                fill,
+            });
+        }
+        match rules.parity(cell) {
+            Some(Parity::Even) => shapes.push(Shape::Rect {
+                x: x + cell_size * 0.12,
+                y: y + cell_size * 0.12,
+                width: cell_size * 0.76,
+                height: cell_size * 0.76,
+                fill: Colour::MARK,
+            }),
+            Some(Parity::Odd) => shapes.push(Shape::Circle {
+                centre: pen.centre(cell),
+                radius: cell_size * 0.38,
+                fill: Some(Colour::MARK),
+                stroke: None,
+            }),
+            None => (),
+        }
+    }
+
+    for constraint in rules.constraints() {
+        match constraint {
+            Constraint::Thermometer(thermo) => pen.thermometer(&mut shapes, &thermo.cells),
+            Constraint::Arrow(arrow) => pen.arrow(&mut shapes, arrow.circle, &arrow.cells),
+            _ => (),
+        }
+    }
+
+    pen.grid_lines(&mut shapes, &rules.layout());
+
+    for constraint in rules.constraints() {
+        match constraint {
+            Constraint::Cage(cage) => pen.cage(&mut shapes, &cage.cells),
+            Constraint::Edge(edge) => pen.edge(&mut shapes, edge),
+            _ => (),
+        }
+    }
+
+    for cell in cells() {
+        let centre = pen.centre(cell);
+        let colour = |normal| match conflicts.contains(&cell) {
+            true => Colour::CONFLICT,
+            false => normal,
+        };
+        match rows[cell.row][cell.col] {
+            Cell::Clue(n) => shapes.push(value(
+                n,
+                centre,
+                cell_size * 0.6,
+                true,
+                colour(Colour::BLACK),
+            )),
+            Cell::Filled(n) => shapes.push(value(
+                n,
+                centre,
+                cell_size * 0.6,
+                false,
+                colour(Colour::SOLUTION),
+            )),
+            Cell::Empty => {
+                let (x, y) = pen.corner(cell);
+                let mark_size = cell_size / ORDER as f32;
+                for n in candidates(cell).into_iter().filter(|&n| n != 0) {
+                    let i = n as usize - 1;
+                    let centre = (
+                        x + ((i % ORDER) as f32 + 0.5) * mark_size,
+                        y + ((i / ORDER) as f32 + 0.5) * mark_size,
+                    );
+                    shapes.push(value(n, centre, mark_size * 0.7, false, Colour::PENCIL));
+                }
+            }
+        }
+    }
+
+    for constraint in rules.constraints() {
+        match constraint {
+            Constraint::Cage(cage) => {
+                if let Some(&first) = cage.cells.iter().min() {
+                    pen.cage_sum(&mut shapes, first, cage.sum, background(first));
+                }
+            }
+            Constraint::Sandwich(sandwich) => pen.sandwich(&mut shapes, sandwich),
+            _ => (),
+        }
+    }
+
+    shapes
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Helper struct for drawing on a grid with its top-left corner at `origin`
+/// and cells `cell_size` points wide.
+struct Pen {
+    origin: (f32, f32),
+    cell_size: f32,
+}
+
+impl Pen {
+    /// Returns the top-left corner of `cell`.
+    fn corner(&self, cell: Coord) -> (f32, f32) {
+        (
+            self.origin.0 + cell.col as f32 * self.cell_size,
+            self.origin.1 + cell.row as f32 * self.cell_size,
+        )
+    }
+
+    /// Returns the centre of `cell`.
+    fn centre(&self, cell: Coord) -> (f32, f32) {
+        let (x, y) = self.corner(cell);
+        (x + self.cell_size / 2.0, y + self.cell_size / 2.0)
+    }
+
+    /// Returns a line from `from` to `to`, `width` cells wide.
+    fn line(
+        &self,
+        from: (f32, f32),
+        to: (f32, f32),
+        width: f32,
+        colour: Colour,
+        style: LineStyle,
+    ) -> Shape {
+        Shape::Line {
+            from,
+            to,
+            width: width * self.cell_size,
+            colour,
+            style,
+        }
+    }
+
+    /// Draw thin lines between cells, thick ones between the boxes of
+    /// `layout`, and a thick line around the grid. Thick lines are drawn
+    /// last so the thin ones don't cut across them.
+    fn grid_lines(&self, shapes: &mut Vec<Shape>, layout: &Layout) {
+        let mut thick = Vec::new();
+        for i in 0..SIZE {
+            for j in 1..SIZE {
+                let width = |a: (usize, usize), b: (usize, usize)| match layout
+                    .is_border(Coord::from(a), Coord::from(b))
+                {
+                    true => THICK_LINE,
+                    false => THIN_LINE,
+                };
+                let lines = [
+                    (
+                        self.corner((i, j).into()),
+                        (0.0, 1.0),
+                        width((i, j - 1), (i, j)),
+                    ),
+                    (
+                        self.corner((j, i).into()),
+                        (1.0, 0.0),
+                        width((j - 1, i), (j, i)),
+                    ),
+                ];
+                for ((x, y), (dx, dy), width) in lines {
+                    let line = Shape::Line {
+                        from: (x, y),
+                        to: (x + dx * self.cell_size, y + dy * self.cell_size),
+                        width,
+                        colour: Colour::BLACK,
+                        style: LineStyle::Solid,
+                    };
+                    match width == THICK_LINE {
+                        true => thick.push(line),
+                        false => shapes.push(line),
+                    }
+                }
+            }
+        }
+        shapes.extend(thick);
+
+        let (x0, y0) = self.origin;
+        let (x1, y1) = (
+            x0 + SIZE as f32 * self.cell_size,
+            y0 + SIZE as f32 * self.cell_size,
+        );
+        let corners = [(x0, y0), (x1, y0), (x1, y1), (x0, y1)];
+        for k in 0..corners.len() {
+            shapes.push(Shape::Line {
+                from: corners[k],
+                to: corners[(k + 1) % corners.len()],
+                width: THICK_LINE,
+                colour: Colour::BLACK,
+                style: LineStyle::Solid,
+            });
+        }
+    }
+
+    /// Draw a thermometer through `cells`, with its bulb in the first.
+    fn thermometer(&self, shapes: &mut Vec<Shape>, cells: &[Coord]) {
+        if let Some(&bulb) = cells.first() {
+            shapes.push(Shape::Circle {
+                centre: self.centre(bulb),
+                radius: self.cell_size * 0.36,
+                fill: Some(Colour::MARK),
+                stroke: None,
+            });
+        }
+        for w in cells.windows(2) {
+            let (from, to) = (self.centre(w[0]), self.centre(w[1]));
+            shapes.push(self.line(from, to, 0.25, Colour::MARK, LineStyle::Round));
+        }
+    }
+
+    /// Draw an arrow from the circle at `circle` through `cells`, with its
+    /// head in the last.
+    fn arrow(&self, shapes: &mut Vec<Shape>, circle: Coord, cells: &[Coord]) {
+        let radius = self.cell_size * 0.4;
+        shapes.push(Shape::Circle {
+            centre: self.centre(circle),
+            radius,
+            fill: None,
+            stroke: Some((Colour::ARROW, self.cell_size * 0.04)),
+        });
+
+        let mut points: Vec<(f32, f32)> = [circle]
+            .iter()
+            .chain(cells)
+            .map(|&c| self.centre(c))
+            .collect();
+        if points.len() < 2 {
+            return;
+        }
+        // The line starts at the edge of the circle rather than its centre.
+        let (dx, dy) = direction(points[0], points[1]);
+        points[0] = (points[0].0 + dx * radius, points[0].1 + dy * radius);
+        for w in points.windows(2) {
+            shapes.push(self.line(w[0], w[1], 0.04, Colour::ARROW, LineStyle::Round));
+        }
+
+        let n = points.len();
+        let (tip, (dx, dy)) = (points[n - 1], direction(points[n - 2], points[n - 1]));
+        let length = self.cell_size * 0.2;
+        for (sx, sy) in [(-dx - dy, dx - dy), (-dx + dy, -dx - dy)] {
+            let end = (
+                tip.0 + sx * length / 2f32.sqrt(),
+                tip.1 + sy * length / 2f32.sqrt(),
+            );
+            shapes.push(self.line(tip, end, 0.04, Colour::ARROW, LineStyle::Round));
+        }
+    }
+
+    /// Draw a dashed line just inside the edge of the cage made of `cells`.
+    fn cage(&self, shapes: &mut Vec<Shape>, cells: &[Coord]) {
+        let inset = self.cell_size * 0.08;
+        let inside = |row: usize, col: usize, dr: isize, dc: isize| {
+            let (r, c) = (row.checked_add_signed(dr), col.checked_add_signed(dc));
+            matches!((r, c), (Some(r), Some(c)) if cells.contains(&(r, c).into()))
+        };
+
+        for &cell in cells {
+            let (x, y) = self.corner(cell);
+            let (row, col) = (cell.row, cell.col);
+            // Where a side ends depends on the cells past that end: it stops
+            // short of an outside corner, meets the same side of the next
+            // cell, or reaches round an inside corner.
+            let end = |ahead: bool, diagonal: bool| match (ahead, diagonal) {
+                (false, _) => inset,
+                (true, false) => 0.0,
+                (true, true) => -inset,
+            };
+            let size = self.cell_size;
+            // Each side is given as whether the cell past it is in the cage,
+            // its start and end, and whether the cells before and after it
+            // along the side, and past those, are in the cage.
+            let sides = [
+                (
+                    inside(row, col, -1, 0),
+                    (x, y + inset),
+                    (x + size, y + inset),
+                    [(0, -1), (-1, -1), (0, 1), (-1, 1)],
+                ),
+                (
+                    inside(row, col, 1, 0),
+                    (x, y + size - inset),
+                    (x + size, y + size - inset),
+                    [(0, -1), (1, -1), (0, 1), (1, 1)],
+                ),
+                (
+                    inside(row, col, 0, -1),
+                    (x + inset, y),
+                    (x + inset, y + size),
+                    [(-1, 0), (-1, -1), (1, 0), (1, -1)],
+                ),
+                (
+                    inside(row, col, 0, 1),
+                    (x + size - inset, y),
+                    (x + size - inset, y + size),
+                    [(-1, 0), (-1, 1), (1, 0), (1, 1)],
+                ),
+            ];
+            for (past, from, to, around) in sides {
+                if past {
+                    continue;
+                }
+                let [before, before_past, after, after_past] =
+                    around.map(|(dr, dc)| inside(row, col, dr, dc));
+                let (start, finish) = (end(before, before_past), end(after, after_past));
+                let (from, to) = match from.0 == to.0 {
+                    true => ((from.0, from.1 + start), (to.0, to.1 - finish)),
+                    false => ((from.0 + start, from.1), (to.0 - finish, to.1)),
+                };
+                shapes.push(self.line(from, to, 0.03, Colour::BLACK, LineStyle::Dashed));
+            }
+        }
+    }
+
+    /// Write the sum of a cage in the top-left corner of `cell`, its first
+    /// cell, over a patch of `background` that breaks the dashed line.
+    fn cage_sum(&self, shapes: &mut Vec<Shape>, cell: Coord, sum: u32, background: Colour) {
+        let (x, y) = self.corner(cell);
+        let size = self.cell_size * 0.24;
+        let text = sum.to_string();
+        let width = size * 0.6 * text.len() as f32;
+        shapes.push(Shape::Rect {
+            x: x + self.cell_size * 0.04,
+            y: y + self.cell_size * 0.04,
+            width: width + size * 0.3,
+            height: size * 1.1,
+            fill: background,
+        });
+        shapes.push(Shape::Text {
+            x: x + self.cell_size * 0.04 + (width + size * 0.3) / 2.0,
+            y: y + self.cell_size * 0.04 + size * 0.9,
+            size,
+            bold: false,
+            fill: Colour::BLACK,
+            text,
+        });
+    }
+
+    /// Draw the dot or greater-than sign of `edge` across the line between
+    /// its cells.
+    fn edge(&self, shapes: &mut Vec<Shape>, edge: &Edge) {
+        let (a, b) = (self.centre(edge.cells.0), self.centre(edge.cells.1));
+        let middle = ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0);
+        let radius = self.cell_size * 0.12;
+        let stroke = Some((Colour::BLACK, self.cell_size * 0.03));
+
+        match edge.relation {
+            Relation::Consecutive => shapes.push(Shape::Circle {
+                centre: middle,
+                radius,
+                fill: Some(Colour::WHITE),
+                stroke,
+            }),
+            Relation::Double => shapes.push(Shape::Circle {
+                centre: middle,
+                radius,
+                fill: Some(Colour::BLACK),
+                stroke,
+            }),
+            Relation::Less | Relation::Greater => {
+                // The sign points at the cell with the smaller value.
+                let (dx, dy) = match edge.relation {
+                    Relation::Less => direction(b, a),
+                    _ => direction(a, b),
+                };
+                let size = self.cell_size * 0.1;
+                let tip = (middle.0 + dx * size, middle.1 + dy * size);
+                for side in [-1.0, 1.0] {
+                    let end = (
+                        middle.0 - dx * size - dy * side * size * 1.2,
+                        middle.1 - dy * size + dx * side * size * 1.2,
+                    );
+                    shapes.push(self.line(tip, end, 0.04, Colour::BLACK, LineStyle::Round));
+                }
+            }
+            Relation::NotConsecutive | Relation::Neither => (),
+        }
+    }
+
+    /// Write a sandwich sum left of its row or above its column.
+    fn sandwich(&self, shapes: &mut Vec<Shape>, sandwich: &Sandwich) {
+        let Some(&first) = sandwich.cells.first() else {
+            return;
+        };
+        let size = self.cell_size * 0.35;
+        let (x, y) = self.centre(first);
+        let (x, y) = match sandwich.is_row() {
+            true => (self.origin.0 - self.cell_size * 0.3, y + size * 0.35),
+            false => (x, self.origin.1 - self.cell_size * 0.15),
+        };
+        shapes.push(Shape::Text {
+            x,
+            y,
+            size,
+            bold: false,
+            fill: Colour::BLACK,
+            text: sandwich.sum.to_string(),
+        });
+    }
+}
+
+/// Returns the unit vector pointing from `a` to `b`.
+fn direction(a: (f32, f32), b: (f32, f32)) -> (f32, f32) {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length = dx.hypot(dy).max(f32::EPSILON);
+    (dx / length, dy / length)
+}
+
+/// Returns every cell of the grid, row by row.
+fn cells() -> impl Iterator<Item = Coord> {
+    (0..SIZE.pow(2)).map(|i| Coord::from((i / SIZE, i % SIZE)))
+}
+
+/// Returns the text of value `n` in a cell, centred on `centre`.
+fn value(n: u8, centre: (f32, f32), size: f32, bold: bool, fill: Colour) -> Shape {
+    Shape::Text {
+        x: centre.0,
+        // Digits are about 0.7 of the font size tall, so the baseline is
+        // dropped by half that to centre them.
+        y: centre.1 + size * 0.35,
+        size,
+        bold,
+        fill,
+        text: symbol(n),
+    }
+}
+
+/// Returns the symbol for value `n`, as it's written in line format.
+fn symbol(n: u8) -> String {
+    char::from_digit(n as u32, 36)
+        .unwrap_or('?')
+        .to_ascii_uppercase()
+        .to_string()
+}
+
+mod html;
+mod pdf;
+mod png;
+mod svg;
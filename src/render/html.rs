@@ -0,0 +1,339 @@
+use std::fmt::Write;
+
+use super::{band, grid_page, to_svg, Highlights};
+use crate::logic::{count_solutions, to_line, Grid, ORDER, SIZE};
+
+/// Width of a cell in the drawings embedded in the page. They're scaled to
+/// fit, so this only sets the proportions of lines and text.
+const CELL_SIZE: f32 = 40.0;
+
+/// Write `puzzles` as a single HTML page that can be played offline in a
+/// browser, one after another. Each is drawn as by `grid_page`, with cells
+/// that can be clicked and typed into, pencil marks, and any values repeated
+/// in a region shown as they're entered. If `answers` are given, in the same
+/// order as the puzzles, a hash of each is embedded so a finished grid can be
+/// checked without giving the solution away. Puzzles with more than one
+/// solution get no hash, as a different solution is just as right, so a
+/// finished grid is only checked against the rules the page knows: its
+/// regions, but not cages, dots or other constraints.
+pub fn to_html(puzzles: &[Grid], answers: &[Grid]) -> String {
+    let mut sections = String::new();
+    let mut data = Vec::new();
+
+    for (i, g) in puzzles.iter().enumerate() {
+        let page = grid_page(g, CELL_SIZE, false, &Highlights::default());
+        // Writing to a `String` can't fail.
+        let _ = write!(
+            sections,
+            "<section class=\"puzzle\">\n<h2>Puzzle {}</h2>\n\
+             <div class=\"board\">\n{}<div class=\"cells\"></div>\n</div>\n\
+             <div class=\"pad\"></div>\n\
+             <div class=\"controls\"><button data-action=\"pencil\">Pencil marks: off</button> \
+             <button data-action=\"check\">Check</button> \
+             <button data-action=\"reset\">Reset</button> \
+             <span class=\"status\"></span></div>\n</section>\n",
+            i + 1,
+            to_svg(&page)
+        );
+
+        let regions: Vec<String> = g
+            .rules()
+            .regions()
+            .iter()
+            .map(|r| {
+                let cells: Vec<String> = r
+                    .cells
+                    .iter()
+                    .map(|c| (c.row * SIZE + c.col).to_string())
+                    .collect();
+                format!("[{}]", cells.join(","))
+            })
+            .collect();
+        let hash = match answers.get(i) {
+            Some(answer) if count_solutions(g.clone(), 2) == 1 => {
+                format!("\"{:016x}\"", solution_hash(&to_line(answer.rows())))
+            }
+            _ => String::from("null"),
+        };
+        let origin = CELL_SIZE / 2.0 + band(g.rules()) * CELL_SIZE;
+        data.push(format!(
+            "{{\"cells\":\"{}\",\"regions\":[{}],\"onlyRegions\":{},\"hash\":{hash},\"origin\":{},\"cell\":{}}}",
+            to_line(g.rows()),
+            regions.join(","),
+            g.rules().constraints().is_empty(),
+            origin / page.width,
+            CELL_SIZE / page.width
+        ));
+    }
+
+    TEMPLATE
+        .replace("{sections}", &sections)
+        .replace("{order}", &ORDER.to_string())
+        .replace("{size}", &SIZE.to_string())
+        .replace("{puzzles}", &format!("[{}]", data.join(",\n")))
+}
+
+/// Returns the 64-bit FNV-1a hash of a solution in line format. The page
+/// works out the same hash of the player's grid to check it.
+fn solution_hash(line: &str) -> u64 {
+    line.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// The page, with `{sections}` for the puzzles' markup, `{order}` and
+/// `{size}` for the grid's dimensions and `{puzzles}` for their data.
+const TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>Sudoku</title>
+<style>
+body { font-family: Helvetica, Arial, sans-serif; max-width: 40rem; margin: 2rem auto; padding: 0 1rem; }
+.board { position: relative; user-select: none; }
+.board svg { display: block; width: 100%; height: auto; }
+.cells div { position: absolute; box-sizing: border-box; cursor: pointer; display: flex;
+  align-items: center; justify-content: center; font-size: calc(var(--cell) * 0.6); color: #1f4eb4; }
+.cells .clue { cursor: default; }
+.cells .selected { background: rgba(255, 224, 64, 0.45); }
+.cells .conflict { background: rgba(240, 96, 96, 0.35); color: #c01c1c; }
+.cells .marks { display: grid; grid-template-columns: repeat({order}, 1fr); width: 100%; height: 100%;
+  font-size: calc(var(--cell) * 0.7 / {order}); color: #808080; }
+.cells .marks span { display: flex; align-items: center; justify-content: center; }
+.pad, .controls { margin: 0.5rem 0; }
+.pad button { min-width: 2.2rem; }
+.status { margin-left: 0.5rem; }
+.solved .status { color: #1a7f37; font-weight: bold; }
+</style>
+</head>
+<body>
+<h1>Sudoku</h1>
+<p>Click a cell, then type a value or use the buttons below the grid. Backspace clears a cell,
+the arrow keys move around, and P switches pencil marks on and off. Values repeated in a row,
+column, box or other region are shown in red, but other rules, such as cages, dots and
+thermometers, aren't checked as you go.</p>
+{sections}<script>
+const SIZE = {size};
+const PUZZLES = {puzzles};
+
+function hash(line) {
+  let h = 0xcbf29ce484222325n;
+  for (const c of line) {
+    h = ((h ^ BigInt(c.charCodeAt(0))) * 0x100000001b3n) & 0xffffffffffffffffn;
+  }
+  return h.toString(16).padStart(16, "0");
+}
+
+const boards = [...document.querySelectorAll(".puzzle")].map((section, p) => {
+  const puzzle = PUZZLES[p];
+  const state = {
+    values: [...puzzle.cells].map(c => (c === "." ? 0 : parseInt(c, 36))),
+    marks: [...puzzle.cells].map(() => new Set()),
+    clues: [...puzzle.cells].map(c => c !== "."),
+    selected: null,
+    pencil: false,
+  };
+  const board = section.querySelector(".board");
+  const status = section.querySelector(".status");
+  const cells = [];
+
+  for (let i = 0; i < SIZE * SIZE; i++) {
+    const cell = document.createElement("div");
+    const pct = x => (x * 100) + "%";
+    cell.style.left = pct(puzzle.origin + (i % SIZE) * puzzle.cell);
+    cell.style.top = pct(puzzle.origin + Math.floor(i / SIZE) * puzzle.cell);
+    cell.style.width = cell.style.height = pct(puzzle.cell);
+    if (state.clues[i]) cell.className = "clue";
+    cell.addEventListener("click", () => { state.selected = i; draw(); });
+    board.querySelector(".cells").appendChild(cell);
+    cells.push(cell);
+  }
+
+  new ResizeObserver(() => {
+    board.style.setProperty("--cell", board.clientWidth * puzzle.cell + "px");
+  }).observe(board);
+
+  const pad = section.querySelector(".pad");
+  for (let n = 1; n <= SIZE; n++) {
+    const button = document.createElement("button");
+    button.textContent = n.toString(36).toUpperCase();
+    button.addEventListener("click", () => enter(n));
+    pad.appendChild(button);
+  }
+  const clear = document.createElement("button");
+  clear.textContent = "Clear";
+  clear.addEventListener("click", () => enter(0));
+  pad.appendChild(clear);
+
+  function conflicts() {
+    const found = new Set();
+    for (const region of puzzle.regions) {
+      const seen = new Map();
+      for (const i of region) {
+        const v = state.values[i];
+        if (!v) continue;
+        if (seen.has(v)) { found.add(i); found.add(seen.get(v)); } else seen.set(v, i);
+      }
+    }
+    return found;
+  }
+
+  function draw() {
+    const clashes = conflicts();
+    cells.forEach((cell, i) => {
+      cell.classList.toggle("selected", state.selected === i);
+      cell.classList.toggle("conflict", clashes.has(i));
+      if (state.clues[i]) return;
+      cell.textContent = "";
+      if (state.values[i]) {
+        cell.textContent = state.values[i].toString(36).toUpperCase();
+      } else if (state.marks[i].size) {
+        const marks = document.createElement("div");
+        marks.className = "marks";
+        for (let n = 1; n <= SIZE; n++) {
+          const mark = document.createElement("span");
+          mark.textContent = state.marks[i].has(n) ? n.toString(36).toUpperCase() : "";
+          marks.appendChild(mark);
+        }
+        cell.appendChild(marks);
+      }
+    });
+    section.querySelector("[data-action=pencil]").textContent =
+      "Pencil marks: " + (state.pencil ? "on" : "off");
+    if (state.values.every(v => v)) check();
+  }
+
+  function enter(n) {
+    const i = state.selected;
+    if (i === null || state.clues[i]) return;
+    if (n === 0) {
+      state.values[i] = 0;
+      state.marks[i].clear();
+    } else if (state.pencil) {
+      state.values[i] = 0;
+      state.marks[i].has(n) ? state.marks[i].delete(n) : state.marks[i].add(n);
+    } else {
+      state.values[i] = state.values[i] === n ? 0 : n;
+    }
+    status.textContent = "";
+    section.classList.remove("solved");
+    draw();
+  }
+
+  function check() {
+    const left = state.values.filter(v => !v).length;
+    if (conflicts().size) {
+      status.textContent = "Some values are repeated.";
+    } else if (left) {
+      status.textContent = "No mistakes so far, " + left + " cells to go.";
+    } else if (puzzle.hash === null && puzzle.onlyRegions) {
+      // With no rules but its regions, a full grid without repeats solves it.
+      status.textContent = "Solved!";
+      section.classList.add("solved");
+    } else if (puzzle.hash === null) {
+      status.textContent = "Every cell is filled with no repeated values, " +
+        "but the puzzle's other rules aren't checked.";
+    } else if (hash(state.values.map(v => v.toString(36)).join("")) === puzzle.hash) {
+      status.textContent = "Solved!";
+      section.classList.add("solved");
+    } else {
+      status.textContent = "Not quite: a rule is broken somewhere.";
+    }
+  }
+
+  section.querySelector("[data-action=pencil]").addEventListener("click", () => {
+    state.pencil = !state.pencil;
+    draw();
+  });
+  section.querySelector("[data-action=check]").addEventListener("click", check);
+  section.querySelector("[data-action=reset]").addEventListener("click", () => {
+    state.values.forEach((_, i) => {
+      if (!state.clues[i]) { state.values[i] = 0; state.marks[i].clear(); }
+    });
+    status.textContent = "";
+    section.classList.remove("solved");
+    draw();
+  });
+
+  function key(e) {
+    const i = state.selected;
+    if (i === null) return false;
+    const moves = { ArrowUp: -SIZE, ArrowDown: SIZE, ArrowLeft: -1, ArrowRight: 1 };
+    const n = parseInt(e.key, 36);
+    if (e.key in moves) {
+      const next = i + moves[e.key];
+      const sameRow = Math.floor(next / SIZE) === Math.floor(i / SIZE);
+      if (next >= 0 && next < SIZE * SIZE && (Math.abs(moves[e.key]) === SIZE || sameRow)) {
+        state.selected = next;
+        draw();
+      }
+    } else if (e.key === "Backspace" || e.key === "Delete" || e.key === "0") {
+      enter(0);
+    } else if (e.key === "p" || e.key === "P") {
+      state.pencil = !state.pencil;
+      draw();
+    } else if (n >= 1 && n <= SIZE) {
+      enter(n);
+    } else {
+      return false;
+    }
+    return true;
+  }
+
+  draw();
+  return { state, key, draw };
+});
+
+document.addEventListener("keydown", e => {
+  for (const board of boards) {
+    if (board.state.selected !== null && board.key(e)) { e.preventDefault(); return; }
+  }
+});
+document.addEventListener("click", e => {
+  const section = e.target.closest(".puzzle");
+  for (const [p, board] of boards.entries()) {
+    if (section !== document.querySelectorAll(".puzzle")[p] && board.state.selected !== null) {
+      board.state.selected = null;
+      board.draw();
+    }
+  }
+});
+</script>
+</body>
+</html>
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::{parse_puzzle, Toggles, Variant};
+
+    const SOLUTION: &str =
+        "534678912672195348198342567859761423426853791713924856961537284287419635345286179";
+
+    fn parse(line: &str) -> Grid {
+        parse_puzzle(line, Variant::Standard, Toggles::default()).unwrap()
+    }
+
+    #[test]
+    fn hashes_match_the_page() {
+        // The values the page's `hash` gives for the same lines.
+        assert_eq!(solution_hash("a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(solution_hash(SOLUTION), 0x5a2a_cc55_052b_39fc);
+
+        let puzzle = format!("{}0{}", &SOLUTION[..40], &SOLUTION[41..]);
+        let html = to_html(&[parse(&puzzle)], &[parse(SOLUTION)]);
+        assert!(html.contains("\"hash\":\"5a2acc55052b39fc\""));
+    }
+
+    #[test]
+    fn puzzles_without_a_unique_solution_have_no_hash() {
+        // With the top two rows empty, the values can be swapped between them
+        // in more than one way.
+        let puzzle = format!("{}{}", "0".repeat(2 * SIZE), &SOLUTION[2 * SIZE..]);
+        let html = to_html(&[parse(&puzzle)], &[parse(SOLUTION)]);
+        assert!(html.contains("\"hash\":null"));
+        assert!(!html.contains("5a2acc55052b39fc"));
+    }
+}
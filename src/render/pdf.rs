@@ -0,0 +1,221 @@
+use std::fmt::Write;
+
+use super::{Colour, LineStyle, Page, Shape, DASH};
+
+/// Widths of the printable ASCII characters in Helvetica, from space to `~`,
+/// in thousandths of the font size. Used to centre text, since PDF only
+/// places text by its left end. Helvetica Bold is close enough for the digits
+/// and capitals drawn in bold.
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+/// Write `pages` as a PDF document, one PDF page to each. The document is
+/// built by hand from PDF's own drawing operators, using the standard
+/// Helvetica fonts every PDF reader has, so nothing is embedded.
+pub fn to_pdf(pages: &[Page]) -> Vec<u8> {
+    // Objects 1 to 4 are the catalog, the page tree and the two fonts, then
+    // each page is followed by its content stream.
+    let kids: Vec<String> = (0..pages.len())
+        .map(|i| format!("{} 0 R", 5 + 2 * i))
+        .collect();
+    let mut objects = vec![
+        String::from("<< /Type /Catalog /Pages 2 0 R >>"),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            pages.len()
+        ),
+        String::from("<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>"),
+        String::from("<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold >>"),
+    ];
+    for (i, page) in pages.iter().enumerate() {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+             /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+            page.width,
+            page.height,
+            6 + 2 * i
+        ));
+        let content = content_stream(page);
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{content}\nendstream",
+            content.len()
+        ));
+    }
+
+    let mut out = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.push_str(&format!("{} 0 obj\n{object}\nendobj\n", i + 1));
+    }
+
+    let xref = out.len();
+    out.push_str(&format!(
+        "xref\n0 {}\n0000000000 65535 f \n",
+        objects.len() + 1
+    ));
+    for offset in offsets {
+        out.push_str(&format!("{offset:010} 00000 n \n"));
+    }
+    out.push_str(&format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+        objects.len() + 1
+    ));
+
+    out.into_bytes()
+}
+
+/// Returns the operators that draw the shapes of `page`. PDF measures from the
+/// bottom-left corner, so every `y` is flipped.
+fn content_stream(page: &Page) -> String {
+    let flip = |y: f32| page.height - y;
+    let mut out = String::new();
+
+    for shape in page.shapes.iter() {
+        // Writing to a `String` can't fail.
+        let _ = match shape {
+            Shape::Line {
+                from,
+                to,
+                width,
+                colour,
+                style,
+            } => writeln!(
+                out,
+                "q {} {width:.2} w {} RG {:.2} {:.2} m {:.2} {:.2} l S Q",
+                match style {
+                    LineStyle::Solid => String::from("2 J"),
+                    LineStyle::Dashed => format!("[{:.2}] 0 d", width * DASH),
+                    LineStyle::Round => String::from("1 J"),
+                },
+                rgb(*colour),
+                from.0,
+                flip(from.1),
+                to.0,
+                flip(to.1)
+            ),
+            Shape::Rect {
+                x,
+                y,
+                width,
+                height,
+                fill,
+            } => writeln!(
+                out,
+                "{} rg {x:.2} {:.2} {width:.2} {height:.2} re f",
+                rgb(*fill),
+                flip(y + height)
+            ),
+            Shape::Circle {
+                centre,
+                radius,
+                fill,
+                stroke,
+            } => writeln!(
+                out,
+                "{}{}{} {}",
+                fill.map_or(String::new(), |c| format!("{} rg ", rgb(c))),
+                stroke.map_or(String::new(), |(c, width)| format!(
+                    "{} RG {width:.2} w ",
+                    rgb(c)
+                )),
+                circle(centre.0, flip(centre.1), *radius),
+                match (fill, stroke) {
+                    (Some(_), Some(_)) => "B",
+                    (Some(_), None) => "f",
+                    (None, _) => "S",
+                }
+            ),
+            Shape::Text {
+                x,
+                y,
+                size,
+                bold,
+                fill,
+                text,
+            } => writeln!(
+                out,
+                "BT /F{} {size:.2} Tf {} rg {:.2} {:.2} Td ({}) Tj ET",
+                if *bold { 2 } else { 1 },
+                rgb(*fill),
+                x - text_width(text, *size) / 2.0,
+                flip(*y),
+                escape(text)
+            ),
+        };
+    }
+
+    out
+}
+
+/// Returns the path of a circle centred on `x` and `y`, made of four Bézier
+/// curves, since PDF has no circles of its own.
+fn circle(x: f32, y: f32, r: f32) -> String {
+    // Distance of the control points from each end of a quarter circle.
+    let k = r * 0.5523;
+    format!(
+        "{:.2} {y:.2} m \
+         {:.2} {:.2} {:.2} {:.2} {x:.2} {:.2} c \
+         {:.2} {:.2} {:.2} {:.2} {:.2} {y:.2} c \
+         {:.2} {:.2} {:.2} {:.2} {x:.2} {:.2} c \
+         {:.2} {:.2} {:.2} {:.2} {:.2} {y:.2} c h",
+        x + r,
+        x + r,
+        y + k,
+        x + k,
+        y + r,
+        y + r,
+        x - k,
+        y + r,
+        x - r,
+        y + k,
+        x - r,
+        x - r,
+        y - k,
+        x - k,
+        y - r,
+        y - r,
+        x + k,
+        y - r,
+        x + r,
+        y - k,
+        x + r
+    )
+}
+
+/// Returns `colour` as the red, green and blue fractions PDF expects.
+fn rgb(colour: Colour) -> String {
+    let Colour(r, g, b) = colour;
+    format!(
+        "{:.3} {:.3} {:.3}",
+        r as f32 / 255.0,
+        g as f32 / 255.0,
+        b as f32 / 255.0
+    )
+}
+
+/// Returns the width of `text` in Helvetica at `size` points.
+fn text_width(text: &str, size: f32) -> f32 {
+    let thousandths: u32 = text
+        .chars()
+        .map(|c| match c {
+            ' '..='~' => HELVETICA_WIDTHS[c as usize - ' ' as usize] as u32,
+            _ => 556,
+        })
+        .sum();
+    thousandths as f32 * size / 1000.0
+}
+
+/// Escape the characters of `text` that have a special meaning in a PDF
+/// string.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('(', "\\(")
+        .replace(')', "\\)")
+}
@@ -0,0 +1,218 @@
+use std::{fmt, sync::LazyLock};
+
+use ab_glyph::{point, Font, FontRef, PxScale, ScaleFont};
+use tiny_skia::{
+    Color, FillRule, LineCap, Paint, PathBuilder, Pixmap, PremultipliedColorU8, Rect, Stroke,
+    StrokeDash, Transform,
+};
+
+use super::{Colour, LineStyle, Page, Shape, DASH};
+
+/// DejaVu Sans, bundled so images look the same wherever they're drawn.
+static REGULAR: LazyLock<FontRef<'static>> = LazyLock::new(|| {
+    FontRef::try_from_slice(include_bytes!("fonts/DejaVuSans.ttf"))
+        .expect("The bundled font is valid")
+});
+/// DejaVu Sans Bold, for clues.
+static BOLD: LazyLock<FontRef<'static>> = LazyLock::new(|| {
+    FontRef::try_from_slice(include_bytes!("fonts/DejaVuSans-Bold.ttf"))
+        .expect("The bundled font is valid")
+});
+
+/// Longest side, in pixels, of an image `to_png` will draw, which keeps the
+/// image's memory under half a gigabyte.
+const MAX_SIDE: f32 = 10_000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Error returned by `to_png` when a page's width or height isn't between 0
+/// and `MAX_SIDE` points.
+pub struct PageSizeError {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl fmt::Display for PageSizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Can't draw a page of {} by {} points as an image",
+            self.width, self.height
+        )
+    }
+}
+
+impl std::error::Error for PageSizeError {}
+
+/// Draw `page` as a PNG image, one pixel to each point, with anti-aliased
+/// shapes and text in the bundled DejaVu Sans fonts. Returns an error if the
+/// page is too big for an image, as with a huge cell size.
+pub fn to_png(page: &Page) -> Result<Vec<u8>, PageSizeError> {
+    let error = PageSizeError {
+        width: page.width,
+        height: page.height,
+    };
+    if !(0.0..=MAX_SIDE).contains(&page.width) || !(0.0..=MAX_SIDE).contains(&page.height) {
+        return Err(error);
+    }
+    let mut pixmap = Pixmap::new(
+        page.width.ceil().max(1.0) as u32,
+        page.height.ceil().max(1.0) as u32,
+    )
+    .ok_or(error)?;
+    pixmap.fill(Color::WHITE);
+
+    for shape in page.shapes.iter() {
+        match shape {
+            Shape::Line {
+                from,
+                to,
+                width,
+                colour,
+                style,
+            } => {
+                let mut path = PathBuilder::new();
+                path.move_to(from.0, from.1);
+                path.line_to(to.0, to.1);
+                let stroke = Stroke {
+                    width: *width,
+                    line_cap: match style {
+                        LineStyle::Solid => LineCap::Square,
+                        LineStyle::Dashed => LineCap::Butt,
+                        LineStyle::Round => LineCap::Round,
+                    },
+                    dash: match style {
+                        LineStyle::Dashed => StrokeDash::new(vec![width * DASH; 2], 0.0),
+                        _ => None,
+                    },
+                    ..Stroke::default()
+                };
+                if let Some(path) = path.finish() {
+                    pixmap.stroke_path(
+                        &path,
+                        &paint(*colour),
+                        &stroke,
+                        Transform::identity(),
+                        None,
+                    );
+                }
+            }
+            Shape::Rect {
+                x,
+                y,
+                width,
+                height,
+                fill,
+            } => {
+                if let Some(rect) = Rect::from_xywh(*x, *y, *width, *height) {
+                    pixmap.fill_rect(rect, &paint(*fill), Transform::identity(), None);
+                }
+            }
+            Shape::Circle {
+                centre,
+                radius,
+                fill,
+                stroke,
+            } => {
+                let Some(path) = PathBuilder::from_circle(centre.0, centre.1, *radius) else {
+                    continue;
+                };
+                if let Some(fill) = fill {
+                    pixmap.fill_path(
+                        &path,
+                        &paint(*fill),
+                        FillRule::Winding,
+                        Transform::identity(),
+                        None,
+                    );
+                }
+                if let Some((colour, width)) = stroke {
+                    let stroke = Stroke {
+                        width: *width,
+                        ..Stroke::default()
+                    };
+                    pixmap.stroke_path(
+                        &path,
+                        &paint(*colour),
+                        &stroke,
+                        Transform::identity(),
+                        None,
+                    );
+                }
+            }
+            Shape::Text {
+                x,
+                y,
+                size,
+                bold,
+                fill,
+                text,
+            } => {
+                let font: &FontRef = if *bold { &BOLD } else { &REGULAR };
+                draw_text(&mut pixmap, font, text, (*x, *y), *size, *fill);
+            }
+        }
+    }
+
+    Ok(pixmap.encode_png().expect("Encoding to memory can't fail"))
+}
+
+/// Returns an anti-aliased paint of `colour`.
+fn paint(colour: Colour) -> Paint<'static> {
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(colour.0, colour.1, colour.2, 255);
+    paint.anti_alias = true;
+    paint
+}
+
+/// Draw `text` in `font`, `size` pixels to the em, centred on `at.0` with its
+/// baseline at `at.1`, blending each glyph's coverage into `pixmap`.
+fn draw_text(
+    pixmap: &mut Pixmap,
+    font: &FontRef,
+    text: &str,
+    at: (f32, f32),
+    size: f32,
+    colour: Colour,
+) {
+    // A `PxScale` is the height from the font's ascent to its descent, not
+    // the em, so it's scaled up from the size.
+    let em = font.units_per_em().unwrap_or(1000.0);
+    let font = font.as_scaled(PxScale::from(size * font.height_unscaled() / em));
+    let width: f32 = text.chars().map(|c| font.h_advance(font.glyph_id(c))).sum();
+
+    let (pixmap_width, pixmap_height) = (pixmap.width() as i32, pixmap.height() as i32);
+    let pixels = pixmap.pixels_mut();
+    let mut caret = at.0 - width / 2.0;
+    for c in text.chars() {
+        let mut glyph = font.scaled_glyph(c);
+        glyph.position = point(caret, at.1);
+        caret += font.h_advance(glyph.id);
+
+        let Some(outlined) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outlined.px_bounds();
+        outlined.draw(|gx, gy, coverage| {
+            let (px, py) = (
+                bounds.min.x as i32 + gx as i32,
+                bounds.min.y as i32 + gy as i32,
+            );
+            if !(0..pixmap_width).contains(&px) || !(0..pixmap_height).contains(&py) {
+                return;
+            }
+            let pixel = &mut pixels[(py * pixmap_width + px) as usize];
+            let blend = |src: u8, dst: u8| {
+                (src as f32 * coverage + dst as f32 * (1.0 - coverage)).round() as u8
+            };
+            let alpha = blend(255, pixel.alpha());
+            if let Some(blended) = PremultipliedColorU8::from_rgba(
+                blend(colour.0, pixel.red()).min(alpha),
+                blend(colour.1, pixel.green()).min(alpha),
+                blend(colour.2, pixel.blue()).min(alpha),
+                alpha,
+            ) {
+                *pixel = blended;
+            }
+        });
+    }
+}
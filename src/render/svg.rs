@@ -0,0 +1,94 @@
+use std::fmt::Write;
+
+use super::{LineStyle, Page, Shape, DASH};
+
+/// Write `page` as a standalone SVG document, sized in points so it prints at
+/// the size it was laid out at.
+pub fn to_svg(page: &Page) -> String {
+    let (w, h) = (page.width, page.height);
+    let mut out = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}pt\" height=\"{h}pt\" \
+         viewBox=\"0 0 {w} {h}\">\n\
+         <rect width=\"{w}\" height=\"{h}\" fill=\"white\"/>\n\
+         <g font-family=\"Helvetica, Arial, sans-serif\" text-anchor=\"middle\" \
+         stroke-linecap=\"square\">\n"
+    );
+
+    for shape in page.shapes.iter() {
+        // Writing to a `String` can't fail.
+        let _ = match shape {
+            Shape::Line {
+                from,
+                to,
+                width,
+                colour,
+                style,
+            } => writeln!(
+                out,
+                "<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" \
+                 stroke=\"{colour}\" stroke-width=\"{width:.2}\"{}/>",
+                from.0,
+                from.1,
+                to.0,
+                to.1,
+                match style {
+                    LineStyle::Solid => String::new(),
+                    LineStyle::Dashed => format!(
+                        " stroke-linecap=\"butt\" stroke-dasharray=\"{:.2}\"",
+                        width * DASH
+                    ),
+                    LineStyle::Round => String::from(" stroke-linecap=\"round\""),
+                }
+            ),
+            Shape::Rect {
+                x,
+                y,
+                width,
+                height,
+                fill,
+            } => writeln!(
+                out,
+                "<rect x=\"{x:.2}\" y=\"{y:.2}\" width=\"{width:.2}\" height=\"{height:.2}\" \
+                 fill=\"{fill}\"/>"
+            ),
+            Shape::Circle {
+                centre,
+                radius,
+                fill,
+                stroke,
+            } => writeln!(
+                out,
+                "<circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"{radius:.2}\" fill=\"{}\"{}/>",
+                centre.0,
+                centre.1,
+                fill.map_or(String::from("none"), |c| c.to_string()),
+                stroke.map_or(String::new(), |(colour, width)| format!(
+                    " stroke=\"{colour}\" stroke-width=\"{width:.2}\""
+                ))
+            ),
+            Shape::Text {
+                x,
+                y,
+                size,
+                bold,
+                fill,
+                text,
+            } => writeln!(
+                out,
+                "<text x=\"{x:.2}\" y=\"{y:.2}\" font-size=\"{size:.2}\"{} fill=\"{fill}\">{}</text>",
+                if *bold { " font-weight=\"bold\"" } else { "" },
+                escape(text)
+            ),
+        };
+    }
+
+    out.push_str("</g>\n</svg>\n");
+    out
+}
+
+/// Escape the characters of `text` that have a special meaning in XML.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
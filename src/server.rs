@@ -0,0 +1,503 @@
+use std::{
+    io::{self, BufRead, BufReader, Read, Take, Write},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use serde_json::{json, Map, Value};
+
+use crate::logic::{
+    find_solutions, generate_batch, parse_parts, parse_puzzle, rate, GenerateOptions, Grid,
+    GridError, Toggles, Variant,
+};
+
+/// Largest request body accepted, which is plenty for any puzzle in line
+/// format.
+const MAX_BODY: usize = 64 * 1024;
+
+/// Largest request line and headers accepted, in bytes, taken together.
+const MAX_HEADER: u64 = 8 * 1024;
+
+/// How long a connection can go without sending anything before it's closed,
+/// so clients that never finish a request don't hold on to a thread.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Number of connections served at once by default. Connections past that
+/// are refused with a 503 reply rather than queued.
+const WORKERS: usize = 32;
+
+/// Number of solutions counted up to when solving, enough to tell whether a
+/// puzzle's solution is unique.
+const COUNT_LIMIT: usize = 2;
+
+/// Number of times solving a puzzle can backtrack before giving up, so a
+/// puzzle with no solution, or a huge number of them, can't tie up a thread.
+const BACKTRACK_LIMIT: usize = 10_000;
+
+#[derive(Debug, Clone, PartialEq)]
+/// Struct representing the reply to a request: an HTTP status code and a
+/// JSON body.
+pub struct Response {
+    pub status: u16,
+    pub body: Value,
+}
+
+impl Response {
+    fn ok(body: Value) -> Self {
+        Response { status: 200, body }
+    }
+
+    /// Construct an error reply with `status`, explained by `message`.
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Response {
+            status,
+            body: json!({ "error": message.into() }),
+        }
+    }
+
+    /// Construct an error reply for `e`, naming its kind so callers can tell
+    /// errors apart without reading the message.
+    fn grid_error(status: u16, e: &GridError) -> Self {
+        Response {
+            status,
+            body: json!({
+                "error": e.to_string().trim_end(),
+                "kind": format!("{:?}", e.kind),
+            }),
+        }
+    }
+
+    /// Returns the reason phrase that goes with the status code.
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            413 => "Payload Too Large",
+            431 => "Request Header Fields Too Large",
+            422 => "Unprocessable Entity",
+            503 => "Service Unavailable",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+/// Struct representing a server answering requests to solve, rate, generate
+/// and validate puzzles with JSON over HTTP. Connections are handled by a
+/// fixed number of worker threads, each closed after one request, or once
+/// it's sent nothing for `READ_TIMEOUT`. While every worker is busy, new
+/// connections get a 503 reply.
+pub struct Server {
+    listener: TcpListener,
+    workers: usize,
+}
+
+impl Server {
+    /// Listen on `addr`, e.g. `"127.0.0.1:8080"`, with `WORKERS` worker
+    /// threads. Port 0 picks a free port, which `local_addr` then returns.
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        Ok(Server {
+            listener: TcpListener::bind(addr)?,
+            workers: WORKERS,
+        })
+    }
+
+    /// Serve at most `workers` connections at once, and at least one.
+    pub fn with_workers(mut self, workers: usize) -> Self {
+        self.workers = workers.max(1);
+        self
+    }
+
+    /// Returns the address the server is listening on.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Answer requests until the listener fails. Errors on a single
+    /// connection, such as the client hanging up, only end that connection.
+    pub fn run(self) -> io::Result<()> {
+        // Every connection handed to the workers is counted until it's
+        // closed, so the queue never holds more than the workers can take.
+        let busy = Arc::new(AtomicUsize::new(0));
+        let (sender, receiver) = mpsc::sync_channel::<TcpStream>(self.workers);
+        let receiver = Arc::new(Mutex::new(receiver));
+        for _ in 0..self.workers {
+            let (busy, receiver) = (Arc::clone(&busy), Arc::clone(&receiver));
+            thread::spawn(move || loop {
+                let next = match receiver.lock() {
+                    Ok(receiver) => receiver.recv(),
+                    Err(_) => return,
+                };
+                let Ok(stream) = next else {
+                    return;
+                };
+                let _ = serve_connection(stream);
+                busy.fetch_sub(1, Ordering::SeqCst);
+            });
+        }
+
+        for stream in self.listener.incoming() {
+            let stream = stream?;
+            if busy.load(Ordering::SeqCst) >= self.workers {
+                let _ = refuse(stream);
+                continue;
+            }
+            busy.fetch_add(1, Ordering::SeqCst);
+            if sender.send(stream).is_err() {
+                return Err(io::Error::other("Every worker thread has stopped"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reply to a connection that arrived while every worker was busy, without
+/// waiting for its request. Whatever part of the request has already arrived
+/// is read first, so closing the connection doesn't reset it.
+fn refuse(stream: TcpStream) -> io::Result<()> {
+    stream.set_write_timeout(Some(READ_TIMEOUT))?;
+    stream.set_nonblocking(true)?;
+    let _ = io::copy(
+        &mut (&stream).take(MAX_HEADER + MAX_BODY as u64),
+        &mut io::sink(),
+    );
+    stream.set_nonblocking(false)?;
+    write_response(
+        &stream,
+        &Response::error(503, "The server is busy, try again later"),
+    )?;
+    stream.shutdown(Shutdown::Write)
+}
+
+/// Read a single request from `stream`, answer it and close the connection.
+fn serve_connection(stream: TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let (response, refused) = match read_request(&mut reader)? {
+        Ok(request) => (
+            handle(&request.method, &request.target, &request.body),
+            false,
+        ),
+        Err(response) => (response, true),
+    };
+
+    write_response(&stream, &response)?;
+
+    // Closing with part of a refused request unread would reset the
+    // connection, which can lose the reply, so the rest is read and dropped
+    // first, up to a limit and for no longer than the read timeout.
+    if refused {
+        stream.shutdown(Shutdown::Write)?;
+        io::copy(&mut reader.take(MAX_BODY as u64), &mut io::sink())?;
+    }
+    Ok(())
+}
+
+/// Write `response` to `stream` as an HTTP reply that closes the connection.
+fn write_response(mut stream: &TcpStream, response: &Response) -> io::Result<()> {
+    let body = response.body.to_string();
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{body}",
+        response.status,
+        response.reason(),
+        body.len()
+    )?;
+    stream.flush()
+}
+
+/// Struct representing the parts of a request the API uses.
+struct Request {
+    method: String,
+    target: String,
+    body: String,
+}
+
+/// Read the request line, headers and body of a request. Returns the reply
+/// to send instead if the request is malformed or too big.
+fn read_request(reader: &mut impl BufRead) -> io::Result<Result<Request, Response>> {
+    let mut head = reader.take(MAX_HEADER);
+    let Some(line) = read_head_line(&mut head)? else {
+        return Ok(Err(header_too_large()));
+    };
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Ok(Err(Response::error(400, "Malformed request line")));
+    };
+    let (method, target) = (method.to_string(), target.to_string());
+
+    let mut length = 0;
+    loop {
+        let Some(header) = read_head_line(&mut head)? else {
+            return Ok(Err(header_too_large()));
+        };
+        if header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, val)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                match val.trim().parse() {
+                    Ok(n) => length = n,
+                    Err(_) => return Ok(Err(Response::error(400, "Invalid Content-Length"))),
+                }
+            }
+        }
+    }
+    if length > MAX_BODY {
+        return Ok(Err(Response::error(
+            413,
+            format!("Request bodies can be at most {MAX_BODY} bytes"),
+        )));
+    }
+
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    match String::from_utf8(body) {
+        Ok(body) => Ok(Ok(Request {
+            method,
+            target,
+            body,
+        })),
+        Err(_) => Ok(Err(Response::error(400, "The body isn't valid UTF-8"))),
+    }
+}
+
+/// Read a line of the request line and headers from `head`, which stops
+/// after `MAX_HEADER` bytes. Returns `None` if the line is cut short by the
+/// limit, and an empty line at the end of the stream.
+fn read_head_line<R: BufRead>(head: &mut Take<R>) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    head.read_line(&mut line)?;
+    match line.ends_with('\n') || head.limit() > 0 {
+        true => Ok(Some(line)),
+        false => Ok(None),
+    }
+}
+
+/// Returns the reply to a request whose line and headers are too long.
+fn header_too_large() -> Response {
+    Response::error(
+        431,
+        format!("The request line and headers can be at most {MAX_HEADER} bytes"),
+    )
+}
+
+/// Answer a request for `target` with `method` and `body`, without any
+/// networking, so the API can be served by other means too.
+///
+/// - `POST /solve` takes `{"puzzle": ...}` and returns the solution and the
+///   number of solutions, counted up to 2. Puzzles with no solution, or that
+///   take too long to solve, get a 422 reply.
+/// - `POST /rate` takes `{"puzzle": ...}` and returns its difficulty and
+///   score.
+/// - `POST /validate` takes `{"puzzle": ...}` and returns whether it follows
+///   the rules, and the conflicts if it doesn't.
+/// - `GET /generate` returns a new puzzle, taking `difficulty`, `symmetry`
+///   and `seed` as query parameters.
+///
+/// Puzzles are in line format. Every endpoint also takes `variant` and
+/// `constraints`, named as on the command line, in the body or query.
+pub fn handle(method: &str, target: &str, body: &str) -> Response {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let expected = match path {
+        "/solve" | "/rate" | "/validate" => "POST",
+        "/generate" => "GET",
+        _ => return Response::error(404, format!("No endpoint at `{path}`")),
+    };
+    if method != expected {
+        return Response::error(405, format!("`{path}` expects {expected}"));
+    }
+
+    let params = match method {
+        "POST" => match serde_json::from_str(body) {
+            Ok(Value::Object(params)) => params,
+            Ok(_) => return Response::error(400, "Expected a JSON object"),
+            Err(e) => return Response::error(400, format!("Invalid JSON: {e}")),
+        },
+        _ => parse_query(query),
+    };
+
+    let result = match path {
+        "/solve" => solve(&params),
+        "/rate" => rate_puzzle(&params),
+        "/validate" => validate(&params),
+        _ => generate(&params),
+    };
+    result.unwrap_or_else(|response| response)
+}
+
+/// Endpoint solving a puzzle.
+fn solve(params: &Map<String, Value>) -> Result<Response, Response> {
+    let (solution, count) = find_solutions(puzzle(params)?, COUNT_LIMIT, BACKTRACK_LIMIT)
+        .map_err(|e| Response::grid_error(422, &e))?;
+
+    Ok(Response::ok(json!({
+        "solution": solution.to_line(),
+        "solutions": count,
+    })))
+}
+
+/// Endpoint rating how hard a puzzle is for a person.
+fn rate_puzzle(params: &Map<String, Value>) -> Result<Response, Response> {
+    let rating = rate(&puzzle(params)?).map_err(|e| Response::grid_error(422, &e))?;
+
+    Ok(Response::ok(json!({
+        "difficulty": rating.difficulty.to_string(),
+        "score": rating.score,
+    })))
+}
+
+/// Endpoint listing the ways a puzzle's values break its rules. Unlike the
+/// other endpoints, puzzles with conflicting clues are accepted.
+fn validate(params: &Map<String, Value>) -> Result<Response, Response> {
+    let line = string_param(params, "puzzle")?.ok_or_else(missing_puzzle)?;
+    let (rows, rules) = parse_parts(
+        line,
+        named_param(params, "variant", Variant::default())?,
+        named_param(params, "constraints", Toggles::default())?,
+    )
+    .map_err(|e| Response::grid_error(400, &e))?;
+
+    let conflicts: Vec<Value> = rules
+        .conflicts(&rows)
+        .iter()
+        .map(|c| {
+            json!({
+                "kind": format!("{:?}", c.kind),
+                "message": c.to_string(),
+                "cells": c.cells.iter().map(|cell| [cell.row + 1, cell.col + 1]).collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+
+    Ok(Response::ok(json!({
+        "valid": conflicts.is_empty(),
+        "conflicts": conflicts,
+    })))
+}
+
+/// Endpoint generating a puzzle.
+fn generate(params: &Map<String, Value>) -> Result<Response, Response> {
+    let defaults = GenerateOptions::default();
+    let options = GenerateOptions {
+        difficulty: named_param(params, "difficulty", defaults.difficulty)?,
+        symmetry: named_param(params, "symmetry", defaults.symmetry)?,
+        variant: named_param(params, "variant", defaults.variant)?,
+        toggles: named_param(params, "constraints", defaults.toggles)?,
+        seed: match string_param(params, "seed")? {
+            Some(seed) => Some(
+                seed.parse()
+                    .map_err(|_| Response::error(400, format!("Invalid seed `{seed}`")))?,
+            ),
+            None => None,
+        },
+        count: 1,
+        threads: 1,
+    };
+
+    let generated = generate_batch(&options).map_err(|e| Response::grid_error(422, &e))?;
+    let p = &generated[0];
+    Ok(Response::ok(json!({
+        "puzzle": p.puzzle.to_line(),
+        "difficulty": p.rating.difficulty.to_string(),
+        "score": p.rating.score,
+        "clues": p.clues,
+        "minimal": p.minimal,
+    })))
+}
+
+/// Parse the `puzzle` parameter, following the `variant` and `constraints`
+/// parameters if given.
+fn puzzle(params: &Map<String, Value>) -> Result<Grid, Response> {
+    let line = string_param(params, "puzzle")?.ok_or_else(missing_puzzle)?;
+    parse_puzzle(
+        line,
+        named_param(params, "variant", Variant::default())?,
+        named_param(params, "constraints", Toggles::default())?,
+    )
+    .map_err(|e| Response::grid_error(400, &e))
+}
+
+fn missing_puzzle() -> Response {
+    Response::error(400, "Expected a `puzzle` in line format")
+}
+
+/// Returns the parameter `name` if it was given, which must be a string.
+fn string_param<'a>(
+    params: &'a Map<String, Value>,
+    name: &str,
+) -> Result<Option<&'a str>, Response> {
+    match params.get(name) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(s)) => Ok(Some(s)),
+        Some(_) => Err(Response::error(
+            400,
+            format!("Expected `{name}` as a string"),
+        )),
+    }
+}
+
+/// Parse the parameter `name` as one of a set of names, such as a
+/// difficulty, or return `default` if it wasn't given.
+fn named_param<T: FromStr<Err = GridError>>(
+    params: &Map<String, Value>,
+    name: &str,
+    default: T,
+) -> Result<T, Response> {
+    match string_param(params, name)? {
+        Some(val) => val
+            .parse()
+            .map_err(|e: GridError| Response::grid_error(400, &e)),
+        None => Ok(default),
+    }
+}
+
+/// Parse the query string of a URL into parameters, decoding `+` and
+/// percent-encoded bytes.
+fn parse_query(query: &str) -> Map<String, Value> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, val) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode(name), Value::String(decode(val)))
+        })
+        .collect()
+}
+
+/// Decode a percent-encoded component of a URL. Invalid escapes are kept as
+/// they are.
+fn decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], escaped) {
+            (b'%', Some(b)) => {
+                out.push(b);
+                i += 3;
+            }
+            (b'+', _) => {
+                out.push(b' ');
+                i += 1;
+            }
+            (b, _) => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
@@ -1,19 +1,24 @@
 use crate::ui::utils::format_time;
 use crate::ui::utils::HOW_TO;
+use crate::ui::utils::HOW_TO_MULTI;
 use crate::ui::utils::SMALL_TITLE;
 use crate::ui::utils::THANK_YOU;
 use std::collections::BTreeMap;
-use std::sync::mpsc;
+use std::sync::{mpsc, Arc};
 use std::thread;
 use std::time;
 
 use crate::logic::{
-    generate, solve_backtracking_heuristics, Cell, Coord, Difficulty, DisplayableGrid, SIZE,
+    generate, generate_from_mask, generate_samurai, is_minimal, parse_cages, parse_dots,
+    parse_puzzle, solve_backtracking_heuristics, Cage, Cell, Coord, Difficulty, DisplayableGrid,
+    Edge, Layout, Mask, Puzzle, Rules, Symmetry, Toggles, Variant, SIZE,
 };
 
+use crate::cli::read_puzzles;
 use crate::logic::Grid;
 use crate::ui::utils::{
-    get_char_response, get_move, get_num_response, prompt_for_value, PromptResponse,
+    get_char_response, get_coord, get_move, get_multi_move, get_num_response, get_raw_response,
+    get_response, prompt_for_value, styled, PromptResponse, Styled,
 };
 
 use self::utils::char_prompt;
@@ -26,6 +31,10 @@ pub fn run() {
         match choice {
             Choice::Solve => solve(),
             Choice::Play => play(),
+            Choice::SudokuX => play_variant(Variant::Diagonal),
+            Choice::Design => design(),
+            Choice::Load => load(),
+            Choice::Samurai => samurai(),
             Choice::Quit => break,
         }
     }
@@ -34,67 +43,264 @@ pub fn run() {
 #[derive(Debug, Copy, Clone)]
 enum Choice {
     Play,
+    SudokuX,
     Solve,
+    Design,
+    Load,
+    Samurai,
     Quit,
 }
 
 /// This main menu handles which of the high-level activities a player would
-/// like to do: play a puzzle or enter one to be solved.
+/// like to do: play a puzzle, play Sudoku X, enter one to be solved, load one
+/// to play, or play a Samurai puzzle.
 fn main_menu() -> Choice {
     println!("{}{SMALL_TITLE}{}", "\n".repeat(4), "\n".repeat(4));
     let map = BTreeMap::from([
         ('p', Choice::Play),
+        ('x', Choice::SudokuX),
         ('s', Choice::Solve),
+        ('d', Choice::Design),
+        ('l', Choice::Load),
+        ('m', Choice::Samurai),
         ('q', Choice::Quit),
     ]);
     println!(
-        "[P]lay a game or enter a puzzle to be [s]olved?\n\n(Enter \"q\" at any time to quit)"
+        "[P]lay a game, play Sudoku [x], where the diagonals also contain each number once, \
+         enter a puzzle to be [s]olved, [d]esign a puzzle from a pattern of clues, [l]oad a \
+         puzzle from a file, or play a Samurai puzzle of five overlapping grids [m]?\n\n\
+         (Enter \"q\" at any time to quit)"
     );
 
     char_prompt("What would you like to do?", map, Some('p'))
 }
 
-/// The "play" sub-menu of the main menu. This obtains a player's difficulty
-/// selection, as well as asking if they want a time limit.
+/// The "play" sub-menu of the main menu. This obtains a player's variant,
+/// then plays it as `play_variant` does.
 fn play() {
-    if let Some(difficulty) = difficulty_menu() {
-        println!(
-            "\nGenerating a puzzle with difficulty: {}",
-            match difficulty {
-                Difficulty::Easy => "easy",
-                Difficulty::Medium => "medium",
-                Difficulty::Hard => "hard",
-            }
-        );
-        let (g, time_taken) = generate(difficulty);
-        println!("Took {:?}\n", time_taken);
-
-        let time_constraint = time_menu();
-        match game_loop(g, time_constraint) {
-            Game::Solved(solve_time) => {
-                let solve_secs = solve_time.as_secs();
-                println!(
-                    "Congratulations! You solved the puzzle in {}",
-                    format_time(solve_secs)
-                );
-            }
-            Game::Quit => println!("{THANK_YOU}"),
+    let Some(variant) = variant_menu() else {
+        println!("{THANK_YOU}");
+        return;
+    };
+
+    play_variant(variant);
+}
+
+/// Obtains a player's extra constraints and difficulty selection for a
+/// puzzle of `variant`, as well as asking if they want a time limit.
+fn play_variant(variant: Variant) {
+    let Some(toggles) = toggles_menu() else {
+        println!("{THANK_YOU}");
+        return;
+    };
+    let Some((difficulty, symmetry)) = difficulty_menu() else {
+        println!("{THANK_YOU}");
+        return;
+    };
+
+    println!(
+        "\nGenerating a {variant} puzzle with difficulty: {difficulty}, symmetry: {symmetry}, \
+         extra constraints: {toggles}"
+    );
+    let (g, time_taken) = match generate(difficulty, symmetry, variant, toggles) {
+        Ok(generated) => generated,
+        Err(e) => {
+            println!("Couldn't generate a puzzle: {e}");
+            return;
+        }
+    };
+    println!("Took {:?}\n", time_taken);
+    if difficulty == Difficulty::Minimal {
+        if is_minimal(&g) {
+            println!(
+                "The puzzle is minimal: removing any clue would allow more than one solution\n"
+            );
+        } else {
+            println!(
+                "The puzzle is minimal for its symmetry, but some single clues could be removed\n"
+            );
+        }
+    }
+
+    play_puzzle(g);
+}
+
+/// A menu for asking the player which variant of Sudoku they'd like.
+fn variant_menu() -> Option<Variant> {
+    let msg = "\nSelect variant:\n\n- Classic [c]\n- Sudoku X, where the shaded diagonals also \
+               contain each number once [x]\n- Hyper, where four shaded extra boxes also contain \
+               each number once [w]\n- Jigsaw, where irregular regions replace the boxes \
+               [j]\n- Killer, where the numbers in each cage add up to its sum [k]\n- Kropki, \
+               where a white dot joins consecutive numbers and a black dot joins numbers where \
+               one is double the other [d]\n";
+    let map = BTreeMap::from([
+        ('c', Some(Variant::Standard)),
+        ('x', Some(Variant::Diagonal)),
+        ('w', Some(Variant::Hyper)),
+        ('j', Some(Variant::Jigsaw)),
+        ('k', Some(Variant::Killer)),
+        ('d', Some(Variant::Kropki)),
+        ('q', None),
+    ]);
+
+    char_prompt(msg, map, Some('c'))
+}
+
+/// A menu for asking the player which extra constraints they'd like on top of
+/// the variant's rules.
+fn toggles_menu() -> Option<Toggles> {
+    println!(
+        "\nSelect extra constraints, separated by commas:\n\n- Anti-knight, where cells a \
+         knight's move apart can't contain the same number [n]\n- Anti-king, where diagonally \
+         touching cells can't contain the same number [k]\n- Non-consecutive, where touching \
+         cells can't contain consecutive numbers [c]"
+    );
+    loop {
+        let response = get_response("Press enter for none ([q]uit)\n> ");
+        if response == "q" {
+            return None;
+        }
+
+        match response.parse::<Toggles>() {
+            Ok(toggles) => return Some(toggles),
+            Err(e) => print!("{e}"),
         }
-    } else {
+    }
+}
+
+/// The "Samurai" sub-menu of the main menu. This obtains a player's
+/// difficulty selection, then generates a Samurai puzzle and lets them play
+/// it.
+fn samurai() {
+    let Some(difficulty) = difficulty_prompt() else {
         println!("{THANK_YOU}");
+        return;
+    };
+
+    println!("\nGenerating a Samurai puzzle with difficulty: {difficulty}");
+    let now = time::Instant::now();
+    let g = match generate_samurai(difficulty, &mut rand::thread_rng()) {
+        Ok(g) => g,
+        Err(e) => {
+            println!("Couldn't generate a puzzle: {e}");
+            return;
+        }
+    };
+    println!("Took {:?}\n", now.elapsed());
+
+    let time_constraint = time_menu();
+    end_game(game_loop(g, time_constraint, get_multi_move, HOW_TO_MULTI));
+}
+
+/// Asks the player if they want a time limit, then lets them play `g`.
+fn play_puzzle(g: Grid) {
+    let time_constraint = time_menu();
+    end_game(game_loop(g, time_constraint, get_move, HOW_TO));
+}
+
+/// Tells the player how their game ended.
+fn end_game(game: Game) {
+    match game {
+        Game::Solved(solve_time) => {
+            let solve_secs = solve_time.as_secs();
+            println!(
+                "Congratulations! You solved the puzzle in {}",
+                format_time(solve_secs)
+            );
+        }
+        Game::Quit => println!("{THANK_YOU}"),
+    }
+}
+
+/// How long to search for a puzzle matching a player's pattern of clues.
+const MASK_TIMEOUT: time::Duration = time::Duration::from_secs(30);
+
+/// The "design" sub-menu of the main menu. This obtains a pattern of clues
+/// from the player, searches for a puzzle with that pattern, and lets them
+/// play it.
+fn design() {
+    let Some(mask) = mask_from_input() else {
+        println!("{THANK_YOU}");
+        return;
+    };
+
+    println!(
+        "\nSearching for a puzzle with that pattern, for up to {} seconds...",
+        MASK_TIMEOUT.as_secs()
+    );
+    let now = time::Instant::now();
+    match generate_from_mask(&mask, MASK_TIMEOUT, &mut rand::thread_rng()) {
+        Ok(g) => {
+            println!("Took {:?}\n", now.elapsed());
+            play_puzzle(g);
+        }
+        Err(e) => println!("{e}"),
     }
 }
 
+/// The "load" sub-menu of the main menu. This reads the first puzzle in a
+/// file in line format, which can include thermometers, arrows and sandwich
+/// sums as well as the sections of each variant, and lets the player play it.
+fn load() {
+    let Some((variant, toggles)) = variant_menu().zip(toggles_menu()) else {
+        println!("{THANK_YOU}");
+        return;
+    };
+
+    loop {
+        let path = get_raw_response(
+            "\nEnter the path of a file with a puzzle in line format on its first line ([q]uit)\n> ",
+        );
+        if path == "q" {
+            println!("{THANK_YOU}");
+            return;
+        }
+
+        let puzzle = read_puzzles(&path).and_then(|puzzles| {
+            let first = puzzles.first().ok_or("The file has no puzzles")?;
+            parse_puzzle(first, variant, toggles).map_err(|e| e.to_string())
+        });
+        match puzzle {
+            Ok(g) => return play_puzzle(g),
+            Err(e) => println!("Couldn't load a puzzle: {}", e.trim_end()),
+        }
+    }
+}
+
+/// A menu for asking the player what difficulty they'd like to play, and what
+/// symmetry the clues should have.
+fn difficulty_menu() -> Option<(Difficulty, Symmetry)> {
+    let difficulty = difficulty_prompt()?;
+
+    let msg = "\nSelect clue symmetry:\n\n- None [n]\n- Rotational, 180° [r]\n\
+               - Rotational, 90° [t]\n- Diagonal [d]\n- Mirrored top to bottom [h]\n\
+               - Mirrored left to right [v]\n- All rotations and reflections [a]\n";
+    let map = BTreeMap::from([
+        ('n', Some(Symmetry::None)),
+        ('r', Some(Symmetry::Rotational180)),
+        ('t', Some(Symmetry::Rotational90)),
+        ('d', Some(Symmetry::Diagonal)),
+        ('h', Some(Symmetry::MirrorHorizontal)),
+        ('v', Some(Symmetry::MirrorVertical)),
+        ('a', Some(Symmetry::Dihedral)),
+        ('q', None),
+    ]);
+    let symmetry = char_prompt(msg, map, Some('n'))?;
+
+    Some((difficulty, symmetry))
+}
+
 /// A menu for asking the player what difficulty they'd like to play.
-fn difficulty_menu() -> Option<Difficulty> {
-    let msg = "\nSelect puzzle difficulty:\n\n- Easy [e]\n- Medium [m]\n- Hard[h]\n";
+fn difficulty_prompt() -> Option<Difficulty> {
+    let msg = "\nSelect puzzle difficulty:\n\n- Easy [e]\n- Medium [m]\n- Hard[h]\n\
+               - Minimal, with no unnecessary clues [x]\n";
     let map = BTreeMap::from([
         ('e', Some(Difficulty::Easy)),
         ('m', Some(Difficulty::Medium)),
         ('h', Some(Difficulty::Hard)),
+        ('x', Some(Difficulty::Minimal)),
         ('q', None),
     ]);
-
     char_prompt(msg, map, Some('m'))
 }
 
@@ -115,17 +321,28 @@ fn time_menu() -> Option<time::Duration> {
     }
 }
 
+/// Signature of the functions that read a move in a puzzle of type `P` from
+/// the player: the cell and the value to put there.
+type MoveReader<P> = fn(&P) -> PromptResponse<(<P as Puzzle>::Target, Cell)>;
+
 /// Enum to model the result of a game
 enum Game {
     Solved(time::Duration),
     Quit,
 }
 
-/// The main game loop for when a player is solving a puzzle. Includes
+/// The main game loop for when a player is solving a puzzle, which can be a
+/// single grid or several overlapping grids. `read_move` reads a cell and
+/// value from the player, and `how_to` explains how to enter them. Includes
 /// undo/redo support, time constraints, and sophisticated input validation.
-fn game_loop(mut g: Grid, time_constraint: Option<time::Duration>) -> Game {
-    let mut undo_history: Vec<Grid> = Vec::new();
-    let mut redo_history: Vec<Grid> = Vec::new();
+fn game_loop<P: Puzzle + Styled>(
+    mut g: P,
+    time_constraint: Option<time::Duration>,
+    read_move: MoveReader<P>,
+    how_to: &str,
+) -> Game {
+    let mut undo_history: Vec<P> = Vec::new();
+    let mut redo_history: Vec<P> = Vec::new();
     let now = time::Instant::now();
     let (tx, rx) = mpsc::channel();
 
@@ -140,10 +357,10 @@ fn game_loop(mut g: Grid, time_constraint: Option<time::Duration>) -> Game {
         });
     }
 
-    println!("{g}\n\n{HOW_TO}\n");
+    println!("{}\n\n{how_to}\n", g.styled());
 
-    while !g.solved {
-        match get_move(&g) {
+    while !g.is_solved() {
+        match read_move(&g) {
             PromptResponse::Val((cell, val)) => {
                 if let Cell::Filled(n) = val {
                     let cur_g = g.clone();
@@ -152,7 +369,7 @@ fn game_loop(mut g: Grid, time_constraint: Option<time::Duration>) -> Game {
                     } else {
                         undo_history.push(cur_g);
                         redo_history.clear();
-                        println!("\n{g}\n");
+                        println!("\n{}\n", g.styled());
                     }
                 }
             }
@@ -161,7 +378,7 @@ fn game_loop(mut g: Grid, time_constraint: Option<time::Duration>) -> Game {
                 Some(last_g) => {
                     redo_history.push(g.clone());
                     g = last_g;
-                    println!("Move undone:\n\n{g}");
+                    println!("Move undone:\n\n{}", g.styled());
                 }
                 None => {
                     println!("No more moves to undo");
@@ -172,7 +389,7 @@ fn game_loop(mut g: Grid, time_constraint: Option<time::Duration>) -> Game {
                 Some(next_g) => {
                     undo_history.push(g);
                     g = next_g;
-                    println!("Move redone:\n\n{g}");
+                    println!("Move redone:\n\n{}", g.styled());
                 }
                 None => println!("No more moves to redo"),
             },
@@ -191,16 +408,43 @@ fn game_loop(mut g: Grid, time_constraint: Option<time::Duration>) -> Game {
 /// The "solve" sub-menu of the main menu. This obtains a grid from the player
 /// and proceeds to solve it.
 fn solve() {
-    match grid_from_input() {
+    let Some((variant, toggles)) = variant_menu().zip(toggles_menu()) else {
+        println!("{THANK_YOU}");
+        return;
+    };
+
+    let mut rules = variant.rules();
+    if variant == Variant::Jigsaw {
+        let Some(layout) = layout_from_input() else {
+            println!("{THANK_YOU}");
+            return;
+        };
+        rules = rules.with_layout(&layout);
+    } else if variant == Variant::Killer {
+        let Some(cages) = cages_from_input() else {
+            println!("{THANK_YOU}");
+            return;
+        };
+        rules = cages.into_iter().fold(rules, Rules::with_cage);
+    } else if variant == Variant::Kropki {
+        let Some((dots, negative)) = dots_from_input() else {
+            println!("{THANK_YOU}");
+            return;
+        };
+        rules = rules.with_dots(&dots, negative);
+    }
+
+    match grid_from_input(toggles.apply(rules)) {
         Some(g) => {
-            println!("{}", g);
+            println!("{}", g.styled());
 
             let now = time::Instant::now();
-            let g_solved = solve_backtracking_heuristics(g);
-            println!("Solved in {:?}", now.elapsed());
-
-            if let Some(g) = g_solved {
-                println!("{}", g);
+            match solve_backtracking_heuristics(g) {
+                Ok(g) => {
+                    println!("Solved in {:?}", now.elapsed());
+                    println!("{}", g.styled());
+                }
+                Err(e) => println!("Couldn't solve the puzzle: {e}"),
             }
         }
         None => {
@@ -209,6 +453,170 @@ fn solve() {
     }
 }
 
+/// Obtains a pattern of clues from user input, one row at a time. Returns
+/// `None` if the user quits.
+pub fn mask_from_input() -> Option<Mask> {
+    rows_from_input(
+        "\"x\" for a clue and \".\" for an empty cell",
+        parse_mask_row,
+        |clue| if clue { '#' } else { ' ' },
+    )
+}
+
+/// Obtains the layout of a Jigsaw puzzle from user input, one row at a time.
+/// Returns `None` if the user quits. If the regions are invalid, the player is
+/// asked to enter the layout again.
+pub fn layout_from_input() -> Option<Layout> {
+    loop {
+        let rows = rows_from_input(
+            "a letter for each cell, the same for cells in the same region",
+            parse_layout_row,
+            |c| c,
+        )?;
+        let layout: String = rows.iter().flatten().collect();
+        match layout.parse::<Layout>() {
+            Ok(layout) => return Some(layout),
+            Err(e) => print!("\nThat layout is invalid: {e}Please enter it again"),
+        }
+    }
+}
+
+/// Obtains the cages of a Killer puzzle from user input: a map of the cages,
+/// one row at a time, then their sums. Returns `None` if the user quits. If
+/// the cages are invalid, the player is asked to enter them again.
+pub fn cages_from_input() -> Option<Vec<Cage>> {
+    loop {
+        let rows = rows_from_input(
+            "a letter for each cell, the same for cells in the same cage, or \".\" for none",
+            parse_layout_row,
+            |c| c,
+        )?;
+        let map: String = rows.iter().flatten().collect();
+        let sums = get_response(
+            "Enter the sum of each cage in the order of their first cells, separated by \"/\" \
+             ([q]uit)\n> ",
+        );
+        if sums == "q" {
+            return None;
+        }
+
+        match parse_cages(&map, &sums) {
+            Ok(cages) => return Some(cages),
+            Err(e) => print!("\nThose cages are invalid: {e}Please enter them again"),
+        }
+    }
+}
+
+/// Obtains the dots of a Kropki puzzle from user input, first between the
+/// cells side by side, then between the cells one above the other. Returns
+/// the dots and whether the negative constraint is used, or `None` if the user
+/// quits.
+pub fn dots_from_input() -> Option<(Vec<Edge>, bool)> {
+    let format = "\"o\" for a white dot, \"*\" for a black dot and \".\" for none, which can be \
+                  spaced out";
+    loop {
+        let across = get_response(&format!(
+            "\nEnter the dots between cells side by side, row by row, as {} characters: \
+             {format} ([q]uit)\n> ",
+            SIZE * (SIZE - 1)
+        ));
+        if across == "q" {
+            return None;
+        }
+        let down = get_response(&format!(
+            "Enter the dots between cells one above the other, row by row, as {} characters: \
+             {format} ([q]uit)\n> ",
+            SIZE * (SIZE - 1)
+        ));
+        if down == "q" {
+            return None;
+        }
+        let negative = get_response(
+            "Are the touching cells without a dot neither consecutive nor one double the other? \
+             [y/n]\n> ",
+        );
+
+        let negative = if negative == "y" { "!" } else { "" };
+        match parse_dots(&format!("{across}{down}{negative}")) {
+            Ok(dots) => return Some(dots),
+            Err(e) => print!("\nThose dots are invalid: {e}Please enter them again"),
+        }
+    }
+}
+
+/// Reads a grid from user input one row at a time, where each row is `SIZE`
+/// characters described by `format`. `parse_row` reads a row, and `show`
+/// gives the character each value is displayed as. Returns `None` if the user
+/// quits.
+fn rows_from_input<T: Copy>(
+    format: &str,
+    parse_row: fn(&str) -> Option<[T; SIZE]>,
+    show: fn(T) -> char,
+) -> Option<[[T; SIZE]; SIZE]> {
+    let mut input: Vec<[T; SIZE]> = Vec::new();
+    let mut redo_stack = Vec::new();
+
+    while input.len() != SIZE {
+        let display_grid = DisplayableGrid(std::array::from_fn(|i| {
+            std::array::from_fn(|j| match input.get(i) {
+                Some(row) => show(row[j]),
+                None if i == input.len() => '?',
+                None => ' ',
+            })
+        }));
+        println!("\n{}\n", display_grid);
+        let prompt = format!(
+            "Enter row {}, marked with '?', as {SIZE} characters: {format} ([u]ndo, [r]edo, \
+             [q]uit)\n> ",
+            input.len() + 1
+        );
+
+        match get_response(&prompt).as_str() {
+            "q" => return None,
+            "u" => match input.pop() {
+                None => println!("No more rows to undo"),
+                Some(last_row) => redo_stack.push(last_row),
+            },
+            "r" => match redo_stack.pop() {
+                None => println!("No more rows to redo"),
+                Some(next_row) => input.push(next_row),
+            },
+            response => match parse_row(response) {
+                Some(row) => {
+                    input.push(row);
+                    redo_stack.clear();
+                }
+                None => println!("Please enter exactly {SIZE} characters: {format}"),
+            },
+        }
+    }
+
+    Some(std::array::from_fn(|i| input[i]))
+}
+
+/// Parses a row of a clue pattern, ignoring spaces. Returns `None` if the row
+/// has the wrong length or contains other characters.
+fn parse_mask_row(response: &str) -> Option<[bool; SIZE]> {
+    let cells: Vec<bool> = response
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| match c {
+            'x' | '#' | '*' => Some(true),
+            '.' | '-' | '_' => Some(false),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+
+    cells.try_into().ok()
+}
+
+/// Parses a row of a Jigsaw layout or Killer cage map, ignoring spaces. Returns `None` if the
+/// row has the wrong length.
+fn parse_layout_row(response: &str) -> Option<[char; SIZE]> {
+    let cells: Vec<char> = response.chars().filter(|c| !c.is_whitespace()).collect();
+    cells.try_into().ok()
+}
+
 /// Transforms a Vec into a grid. This will panic if the Vec is too small.
 fn grid_from_vec(v: Vec<Cell>) -> DisplayableGrid<Cell> {
     DisplayableGrid(std::array::from_fn(|i| {
@@ -216,8 +624,58 @@ fn grid_from_vec(v: Vec<Cell>) -> DisplayableGrid<Cell> {
     }))
 }
 
-/// Obtains a grid from user input. Returns `None` if the user quits.
-pub fn grid_from_input() -> Option<Grid> {
+/// Obtains a grid following `rules` from user input. Returns `None` if the
+/// user quits. If the clues entered are invalid, the player is asked to
+/// correct them.
+pub fn grid_from_input(rules: Rules) -> Option<Grid> {
+    let rules = Arc::new(rules);
+    correct_grid(grid_input(&rules)?, rules)
+}
+
+/// Shows the player why `input` is invalid, with any conflicting clues
+/// highlighted, and lets them change cells until it's a valid grid. Returns
+/// `None` if the user quits.
+fn correct_grid(mut input: DisplayableGrid<Cell>, rules: Arc<Rules>) -> Option<Grid> {
+    loop {
+        let e = match Grid::from_rows_with_rules(input.0, rules.clone()) {
+            Ok(g) => return Some(g),
+            Err(e) => e,
+        };
+
+        let conflicts = rules.conflicts(&input.0);
+        let cells: Vec<Coord> = conflicts.iter().flat_map(|c| c.cells.clone()).collect();
+        println!("\n{}\n", styled(&rules, &input, &cells));
+        print!("That puzzle is invalid: {e}");
+        for conflict in conflicts.iter() {
+            println!("- {conflict}");
+        }
+        println!("\nChoose a cell to correct (conflicting clues are highlighted)");
+
+        match get_coord() {
+            PromptResponse::Val(user_cell) => {
+                if !(1..=SIZE).contains(&user_cell.row) || !(1..=SIZE).contains(&user_cell.col) {
+                    println!("Rows and columns must be between 1 and {SIZE}");
+                    continue;
+                }
+
+                let prompt = format!(
+                    "Enter the new value for cell {user_cell}, or leave blank to clear it\n> "
+                );
+                match prompt_for_value(&prompt, true) {
+                    PromptResponse::Val(c) => input.0[user_cell.row - 1][user_cell.col - 1] = c,
+                    PromptResponse::Quit => return None,
+                    _ => println!("Undo and redo aren't available while correcting a puzzle"),
+                }
+            }
+            PromptResponse::Quit => return None,
+            _ => println!("Undo and redo aren't available while correcting a puzzle"),
+        }
+    }
+}
+
+/// Reads the clues for a grid following `rules` cell by cell. Returns `None`
+/// if the user quits.
+fn grid_input(rules: &Rules) -> Option<DisplayableGrid<Cell>> {
     let mut input: Vec<Cell> = Vec::new();
     let mut redo_stack = Vec::new();
 
@@ -225,7 +683,7 @@ pub fn grid_from_input() -> Option<Grid> {
     while input.len() != SIZE.pow(2) {
         let (i, j) = (input.len() / SIZE, input.len() % SIZE);
         display_grid.0[i][j] = Cell::Clue(0);
-        println!("\n{}\n", display_grid);
+        println!("\n{}\n", styled(rules, &display_grid, &[]));
         let prompt = format!(
             "Please enter value for cell {}, marked with a '?' ([u]ndo, [r]edo, [q]uit)\n> ",
             Coord::from((i + 1, j + 1))
@@ -257,8 +715,7 @@ pub fn grid_from_input() -> Option<Grid> {
         }
     }
 
-    let new_g = grid_from_vec(input).into();
-    Some(new_g)
+    Some(grid_from_vec(input))
 }
 
 mod utils;
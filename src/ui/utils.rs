@@ -6,9 +6,10 @@ use std::{
 };
 
 use crate::{
-    logic::{Cell, Coord, DisplayableGrid, Grid},
+    logic::{Background, Cell, Coord, DisplayableGrid, Grid, MultiGrid, Rules},
     SIZE,
 };
+use colored::Colorize;
 use lazy_static::lazy_static;
 use regex::Regex;
 
@@ -46,6 +47,44 @@ pub fn get_coord() -> PromptResponse<Coord> {
     }
 }
 
+/// Get the cell of a grid in a multi-grid puzzle from the player, as the
+/// grid's number, counting from 1 up to `grids`, then the row and column in
+/// that grid. Loops until the numbers are in range.
+pub fn get_grid_coord(grids: usize) -> PromptResponse<(usize, Coord)> {
+    lazy_static! {
+        static ref GRID_COORD_REGEX: Regex = Regex::new(
+            format!(
+                "^\\D*(?P<grid>\\d+)\\D+(?P<row>\\d{{1, {NUM_WIDTH}}})\\D+(?P<col>\\d{{1, {NUM_WIDTH}}})\\D*$"
+            )
+            .as_str()
+        )
+        .unwrap();
+    }
+    loop {
+        let r = get_response("Enter cell (format: \"grid row col\")\n> ");
+        match r.as_str() {
+            "u" => return PromptResponse::Undo,
+            "r" => return PromptResponse::Redo,
+            "q" => return PromptResponse::Quit,
+            _ => {
+                let Some(caps) = GRID_COORD_REGEX.captures(&r) else {
+                    println!("Invalid format, please provide cell as \"<grid> <row> <col>\"");
+                    continue;
+                };
+                let parse = |name: &str| caps[name].parse::<usize>().unwrap_or_default();
+                let (grid, row, col) = (parse("grid"), parse("row"), parse("col"));
+                if !(1..=grids).contains(&grid) {
+                    println!("Grids are numbered from 1 to {grids}");
+                } else if !(1..=SIZE).contains(&row) || !(1..=SIZE).contains(&col) {
+                    println!("Rows and columns must be between 1 and {SIZE}");
+                } else {
+                    return PromptResponse::Val((grid, Coord::from((row, col))));
+                }
+            }
+        };
+    }
+}
+
 /// Formats time in seconds into "x hours, x minutes, x seconds", omitting
 /// units appropriately and accounting for correct pluralisation, e.g. "25
 /// minutes, 1 second".
@@ -77,7 +116,10 @@ pub fn get_move(g: &Grid) -> PromptResponse<(Coord, Cell)> {
             // show user the grid they've chosen
             let mut display_g = DisplayableGrid(*g.rows());
             display_g.0[acc_cell.row][acc_cell.col] = Cell::Clue(0);
-            println!("{display_g}\nCell {user_cell} marked with \"?\"");
+            println!(
+                "{}\nCell {user_cell} marked with \"?\"",
+                styled(g.rules(), &display_g, &[])
+            );
 
             match prompt_for_value(&format!("Enter the value for cell {user_cell}\n> "), false) {
                 PromptResponse::Val(val) => PromptResponse::Val((acc_cell, val)),
@@ -92,6 +134,84 @@ pub fn get_move(g: &Grid) -> PromptResponse<(Coord, Cell)> {
     }
 }
 
+/// Obtains a cell of one of the grids of `g` and a value from the player,
+/// accepting undo/redo and quit.
+pub fn get_multi_move(g: &MultiGrid) -> PromptResponse<((usize, Coord), Cell)> {
+    match get_grid_coord(g.grids().len()) {
+        PromptResponse::Val((grid, user_cell)) => {
+            // adjust the grid and coord to match the zero-based arrays
+            let (i, acc_cell) = (
+                grid - 1,
+                Coord::from((user_cell.row - 1, user_cell.col - 1)),
+            );
+            println!(
+                "{}\nCell {user_cell} of grid {grid} marked with \"?\"",
+                g.marked(i, acc_cell).with_cells(styled_cell)
+            );
+
+            let prompt = format!("Enter the value for cell {user_cell} of grid {grid}\n> ");
+            match prompt_for_value(&prompt, false) {
+                PromptResponse::Val(val) => PromptResponse::Val(((i, acc_cell), val)),
+                PromptResponse::Undo => PromptResponse::Undo,
+                PromptResponse::Redo => PromptResponse::Redo,
+                PromptResponse::Quit => PromptResponse::Quit,
+            }
+        }
+        PromptResponse::Undo => PromptResponse::Undo,
+        PromptResponse::Redo => PromptResponse::Redo,
+        PromptResponse::Quit => PromptResponse::Quit,
+    }
+}
+
+/// Returns `cell` as it's shown in the terminal, with clues in bold.
+pub fn styled_cell(cell: &Cell) -> String {
+    match cell {
+        Cell::Clue(n) if *n != 0 => cell.to_string().bold().to_string(),
+        _ => cell.to_string(),
+    }
+}
+
+/// Returns `g` displayed under `rules` for the terminal, with clues in bold,
+/// cells shaded with their background under `rules` and the cells at
+/// `highlighted` in red.
+pub fn styled(rules: &Rules, g: &DisplayableGrid<Cell>, highlighted: &[Coord]) -> String {
+    let cells = DisplayableGrid(std::array::from_fn(|i| {
+        std::array::from_fn(|j| {
+            let coord = Coord::from((i, j));
+            let mut cell = styled_cell(&g.0[i][j]);
+            if highlighted.contains(&coord) {
+                cell = cell.red().to_string();
+            }
+            match rules.background(coord) {
+                Some(Background::Blue) => cell.on_blue().to_string(),
+                Some(Background::Cyan) => cell.on_cyan().to_string(),
+                Some(Background::Magenta) => cell.on_magenta().to_string(),
+                Some(Background::Grey) => cell.on_bright_black().to_string(),
+                None => cell,
+            }
+        })
+    }));
+    rules.display(&cells)
+}
+
+/// Trait for puzzles that can be shown in the terminal with colour, rather
+/// than as the plain text of their `Display`.
+pub trait Styled {
+    fn styled(&self) -> String;
+}
+
+impl Styled for Grid {
+    fn styled(&self) -> String {
+        styled(self.rules(), &DisplayableGrid(*self.rows()), &[])
+    }
+}
+
+impl Styled for MultiGrid {
+    fn styled(&self) -> String {
+        self.display().with_cells(styled_cell).to_string()
+    }
+}
+
 #[derive(Debug)]
 /// Models a player response where undo, redo, and quit are acceptable in
 /// addition to the generic value `T`.
@@ -105,6 +225,12 @@ pub enum PromptResponse<T> {
 /// Get a string response from the player, accounting for any unexepected
 /// read errors.
 pub fn get_response(prompt: &str) -> String {
+    get_raw_response(prompt).to_lowercase()
+}
+
+/// Get a string response from the player without changing its case, for
+/// answers such as file paths.
+pub fn get_raw_response(prompt: &str) -> String {
     loop {
         print!("{prompt}");
         let _ = std::io::stdout().flush();
@@ -113,7 +239,7 @@ pub fn get_response(prompt: &str) -> String {
         if let Err(e) = io::stdin().read_line(&mut response) {
             println!("Unexpected error: {e}\nPlease try again");
         } else {
-            return response.trim().to_lowercase();
+            return response.trim().to_string();
         }
     }
 }
@@ -154,7 +280,7 @@ where
     let prompt = format!(
         "{prompt}{}\n{}> ",
         format_chars(&map),
-        default.unwrap_or(char::default())
+        default.unwrap_or_default()
     );
 
     loop {
@@ -220,6 +346,11 @@ pub const HOW_TO: &str = "Enter numbers using the row/column indices to the left
             The centre cell of the box to its left is 5, 2.\n\nWhen guessing,\
             enter the cell as \"row<space>column\", e.g. \"8 3\"";
 
+pub const HOW_TO_MULTI: &str = "Each grid is numbered above its top-left corner, and \
+            enter cells as \"grid<space>row<space>column\", with rows and columns \
+            counted within that grid, e.g. \"3 5 5\" for the centre cell of the \
+            puzzle.\nCells where two grids overlap can be entered from either one.";
+
 pub const SMALL_TITLE: &str = r"   _____           _       _          
   / ____|         | |     | |         
  | (___  _   _  __| | ___ | | ___   _ 
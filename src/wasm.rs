@@ -0,0 +1,212 @@
+use std::str::FromStr;
+
+use js_sys::{Error, RangeError};
+use rand::{rngs::StdRng, SeedableRng};
+use wasm_bindgen::prelude::*;
+
+use crate::logic::{
+    count_solutions, generate_with_rng, parse_puzzle, rate, solve_with_limit, Cell, Coord,
+    Difficulty, Grid, GridError, Symmetry, Toggles, Variant, SIZE,
+};
+
+/// Number of times solving a grid can backtrack before giving up, so a grid
+/// with no solution can't hang the page.
+const BACKTRACK_LIMIT: usize = 10_000;
+
+/// Turn a `GridError` into a JavaScript `Error` whose name is the error's
+/// kind, e.g. `NoSolution`, so callers can tell errors apart without reading
+/// the message.
+impl From<GridError> for JsValue {
+    fn from(e: GridError) -> Self {
+        let error = Error::new(e.to_string().trim_end());
+        error.set_name(&format!("{:?}", e.kind));
+        error.into()
+    }
+}
+
+#[wasm_bindgen(js_name = Grid)]
+/// A puzzle being played, wrapping `Grid`. Rows and columns count from 0.
+pub struct JsGrid(Grid);
+
+#[wasm_bindgen(js_class = Grid)]
+impl JsGrid {
+    /// Parse a puzzle in line format. `variant` and `constraints` are named as
+    /// on the command line, and default to standard with none.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        line: &str,
+        variant: Option<String>,
+        constraints: Option<String>,
+    ) -> Result<JsGrid, JsValue> {
+        let variant = named(variant, Variant::default())?;
+        let toggles = named(constraints, Toggles::default())?;
+        Ok(JsGrid(parse_puzzle(line, variant, toggles)?))
+    }
+
+    /// Returns the value at `row` and `col`, or `undefined` if it's empty.
+    pub fn get(&self, row: usize, col: usize) -> Result<Option<u8>, JsValue> {
+        Ok(self.0.get_cell(coord(row, col)?).value())
+    }
+
+    /// Returns `true` if the cell at `row` and `col` is a clue.
+    #[wasm_bindgen(js_name = isClue)]
+    pub fn is_clue(&self, row: usize, col: usize) -> Result<bool, JsValue> {
+        Ok(matches!(self.0.get_cell(coord(row, col)?), Cell::Clue(_)))
+    }
+
+    /// Returns the values that can still go in the cell at `row` and `col`.
+    pub fn candidates(&self, row: usize, col: usize) -> Result<Vec<u8>, JsValue> {
+        Ok(self.0.candidates_at(coord(row, col)?))
+    }
+
+    /// Put `val` in the cell at `row` and `col`. Throws if the cell is a clue
+    /// or `val` isn't one of its candidates.
+    pub fn update(&mut self, row: usize, col: usize, val: u8) -> Result<(), JsValue> {
+        Ok(self.0.update(coord(row, col)?, val)?)
+    }
+
+    /// Empty the cell at `row` and `col`. Throws if the cell is a clue.
+    pub fn clear(&mut self, row: usize, col: usize) -> Result<(), JsValue> {
+        Ok(self.0.clear(coord(row, col)?)?)
+    }
+
+    /// Whether every cell is filled without breaking the rules.
+    #[wasm_bindgen(getter)]
+    pub fn solved(&self) -> bool {
+        self.0.solved
+    }
+
+    /// The number of cells left to fill.
+    #[wasm_bindgen(getter, js_name = emptyCells)]
+    pub fn empty_cells(&self) -> usize {
+        self.0.empty_cell_count
+    }
+
+    /// Returns a solved copy of the grid. Throws a `NoSolution` error if it
+    /// can't be solved, or a `TimedOut` error if the search gives up first.
+    pub fn solve(&self) -> Result<JsGrid, JsValue> {
+        let mut rng = StdRng::seed_from_u64(0);
+        let solution = solve_with_limit(self.0.clone(), BACKTRACK_LIMIT, &mut rng)?;
+        Ok(JsGrid(solution))
+    }
+
+    /// Count the solutions of the grid, stopping once `limit` have been found.
+    #[wasm_bindgen(js_name = countSolutions)]
+    pub fn count_solutions(&self, limit: usize) -> usize {
+        count_solutions(self.0.clone(), limit)
+    }
+
+    /// Rate how hard the grid is for a person to solve.
+    pub fn rate(&self) -> Result<JsRating, JsValue> {
+        let rating = rate(&self.0)?;
+        Ok(JsRating {
+            difficulty: rating.difficulty.to_string(),
+            score: rating.score,
+        })
+    }
+
+    /// Write the grid in line format.
+    #[wasm_bindgen(js_name = toLine)]
+    pub fn to_line(&self) -> String {
+        self.0.to_line()
+    }
+}
+
+#[wasm_bindgen(js_name = Rating, getter_with_clone)]
+/// How hard a puzzle is for a person to solve, as returned by `rate`.
+pub struct JsRating {
+    pub difficulty: String,
+    pub score: u32,
+}
+
+/// Generate a puzzle of `difficulty` from `seed`, so the same seed always
+/// gives the same puzzle. WebAssembly has no source of randomness of its own,
+/// so pass e.g. a value from `crypto.getRandomValues` for a new puzzle each
+/// time. `symmetry`, `variant` and `constraints` are named as on the command
+/// line.
+#[wasm_bindgen]
+pub fn generate(
+    difficulty: &str,
+    seed: u64,
+    symmetry: Option<String>,
+    variant: Option<String>,
+    constraints: Option<String>,
+) -> Result<JsGrid, JsValue> {
+    let g = generate_with_rng(
+        Difficulty::from_str(difficulty)?,
+        named(symmetry, Symmetry::default())?,
+        named(variant, Variant::default())?,
+        named(constraints, Toggles::default())?,
+        &mut StdRng::seed_from_u64(seed),
+    )?;
+    Ok(JsGrid(g))
+}
+
+/// Parse `val` as one of a set of names, or return `default` if it wasn't
+/// given.
+fn named<T: FromStr<Err = GridError>>(val: Option<String>, default: T) -> Result<T, JsValue> {
+    match val {
+        Some(val) => Ok(val.parse()?),
+        None => Ok(default),
+    }
+}
+
+/// Returns the coordinates of `row` and `col`, or a `RangeError` if they're
+/// outside the grid.
+fn coord(row: usize, col: usize) -> Result<Coord, JsValue> {
+    if row < SIZE && col < SIZE {
+        Ok((row, col).into())
+    } else {
+        Err(RangeError::new(&format!("Cell ({row}, {col}) is outside the grid")).into())
+    }
+}
+
+// Anything that builds a JavaScript value, such as an error, only works in a
+// WebAssembly host, so these tests cover the calls that succeed natively.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUZZLE: &str =
+        "530070000600195000098000060800060003400803001700020006060000280000419005000080079";
+    const SOLUTION: &str =
+        "534678912672195348198342567859761423426853791713924856961537284287419635345286179";
+
+    #[test]
+    fn plays_a_puzzle() {
+        let mut g = JsGrid::new(PUZZLE, None, None).unwrap();
+        assert_eq!(g.get(0, 0).unwrap(), Some(5));
+        assert_eq!(g.get(0, 2).unwrap(), None);
+        assert!(g.is_clue(0, 0).unwrap());
+        assert_eq!(g.candidates(0, 2).unwrap(), [1, 2, 4]);
+
+        g.update(0, 2, 4).unwrap();
+        assert!(!g.is_clue(0, 2).unwrap());
+        assert_eq!(g.empty_cells(), PUZZLE.matches('0').count() - 1);
+        g.clear(0, 2).unwrap();
+        assert_eq!(g.get(0, 2).unwrap(), None);
+        assert!(!g.solved());
+    }
+
+    #[test]
+    fn solves_counts_and_rates() {
+        let g = JsGrid::new(PUZZLE, Some("standard".into()), Some("none".into())).unwrap();
+        let solution = g.solve().unwrap();
+        assert!(solution.solved());
+        assert_eq!(solution.to_line(), SOLUTION);
+        assert_eq!(g.count_solutions(2), 1);
+        assert_eq!(g.rate().unwrap().difficulty, "easy");
+
+        let empty = JsGrid::new(&"0".repeat(SIZE.pow(2)), Some("diagonal".into()), None).unwrap();
+        assert!(empty.solve().unwrap().solved());
+        assert_eq!(empty.count_solutions(3), 3);
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_puzzle() {
+        let first = generate("easy", 7, Some("rotational".into()), None, None).unwrap();
+        let second = generate("easy", 7, Some("rotational".into()), None, None).unwrap();
+        assert_eq!(first.to_line(), second.to_line());
+        assert_eq!(first.count_solutions(2), 1);
+    }
+}
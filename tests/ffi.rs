@@ -0,0 +1,178 @@
+use std::{
+    ffi::{c_char, CStr, CString},
+    ptr,
+};
+
+use scanlan_sudoku::ffi::SudokuStatus;
+
+/// Stands in for the opaque handle, which C only sees through pointers.
+#[repr(C)]
+struct SudokuGrid {
+    _private: [u8; 0],
+}
+
+// Declared as in the header, so the library is called as C code calls it.
+extern "C" {
+    fn sudoku_last_error() -> *const c_char;
+    fn sudoku_grid_parse(
+        line: *const c_char,
+        variant: *const c_char,
+        constraints: *const c_char,
+        out: *mut *mut SudokuGrid,
+    ) -> SudokuStatus;
+    fn sudoku_grid_solve(grid: *const SudokuGrid, out: *mut *mut SudokuGrid) -> SudokuStatus;
+    fn sudoku_grid_count_solutions(
+        grid: *const SudokuGrid,
+        limit: usize,
+        out: *mut usize,
+    ) -> SudokuStatus;
+    fn sudoku_generate(
+        difficulty: *const c_char,
+        seed: u64,
+        symmetry: *const c_char,
+        variant: *const c_char,
+        constraints: *const c_char,
+        out: *mut *mut SudokuGrid,
+    ) -> SudokuStatus;
+    fn sudoku_grid_get(grid: *const SudokuGrid, row: usize, col: usize) -> u8;
+    fn sudoku_grid_to_line(grid: *const SudokuGrid, buf: *mut c_char, len: usize) -> usize;
+    fn sudoku_grid_free(grid: *mut SudokuGrid);
+}
+
+const PUZZLE: &str =
+    "4.....8.5.3..........7......2.....6.....8.4......1.......6.3.7.5..2.....1.4......";
+const SOLUTION: &str =
+    "417369825632158947958724316825437169791586432346912758289643571573291684164875293";
+
+fn c(s: &str) -> CString {
+    CString::new(s).unwrap()
+}
+
+fn parse(line: &str) -> Result<*mut SudokuGrid, SudokuStatus> {
+    let mut grid = ptr::null_mut();
+    match unsafe { sudoku_grid_parse(c(line).as_ptr(), ptr::null(), ptr::null(), &mut grid) } {
+        SudokuStatus::Ok => Ok(grid),
+        status => Err(status),
+    }
+}
+
+fn to_line(grid: *const SudokuGrid) -> String {
+    unsafe {
+        let len = sudoku_grid_to_line(grid, ptr::null_mut(), 0);
+        let mut buf = vec![0 as c_char; len + 1];
+        sudoku_grid_to_line(grid, buf.as_mut_ptr(), buf.len());
+        CStr::from_ptr(buf.as_ptr()).to_str().unwrap().to_string()
+    }
+}
+
+fn last_error() -> String {
+    unsafe { CStr::from_ptr(sudoku_last_error()) }
+        .to_str()
+        .unwrap()
+        .to_string()
+}
+
+#[test]
+fn parses_solves_and_counts() {
+    let grid = parse(PUZZLE).unwrap();
+    assert_eq!(to_line(grid), PUZZLE);
+    assert_eq!(unsafe { sudoku_grid_get(grid, 0, 0) }, 4);
+    assert_eq!(unsafe { sudoku_grid_get(grid, 0, 1) }, 0);
+    assert_eq!(unsafe { sudoku_grid_get(grid, 9, 0) }, 0);
+
+    let mut count = 0;
+    let status = unsafe { sudoku_grid_count_solutions(grid, 2, &mut count) };
+    assert_eq!(status, SudokuStatus::Ok);
+    assert_eq!(count, 1);
+
+    let mut solution = ptr::null_mut();
+    assert_eq!(
+        unsafe { sudoku_grid_solve(grid, &mut solution) },
+        SudokuStatus::Ok
+    );
+    assert_eq!(to_line(solution), SOLUTION);
+    // Solving leaves the puzzle as it was.
+    assert_eq!(to_line(grid), PUZZLE);
+
+    unsafe {
+        sudoku_grid_free(grid);
+        sudoku_grid_free(solution);
+    }
+}
+
+#[test]
+fn reports_errors() {
+    assert_eq!(parse("123"), Err(SudokuStatus::InvalidFormat));
+    assert!(last_error().starts_with("Expected 81 cells"));
+
+    let clash = format!("44{}", ".".repeat(79));
+    assert_eq!(parse(&clash), Err(SudokuStatus::BrokenRules));
+
+    let mut grid = ptr::null_mut();
+    let status = unsafe {
+        sudoku_grid_parse(
+            c(PUZZLE).as_ptr(),
+            c("sideways").as_ptr(),
+            ptr::null(),
+            &mut grid,
+        )
+    };
+    assert_eq!(status, SudokuStatus::InvalidFormat);
+    assert!(last_error().contains("sideways"));
+    assert!(grid.is_null());
+
+    let status = unsafe { sudoku_grid_parse(ptr::null(), ptr::null(), ptr::null(), &mut grid) };
+    assert_eq!(status, SudokuStatus::NullPointer);
+    unsafe { sudoku_grid_free(ptr::null_mut()) };
+}
+
+#[test]
+fn generates_from_a_seed() {
+    let generate = |seed| {
+        let mut grid = ptr::null_mut();
+        let status = unsafe {
+            sudoku_generate(
+                c("easy").as_ptr(),
+                seed,
+                c("rotational").as_ptr(),
+                ptr::null(),
+                c("anti-king").as_ptr(),
+                &mut grid,
+            )
+        };
+        assert_eq!(status, SudokuStatus::Ok);
+        let line = to_line(grid);
+        unsafe { sudoku_grid_free(grid) };
+        line
+    };
+
+    let line = generate(5);
+    assert_eq!(line, generate(5));
+    assert_ne!(line, generate(6));
+    let cells = line.as_bytes();
+    assert!((0..81).all(|i| (cells[i] == b'.') == (cells[80 - i] == b'.')));
+
+    let mut grid = ptr::null_mut();
+    let status = unsafe {
+        sudoku_generate(
+            c("impossible").as_ptr(),
+            0,
+            ptr::null(),
+            ptr::null(),
+            ptr::null(),
+            &mut grid,
+        )
+    };
+    assert_eq!(status, SudokuStatus::InvalidFormat);
+}
+
+#[test]
+fn truncates_lines_to_fit() {
+    let grid = parse(PUZZLE).unwrap();
+    let mut buf = [1 as c_char; 8];
+    let len = unsafe { sudoku_grid_to_line(grid, buf.as_mut_ptr(), buf.len()) };
+    assert_eq!(len, 81);
+    let written = unsafe { CStr::from_ptr(buf.as_ptr()) };
+    assert_eq!(written.to_str().unwrap(), &PUZZLE[..7]);
+    unsafe { sudoku_grid_free(grid) };
+}
@@ -0,0 +1,107 @@
+use rand::{rngs::StdRng, SeedableRng};
+use scanlan_sudoku::logic::{
+    count_solutions, generate_with_rng, is_minimal, parse_puzzle, Cell, Difficulty, Symmetry,
+    Toggles, Variant,
+};
+
+/// Generate puzzles of `variant` with `toggles` from a few seeds at each
+/// difficulty, and check that every one has exactly one solution.
+fn assert_unique(variant: Variant, toggles: Toggles) {
+    for seed in 0..3 {
+        for d in [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard] {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let g =
+                generate_with_rng(d, Symmetry::Rotational180, variant, toggles, &mut rng).unwrap();
+            assert_eq!(
+                count_solutions(g, 2),
+                1,
+                "{d} {variant} puzzle with {toggles} from seed {seed}"
+            );
+        }
+    }
+}
+
+#[test]
+fn generates_unique_diagonal_puzzles() {
+    assert_unique(Variant::Diagonal, Toggles::default());
+}
+
+#[test]
+fn generates_unique_jigsaw_puzzles() {
+    assert_unique(Variant::Jigsaw, Toggles::default());
+}
+
+#[test]
+fn generates_unique_hyper_puzzles() {
+    assert_unique(Variant::Hyper, Toggles::default());
+}
+
+#[test]
+fn generates_unique_kropki_puzzles() {
+    assert_unique(Variant::Kropki, Toggles::default());
+}
+
+#[test]
+fn generates_unique_anti_knight_puzzles() {
+    let toggles = Toggles {
+        anti_knight: true,
+        ..Toggles::default()
+    };
+    assert_unique(Variant::Standard, toggles);
+}
+
+#[test]
+fn generates_unique_anti_king_puzzles() {
+    let toggles = Toggles {
+        anti_king: true,
+        ..Toggles::default()
+    };
+    assert_unique(Variant::Standard, toggles);
+}
+
+#[test]
+fn clue_patterns_have_the_symmetry_asked_for() {
+    for symmetry in Symmetry::ALL {
+        let mut rng = StdRng::seed_from_u64(1);
+        let g = generate_with_rng(
+            Difficulty::Hard,
+            symmetry,
+            Variant::Standard,
+            Toggles::default(),
+            &mut rng,
+        )
+        .unwrap();
+        for orbit in symmetry.orbits() {
+            let clues = orbit
+                .iter()
+                .filter(|&&cell| matches!(g.get_cell(cell), Cell::Clue(_)))
+                .count();
+            assert!(
+                clues == 0 || clues == orbit.len(),
+                "{symmetry} puzzle {} isn't symmetric",
+                g.to_line()
+            );
+        }
+    }
+}
+
+#[test]
+fn recognises_minimal_puzzles() {
+    // No puzzle with fewer than 17 clues has a unique solution, so removing
+    // any clue from this one gives it more.
+    let minimal = parse_puzzle(
+        "000000010400000000020000000000050407008000300001090000300400200050100000000806000",
+        Variant::Standard,
+        Toggles::default(),
+    )
+    .unwrap();
+    assert!(is_minimal(&minimal));
+
+    let not_minimal = parse_puzzle(
+        "530070000600195000098000060800060003400803001700020006060000280000419005000080079",
+        Variant::Standard,
+        Toggles::default(),
+    )
+    .unwrap();
+    assert!(!is_minimal(&not_minimal));
+}
@@ -0,0 +1,125 @@
+use scanlan_sudoku::{
+    logic::{parse_puzzle, solve_backtracking_heuristics, Cell, Grid, Toggles, Variant},
+    render::{book, grid_page, to_pdf, to_png, Colour, Highlights, Page, RenderOptions, Shape},
+};
+
+const PUZZLE: &str =
+    "530070000600195000098000060800060003400803001700020006060000280000419005000080079";
+
+fn puzzle() -> Grid {
+    parse_puzzle(PUZZLE, Variant::Standard, Toggles::default()).unwrap()
+}
+
+/// Returns the text drawn on `page`.
+fn texts(page: &Page) -> Vec<&str> {
+    page.shapes
+        .iter()
+        .filter_map(|shape| match shape {
+            Shape::Text { text, .. } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn pdf_cross_reference_points_at_each_object() {
+    let pages = book(
+        &[puzzle()],
+        &[solve_backtracking_heuristics(puzzle()).unwrap()],
+        &RenderOptions::default(),
+    );
+    let pdf = String::from_utf8(to_pdf(&pages)).unwrap();
+
+    let (_, start) = pdf.rsplit_once("startxref\n").unwrap();
+    let xref: usize = start.lines().next().unwrap().parse().unwrap();
+    let mut lines = pdf[xref..].lines();
+    assert_eq!(lines.next(), Some("xref"));
+    let count: usize = lines.next().unwrap()["0 ".len()..].parse().unwrap();
+    assert_eq!(lines.next(), Some("0000000000 65535 f "));
+
+    for n in 1..count {
+        let entry = lines.next().unwrap();
+        let offset: usize = entry[..10].parse().unwrap();
+        assert!(
+            pdf[offset..].starts_with(&format!("{n} 0 obj\n")),
+            "object {n} isn't at {offset}"
+        );
+    }
+    assert!(lines.next().unwrap().starts_with("trailer"));
+}
+
+#[test]
+fn answer_key_follows_the_puzzles() {
+    let puzzles = vec![puzzle(); 3];
+    let answers = vec![solve_backtracking_heuristics(puzzle()).unwrap(); 3];
+    let options = RenderOptions {
+        per_page: 2,
+        ..RenderOptions::default()
+    };
+    let pages = book(&puzzles, &answers, &options);
+
+    let titles: Vec<Vec<&str>> = pages
+        .iter()
+        .map(|page| {
+            texts(page)
+                .into_iter()
+                .filter(|t| t.contains(' '))
+                .collect()
+        })
+        .collect();
+    assert_eq!(
+        titles,
+        [
+            vec!["Puzzle 1", "Puzzle 2"],
+            vec!["Puzzle 3"],
+            vec!["Solution 1", "Solution 2"],
+            vec!["Solution 3"],
+        ]
+    );
+    assert_eq!(book(&puzzles, &[], &options).len(), 2);
+}
+
+#[test]
+fn clues_and_solved_values_are_drawn_differently() {
+    let mut g = puzzle();
+    g.update((0, 2).into(), 4).unwrap();
+    assert_eq!(g.get_cell((0, 2).into()), &Cell::Filled(4));
+    let page = grid_page(&g, 30.0, false, &Highlights::default());
+
+    let style = |digit: &str| {
+        page.shapes
+            .iter()
+            .find_map(|shape| match shape {
+                Shape::Text {
+                    text, bold, fill, ..
+                } if text == digit => Some((*bold, *fill)),
+                _ => None,
+            })
+            .unwrap()
+    };
+    // The clue 5 is first in the top row, and the only 4 is the one placed.
+    assert_eq!(style("5"), (true, Colour::BLACK));
+    assert_eq!(style("4"), (false, Colour::SOLUTION));
+
+    let pdf = String::from_utf8(to_pdf(&[page])).unwrap();
+    assert!(pdf.contains("BT /F2") && pdf.contains("BT /F1"));
+}
+
+#[test]
+fn png_matches_the_page_size() {
+    let page = grid_page(&puzzle(), 30.0, false, &Highlights::default());
+    let png = to_png(&page).unwrap();
+    let image = tiny_skia::Pixmap::decode_png(&png).unwrap();
+    assert_eq!(image.width(), page.width.ceil() as u32);
+    assert_eq!(image.height(), page.height.ceil() as u32);
+
+    // The margin is left white, and the grid's border is drawn in black.
+    let pixel = |x: u32, y: u32| image.pixel(x, y).unwrap();
+    let white = pixel(0, 0);
+    assert_eq!((white.red(), white.green(), white.blue()), (255, 255, 255));
+    let border = pixel(15, page.height as u32 / 2);
+    assert_eq!((border.red(), border.green(), border.blue()), (0, 0, 0));
+
+    let huge = grid_page(&puzzle(), 10_000.0, false, &Highlights::default());
+    assert!(to_png(&huge).is_err());
+}
@@ -0,0 +1,233 @@
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpStream},
+    sync::OnceLock,
+    thread,
+    time::{Duration, Instant},
+};
+
+use scanlan_sudoku::server::Server;
+use serde_json::{json, Value};
+
+const PUZZLE: &str =
+    "4.....8.5.3..........7......2.....6.....8.4......1.......6.3.7.5..2.....1.4......";
+const SOLUTION: &str =
+    "417369825632158947958724316825437169791586432346912758289643571573291684164875293";
+
+/// Start a server on a free port, shared by every test.
+fn server() -> SocketAddr {
+    static ADDR: OnceLock<SocketAddr> = OnceLock::new();
+    *ADDR.get_or_init(|| {
+        let server = Server::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        thread::spawn(move || server.run());
+        addr
+    })
+}
+
+/// Send a request and return the status code and JSON body of the reply.
+fn request(method: &str, target: &str, body: &str) -> (u16, Value) {
+    request_to(server(), method, target, body)
+}
+
+/// Send a request to the server at `addr`, as `request` does.
+fn request_to(addr: SocketAddr, method: &str, target: &str, body: &str) -> (u16, Value) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(
+        stream,
+        "{method} {target} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n{body}",
+        body.len()
+    )
+    .unwrap();
+
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply).unwrap();
+    let (head, body) = reply.split_once("\r\n\r\n").unwrap();
+    let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+    (status, serde_json::from_str(body).unwrap())
+}
+
+fn post(path: &str, body: Value) -> (u16, Value) {
+    request("POST", path, &body.to_string())
+}
+
+#[test]
+fn solves_a_puzzle() {
+    let (status, body) = post("/solve", json!({ "puzzle": PUZZLE }));
+    assert_eq!(status, 200);
+    assert_eq!(body["solution"], SOLUTION);
+    assert_eq!(body["solutions"], 1);
+}
+
+#[test]
+fn counts_more_than_one_solution() {
+    let puzzle = format!("{}{}", &SOLUTION[..72], ".".repeat(9));
+    let (status, body) = post("/solve", json!({ "puzzle": puzzle }));
+    assert_eq!(status, 200);
+    assert_eq!(body["solutions"], 1);
+
+    let (status, body) = post("/solve", json!({ "puzzle": ".".repeat(81) }));
+    assert_eq!(status, 200);
+    assert_eq!(body["solutions"], 2);
+}
+
+#[test]
+fn rejects_puzzles_with_no_solution() {
+    // Every cell has a candidate, but the first two cells of the top row can
+    // only hold a 9: 1-7 are in their row, and each has an 8 in its column.
+    let zeros = "0".repeat(9);
+    let puzzle = format!("001234567{zeros}800000000{zeros}{zeros}080000000{zeros}{zeros}{zeros}");
+    let (status, body) = post("/solve", json!({ "puzzle": puzzle }));
+    assert_eq!(status, 422);
+    assert_eq!(body["kind"], "NoSolution");
+}
+
+#[test]
+fn rejects_invalid_puzzles() {
+    let (status, body) = post("/solve", json!({ "puzzle": "12" }));
+    assert_eq!(status, 400);
+    assert_eq!(body["kind"], "InvalidFormat");
+
+    let (status, body) = post("/solve", json!({}));
+    assert_eq!(status, 400);
+    assert!(body["error"].is_string());
+
+    let (status, body) = request("POST", "/solve", "{");
+    assert_eq!(status, 400);
+    assert!(body["error"].as_str().unwrap().starts_with("Invalid JSON"));
+}
+
+#[test]
+fn solves_with_variants_and_constraints() {
+    let (status, body) = post(
+        "/solve",
+        json!({ "puzzle": ".".repeat(81), "variant": "diagonal", "constraints": "anti-king" }),
+    );
+    assert_eq!(status, 200);
+    let solution = body["solution"].as_str().unwrap();
+    let (_, check) = post(
+        "/validate",
+        json!({ "puzzle": solution, "variant": "diagonal", "constraints": "anti-king" }),
+    );
+    assert_eq!(check["valid"], true);
+
+    let (status, body) = post("/solve", json!({ "puzzle": PUZZLE, "variant": "sideways" }));
+    assert_eq!(status, 400);
+    assert_eq!(body["kind"], "InvalidFormat");
+}
+
+#[test]
+fn rates_a_puzzle() {
+    let (status, body) = post("/rate", json!({ "puzzle": SOLUTION }));
+    assert_eq!(status, 200);
+    assert_eq!(body["difficulty"], "easy");
+    assert_eq!(body["score"], 0);
+
+    let (status, body) = post("/rate", json!({ "puzzle": PUZZLE }));
+    assert_eq!(status, 200);
+    assert!(body["score"].as_u64().unwrap() > 0);
+}
+
+#[test]
+fn validates_a_puzzle() {
+    let (status, body) = post("/validate", json!({ "puzzle": PUZZLE }));
+    assert_eq!(status, 200);
+    assert_eq!(body["valid"], true);
+    assert_eq!(body["conflicts"], json!([]));
+
+    let puzzle = format!("44{}", ".".repeat(79));
+    let (status, body) = post("/validate", json!({ "puzzle": puzzle }));
+    assert_eq!(status, 200);
+    assert_eq!(body["valid"], false);
+    let kinds: Vec<&str> = body["conflicts"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c["kind"].as_str().unwrap())
+        .collect();
+    assert_eq!(kinds, ["DuplicateInRow", "DuplicateInBox"]);
+    assert_eq!(body["conflicts"][0]["cells"], json!([[1, 1], [1, 2]]));
+}
+
+#[test]
+fn generates_repeatable_puzzles() {
+    let target = "/generate?difficulty=easy&seed=7&symmetry=rotational";
+    let (status, first) = request("GET", target, "");
+    assert_eq!(status, 200);
+    let (_, second) = request("GET", target, "");
+    assert_eq!(first, second);
+
+    let puzzle = first["puzzle"].as_str().unwrap();
+    assert_eq!(first["clues"], puzzle.chars().filter(|&c| c != '.').count());
+    let (_, solved) = post("/solve", json!({ "puzzle": puzzle }));
+    assert!(solved["solution"].is_string());
+}
+
+#[test]
+fn generates_with_encoded_constraints() {
+    let (status, body) = request(
+        "GET",
+        "/generate?difficulty=easy&seed=3&constraints=anti-king%2Canti-knight",
+        "",
+    );
+    assert_eq!(status, 200);
+    let (_, check) = post(
+        "/validate",
+        json!({ "puzzle": body["puzzle"], "constraints": "anti-king,anti-knight" }),
+    );
+    assert_eq!(check["valid"], true);
+
+    let (status, _) = request("GET", "/generate?seed=lots", "");
+    assert_eq!(status, 400);
+    let (status, _) = request("GET", "/generate?difficulty=impossible", "");
+    assert_eq!(status, 400);
+}
+
+#[test]
+fn rejects_unknown_routes_and_methods() {
+    assert_eq!(request("GET", "/nowhere", "").0, 404);
+    assert_eq!(request("GET", "/solve", "").0, 405);
+    assert_eq!(request("POST", "/generate", "{}").0, 405);
+}
+
+#[test]
+fn rejects_oversized_headers() {
+    let mut stream = TcpStream::connect(server()).unwrap();
+    let long = "a".repeat(16 * 1024);
+    write!(stream, "GET /generate HTTP/1.1\r\nX-Long: {long}\r\n\r\n").unwrap();
+
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply).unwrap();
+    assert!(reply.starts_with("HTTP/1.1 431 "));
+}
+
+#[test]
+fn refuses_connections_while_every_worker_is_busy() {
+    let server = Server::bind("127.0.0.1:0").unwrap().with_workers(1);
+    let addr = server.local_addr().unwrap();
+    thread::spawn(move || server.run());
+    let body = json!({ "puzzle": PUZZLE }).to_string();
+
+    // A connection that hasn't sent its request yet holds the only worker.
+    let idle = TcpStream::connect(addr).unwrap();
+    let (status, reply) = request_to(addr, "POST", "/solve", &body);
+    assert_eq!(status, 503);
+    assert!(reply["error"].is_string());
+
+    // Once it's closed, the worker is free again.
+    drop(idle);
+    let start = Instant::now();
+    loop {
+        match request_to(addr, "POST", "/solve", &body) {
+            (200, reply) => {
+                assert_eq!(reply["solution"], SOLUTION);
+                break;
+            }
+            (status, _) => {
+                assert_eq!(status, 503);
+                assert!(start.elapsed() < Duration::from_secs(5));
+                thread::sleep(Duration::from_millis(50));
+            }
+        }
+    }
+}
@@ -0,0 +1,56 @@
+"""Tests of the Python bindings. Install them with `maturin develop`, then run
+`python -m pytest tests/test_python.py` or
+`python -m unittest discover -s tests -p "test_python.py"`."""
+
+import unittest
+
+import scanlan_sudoku
+
+PUZZLE = "530070000600195000098000060800060003400803001700020006060000280000419005000080079"
+SOLUTION = "534678912672195348198342567859761423426853791713924856961537284287419635345286179"
+
+
+class TestBindings(unittest.TestCase):
+    def test_parse(self):
+        g = scanlan_sudoku.parse(PUZZLE)
+        self.assertEqual(g.to_line(), PUZZLE.replace("0", "."))
+        self.assertEqual(g.rows()[0], [5, 3, 0, 0, 7, 0, 0, 0, 0])
+        self.assertEqual(g.empty_cells, PUZZLE.count("0"))
+        self.assertFalse(g.solved)
+        self.assertEqual(scanlan_sudoku.Grid(PUZZLE, "standard", "none").rows(), g.rows())
+
+    def test_solve(self):
+        solution = scanlan_sudoku.parse(PUZZLE).solve()
+        self.assertTrue(solution.solved)
+        self.assertEqual(solution.to_line(), SOLUTION)
+
+    def test_count_solutions(self):
+        self.assertEqual(scanlan_sudoku.parse(PUZZLE).count_solutions(), 1)
+        self.assertEqual(scanlan_sudoku.parse("0" * 81, "diagonal").count_solutions(3), 3)
+
+    def test_rate(self):
+        difficulty, score = scanlan_sudoku.parse(PUZZLE).rate()
+        self.assertEqual(difficulty, "easy")
+        self.assertIsInstance(score, int)
+
+    def test_generate(self):
+        first = scanlan_sudoku.generate("easy", seed=7, symmetry="rotational")
+        second = scanlan_sudoku.generate("easy", seed=7, symmetry="rotational")
+        self.assertEqual(first.to_line(), second.to_line())
+        self.assertEqual(first.count_solutions(), 1)
+
+    def test_errors(self):
+        with self.assertRaises(scanlan_sudoku.GridError) as raised:
+            scanlan_sudoku.parse("12")
+        self.assertEqual(raised.exception.args[1], "InvalidFormat")
+
+        with self.assertRaises(scanlan_sudoku.GridError):
+            scanlan_sudoku.parse("55" + PUZZLE[2:])
+        with self.assertRaises(scanlan_sudoku.GridError):
+            scanlan_sudoku.parse(PUZZLE, variant="octagonal")
+        with self.assertRaises(ValueError):
+            scanlan_sudoku.generate("impossible")
+
+
+if __name__ == "__main__":
+    unittest.main()